@@ -0,0 +1,269 @@
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+use crate::units::{Rounding, Units};
+use crate::weather::{self, Forecast, WeatherData};
+
+/// Thresholds that raise a weather alert, always in metric (°C, mm, m/s) so
+/// they mean the same whatever the display units. `None` turns a rule off;
+/// in `userconfig.toml` that is the value `"off"`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AlertRules {
+    /// Any hour colder than this.
+    pub frost_below: Option<f64>,
+    /// At least this much precipitation over the whole horizon.
+    pub rain_above: Option<f64>,
+    /// Any hour with gusts this strong.
+    pub gusts_above: Option<f64>,
+    /// Any hour with a thunder symbol (WMO 95–99 on Open-Meteo).
+    pub thunderstorms: bool,
+    /// How far ahead to look, from the current hour.
+    pub horizon_hours: u32,
+}
+
+impl Default for AlertRules {
+    fn default() -> Self {
+        Self {
+            frost_below: Some(0.0),
+            rain_above: Some(10.0),
+            gusts_above: Some(20.0),
+            thunderstorms: true,
+            horizon_hours: 24,
+        }
+    }
+}
+
+/// Longest `horizon_hours` offered; about as far as a forecast is sharp.
+pub const MAX_ALERT_HORIZON_HOURS: u32 = 48;
+
+/// A threshold as `userconfig.toml` holds it: `Some(Some(x))` for a number,
+/// `Some(None)` for `"off"`, `None` for anything else.
+pub fn parse_threshold(text: &str) -> Option<Option<f64>> {
+    let text = text.trim();
+    if text.eq_ignore_ascii_case("off") {
+        return Some(None);
+    }
+    text.parse::<f64>().ok().filter(|x| x.is_finite()).map(Some)
+}
+
+/// The other way round: a float, or the string `"off"`.
+pub fn threshold_to_toml(threshold: Option<f64>) -> toml_edit::Value {
+    match threshold {
+        Some(x) => x.into(),
+        None => "off".into(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlertKind {
+    Frost,
+    HeavyRain,
+    Gusts,
+    Thunderstorm,
+}
+
+/// One rule that fires within the horizon, from the first hour that
+/// triggers it until the end of the last.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    pub kind: AlertKind,
+    /// The forecast's location, for telling the alerts of several apart.
+    pub coordinates: [f32; 2],
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// The lowest temperature, the total precipitation or the strongest gust,
+    /// in metric; unused for thunderstorms.
+    pub value: f64,
+}
+
+impl Alert {
+    /// The local days the alert touches, for flagging events on them.
+    pub fn days(&self) -> Vec<NaiveDate> {
+        let last = (self.end - chrono::Duration::seconds(1)).date();
+        self.start.date().iter_days().take_while(|day| *day <= last).collect()
+    }
+
+    /// "Frost: down to -3°C, Tue 02:00–08:00", in `units` and `rounding`.
+    pub fn message(&self, units: Units, rounding: Rounding) -> String {
+        let end_format = if self.end.date() == self.start.date() { "%H:%M" } else { "%a %H:%M" };
+        let span = format!("{}–{}", self.start.format("%a %H:%M"), self.end.format(end_format));
+        let metric = Units::default();
+        match self.kind {
+            AlertKind::Frost => {
+                let low = units.temperature.convert(self.value, metric.temperature);
+                format!("Frost: down to {}{}, {span}", rounding.format(low), units.temperature.label())
+            }
+            AlertKind::HeavyRain => {
+                let total = units.precipitation.convert(self.value, metric.precipitation);
+                format!("Heavy rain: {} in total, {span}", units.precipitation.format(total))
+            }
+            AlertKind::Gusts => {
+                let gust = units.wind.convert(self.value, metric.wind);
+                format!("Wind gusts up to {:.0} {}, {span}", gust, units.wind.label())
+            }
+            AlertKind::Thunderstorm => format!("Thunderstorms, {span}"),
+        }
+    }
+}
+
+/// The hours from the one containing `now` up to `hours` later, each with
+/// the latest point at or before it. Stops where the forecast does.
+fn hours_ahead(points: &[WeatherData], now: NaiveDateTime, hours: u32) -> Vec<(NaiveDateTime, &WeatherData)> {
+    let Some(end) = weather::points_end(points) else {
+        return Vec::new();
+    };
+    let first = now.with_minute(0).and_then(|time| time.with_second(0)).unwrap_or(now);
+    (0..hours as i64)
+        .map(|offset| first + chrono::Duration::hours(offset))
+        .take_while(|hour| *hour < end)
+        .filter_map(|hour| {
+            let covering = points.partition_point(|point| point.time <= hour).checked_sub(1)?;
+            Some((hour, &points[covering]))
+        })
+        .collect()
+}
+
+/// The span from the first to the end of the last of `hours`.
+fn span(hours: &[NaiveDateTime]) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    Some((*hours.first()?, *hours.last()? + chrono::Duration::hours(1)))
+}
+
+/// Which `rules` the forecast triggers in the `horizon_hours` after `now`,
+/// at most one alert per kind.
+pub fn evaluate(forecast: &Forecast, rules: &AlertRules, now: NaiveDateTime) -> Vec<Alert> {
+    let metric = forecast.clone().in_units(Units::default());
+    let hours = hours_ahead(&metric.points, now, rules.horizon_hours);
+    let mut alerts = Vec::new();
+
+    let mut add = |kind: AlertKind, matching: Vec<(NaiveDateTime, f64)>, value: f64| {
+        let times: Vec<NaiveDateTime> = matching.iter().map(|(time, _)| *time).collect();
+        if let Some((start, end)) = span(&times) {
+            alerts.push(Alert { kind, coordinates: forecast.coordinates, start, end, value });
+        }
+    };
+
+    if let Some(threshold) = rules.frost_below {
+        let cold: Vec<(NaiveDateTime, f64)> = hours.iter().filter(|(_, point)| point.temp < threshold).map(|(time, point)| (*time, point.temp)).collect();
+        let low = cold.iter().map(|(_, temp)| *temp).fold(f64::MAX, f64::min);
+        add(AlertKind::Frost, cold, low);
+    }
+    if let Some(threshold) = rules.rain_above {
+        let wet: Vec<(NaiveDateTime, f64)> = hours
+            .iter()
+            .filter_map(|(time, point)| point.details.precipitation.filter(|amount| *amount > 0.0).map(|amount| (*time, amount)))
+            .collect();
+        let total = (wet.iter().map(|(_, amount)| amount).sum::<f64>() * 10.0).round() / 10.0;
+        if total >= threshold {
+            add(AlertKind::HeavyRain, wet, total);
+        }
+    }
+    if let Some(threshold) = rules.gusts_above {
+        let windy: Vec<(NaiveDateTime, f64)> = hours
+            .iter()
+            .filter_map(|(time, point)| point.details.wind_gusts.filter(|gust| *gust >= threshold).map(|gust| (*time, gust)))
+            .collect();
+        let strongest = windy.iter().map(|(_, gust)| *gust).fold(0.0, f64::max);
+        add(AlertKind::Gusts, windy, strongest);
+    }
+    if rules.thunderstorms {
+        let stormy: Vec<(NaiveDateTime, f64)> = hours.iter().filter(|(_, point)| weather::is_thunder(point.symbol)).map(|(time, _)| (*time, 0.0)).collect();
+        add(AlertKind::Thunderstorm, stormy, 0.0);
+    }
+    alerts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weather::{DayPhase, HourDetails, WeatherProviderKind};
+    use chrono::Local;
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 1, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    /// Two days of hourly points at 1 °C, dry, calm and cloudy.
+    fn forecast(units: Units, edit: impl Fn(NaiveDateTime, &mut WeatherData)) -> Forecast {
+        let points = (0..48)
+            .map(|hour| {
+                let time = at(10, 0) + chrono::Duration::hours(hour);
+                let mut point = WeatherData {
+                    time,
+                    temp: 1.0,
+                    symbol: 4,
+                    phase: DayPhase::Day,
+                    details: HourDetails { precipitation: Some(0.0), wind_gusts: Some(5.0), ..HourDetails::default() },
+                };
+                edit(time, &mut point);
+                point
+            })
+            .collect();
+        Forecast {
+            fetched_at: Local::now(),
+            coordinates: [59.91, 10.75],
+            provider: WeatherProviderKind::OpenMeteo,
+            units,
+            points,
+            daily: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn calm_weather_raises_nothing() {
+        assert_eq!(evaluate(&forecast(Units::default(), |_, _| {}), &AlertRules::default(), at(10, 6)), Vec::new());
+    }
+
+    #[test]
+    fn each_rule_spans_its_hours_within_the_horizon() {
+        let stormy = forecast(Units::default(), |time, point| {
+            if time >= at(10, 22) && time < at(11, 4) {
+                point.temp = -4.0 + (time - at(10, 22)).num_hours() as f64 * 0.5;
+            }
+            if time >= at(10, 12) && time < at(10, 15) {
+                point.details.precipitation = Some(4.0);
+                point.details.wind_gusts = Some(23.5);
+                point.symbol = 22;
+            }
+            // Past the 24-hour horizon: ignored.
+            if time >= at(11, 12) {
+                point.details.wind_gusts = Some(40.0);
+            }
+        });
+        let alerts = evaluate(&stormy, &AlertRules::default(), at(10, 9));
+        let kinds: Vec<AlertKind> = alerts.iter().map(|alert| alert.kind).collect();
+        assert_eq!(kinds, vec![AlertKind::Frost, AlertKind::HeavyRain, AlertKind::Gusts, AlertKind::Thunderstorm]);
+
+        let frost = alerts[0];
+        assert_eq!((frost.start, frost.end, frost.value), (at(10, 22), at(11, 4), -4.0));
+        assert_eq!(frost.days(), vec![at(10, 0).date(), at(11, 0).date()]);
+        assert_eq!((alerts[1].start, alerts[1].end, alerts[1].value), (at(10, 12), at(10, 15), 12.0));
+        assert_eq!(alerts[2].value, 23.5);
+        assert_eq!(alerts[3].days(), vec![at(10, 0).date()]);
+
+        // Rain below the threshold in total, and rules turned off, stay quiet.
+        let rules = AlertRules { frost_below: None, rain_above: Some(12.5), gusts_above: None, thunderstorms: false, horizon_hours: 24 };
+        assert_eq!(evaluate(&stormy, &rules, at(10, 9)), Vec::new());
+    }
+
+    #[test]
+    fn thresholds_parse_as_numbers_or_off() {
+        assert_eq!(parse_threshold(" -2.5 "), Some(Some(-2.5)));
+        assert_eq!(parse_threshold("OFF"), Some(None));
+        assert_eq!(parse_threshold("cold"), None);
+        assert_eq!(parse_threshold("NaN"), None);
+        assert_eq!(threshold_to_toml(None).as_str(), Some("off"));
+        assert_eq!(threshold_to_toml(Some(10.0)).as_float(), Some(10.0));
+    }
+
+    #[test]
+    fn thresholds_are_metric_whatever_the_forecast_units() {
+        use crate::units::TemperatureUnit;
+        let fahrenheit = Units { temperature: TemperatureUnit::Fahrenheit, ..Units::default() };
+        // 30 °F is below freezing, though not below "0".
+        let cold = forecast(fahrenheit, |_, point| point.temp = 30.0);
+        let alerts = evaluate(&cold, &AlertRules::default(), at(10, 0));
+        assert_eq!(alerts.len(), 1);
+        assert!((alerts[0].value - (-10.0 / 9.0)).abs() < 1e-9);
+        assert_eq!(alerts[0].message(fahrenheit, Rounding::Nearest), "Frost: down to 30°F, Fri 00:00–Sat 00:00");
+    }
+}
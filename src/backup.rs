@@ -0,0 +1,512 @@
+use std::{collections::{BTreeMap, HashMap, HashSet}, error::Error, fs::{self, File}, io::{Read, Write}, path::{Path, PathBuf}};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime, TimeZone};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+
+use crate::tasks::{self, Active};
+
+/// Directory (inside the data dir) holding the snapshot bundles.
+const BACKUP_DIR: &str = "backups";
+
+/// A scheduled snapshot is taken once the newest one is at least this old.
+const BACKUP_INTERVAL_HOURS: i64 = 24;
+
+/// Generations kept by `prune_backups`: the newest scheduled snapshot of each
+/// of the last N days, ISO weeks and months. A snapshot can count towards more
+/// than one generation.
+const KEEP_DAILY: usize = 7;
+const KEEP_WEEKLY: usize = 4;
+const KEEP_MONTHLY: usize = 12;
+/// Safety snapshots (before a migration or a restore) are kept by count, so a
+/// scheduled snapshot taken later the same day can't rotate them out.
+const KEEP_SAFETY: usize = 5;
+
+/// Top-level data files captured verbatim, by name inside the data dir.
+const DATA_FILES: [&str; 4] = ["read_at_startup.json", "colorschemes.json", "themes.json", "notepad_text.json"];
+/// The pre-segment archive. Only present until `tasks::migrate_legacy_archive`
+/// has run, which is exactly when a pre-migration snapshot needs it.
+const LEGACY_ARCHIVE_FILE: &str = "archived.jsonl";
+/// Bundle key for the config file, which lives outside the data dir lookup.
+const CONFIG_ENTRY: &str = "userconfig.toml";
+/// Bundle key prefix for archive segment logs (`archive/YYYY-MM.jsonl`).
+const ARCHIVE_PREFIX: &str = "archive/";
+
+const FILE_PREFIX: &str = "taskdeck-";
+const FILE_SUFFIX: &str = ".json.gz";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotKind {
+    Scheduled,
+    PreMigration,
+    PreRestore,
+}
+
+impl SnapshotKind {
+    /// File-name suffix; scheduled snapshots have none.
+    fn tag(self) -> &'static str {
+        match self {
+            SnapshotKind::Scheduled => "",
+            SnapshotKind::PreMigration => "-pre-migration",
+            SnapshotKind::PreRestore => "-pre-restore",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SnapshotKind::Scheduled => "Scheduled",
+            SnapshotKind::PreMigration => "Before migration",
+            SnapshotKind::PreRestore => "Before restore",
+        }
+    }
+}
+
+/// One snapshot: the text of every data file, keyed by its path relative to the
+/// data dir (archive segments as `archive/YYYY-MM.jsonl`, the config as
+/// `userconfig.toml`). Stored as gzipped JSON.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bundle {
+    pub created: DateTime<Local>,
+    pub kind: SnapshotKind,
+    pub files: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ItemCounts {
+    pub active: usize,
+    pub archived: usize,
+    pub colorschemes: usize,
+}
+
+/// A bundle on disk, summarised for the restore dialog.
+pub struct SnapshotInfo {
+    pub path: PathBuf,
+    pub created: DateTime<Local>,
+    pub kind: SnapshotKind,
+    pub size: u64,
+    pub counts: ItemCounts,
+}
+
+/// How a snapshot differs from the current data, from the point of view of
+/// restoring it.
+#[derive(Debug, Default, PartialEq)]
+pub struct BackupDiff {
+    /// Names of active items that restoring would bring back.
+    pub active_only_in_backup: Vec<String>,
+    /// Names of active items that restoring would drop.
+    pub active_only_now: Vec<String>,
+    /// Names of active items present in both but edited since.
+    pub active_changed: Vec<String>,
+    pub archived_in_backup: usize,
+    pub archived_now: usize,
+    /// Other files (schemes, notepad, config) whose contents differ.
+    pub files_changed: Vec<String>,
+}
+
+impl BackupDiff {
+    pub fn is_empty(&self) -> bool {
+        self.active_only_in_backup.is_empty()
+            && self.active_only_now.is_empty()
+            && self.active_changed.is_empty()
+            && self.archived_in_backup == self.archived_now
+            && self.files_changed.is_empty()
+    }
+}
+
+fn backup_dir(exe_path: &PathBuf) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = tasks::get_data_dir(exe_path)?.join(BACKUP_DIR);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn file_name(created: DateTime<Local>, kind: SnapshotKind) -> String {
+    format!("{FILE_PREFIX}{}{}{FILE_SUFFIX}", created.format(TIMESTAMP_FORMAT), kind.tag())
+}
+
+/// Recover the timestamp and kind from a bundle's file name, so listing and
+/// pruning don't have to decompress anything. Foreign files yield `None`.
+fn parse_file_name(name: &str) -> Option<(DateTime<Local>, SnapshotKind)> {
+    let stem = name.strip_prefix(FILE_PREFIX)?.strip_suffix(FILE_SUFFIX)?;
+    let (stamp, kind) = [SnapshotKind::PreMigration, SnapshotKind::PreRestore]
+        .into_iter()
+        .find_map(|kind| stem.strip_suffix(kind.tag()).map(|stamp| (stamp, kind)))
+        .unwrap_or((stem, SnapshotKind::Scheduled));
+    let naive = NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT).ok()?;
+    Some((Local.from_local_datetime(&naive).earliest()?, kind))
+}
+
+/// Read the current state into a bundle. Missing files are simply absent.
+fn capture(kind: SnapshotKind, config_path: &Path, exe_path: &PathBuf) -> Result<Bundle, Box<dyn Error>> {
+    let data_dir = tasks::get_data_dir(exe_path)?;
+    let mut files = BTreeMap::new();
+
+    for name in DATA_FILES.iter().chain([&LEGACY_ARCHIVE_FILE]) {
+        let path = data_dir.join(name);
+        if path.exists() {
+            files.insert(name.to_string(), fs::read_to_string(path)?);
+        }
+    }
+    for (key, contents) in tasks::read_archive_segments(exe_path)? {
+        files.insert(format!("{ARCHIVE_PREFIX}{key}.jsonl"), contents);
+    }
+    if config_path.exists() {
+        files.insert(CONFIG_ENTRY.to_string(), fs::read_to_string(config_path)?);
+    }
+
+    Ok(Bundle { created: Local::now(), kind, files })
+}
+
+pub fn read_bundle(path: &Path) -> Result<Bundle, Box<dyn Error>> {
+    let mut json = String::new();
+    GzDecoder::new(File::open(path)?).read_to_string(&mut json)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+/// Snapshot the data files into a new compressed bundle and return its path.
+pub fn take_snapshot(kind: SnapshotKind, config_path: &Path, exe_path: &PathBuf) -> Result<PathBuf, Box<dyn Error>> {
+    let bundle = capture(kind, config_path, exe_path)?;
+    let dir = backup_dir(exe_path)?;
+    let path = dir.join(file_name(bundle.created, kind));
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(serde_json::to_string(&bundle)?.as_bytes())?;
+    tasks::write_atomically(&dir, &path, &encoder.finish()?)?;
+
+    Ok(path)
+}
+
+/// The scheduled backup: takes a snapshot if the newest scheduled one is older
+/// than `BACKUP_INTERVAL_HOURS` (or there is none), then rotates old
+/// generations out. Returns the new snapshot's path if one was taken.
+pub fn run_scheduled_backup(config_path: &Path, exe_path: &PathBuf) -> Result<Option<PathBuf>, Box<dyn Error>> {
+    let now = Local::now();
+    let newest = snapshot_files(exe_path)?
+        .into_iter()
+        .filter(|(_, _, kind)| *kind == SnapshotKind::Scheduled)
+        .map(|(_, created, _)| created)
+        .max();
+    if newest.is_some_and(|newest| now - newest < Duration::hours(BACKUP_INTERVAL_HOURS)) {
+        return Ok(None);
+    }
+
+    let path = take_snapshot(SnapshotKind::Scheduled, config_path, exe_path)?;
+    prune_backups(exe_path)?;
+    Ok(Some(path))
+}
+
+/// A bundle found on disk: `(path, created, kind)`, read from its file name.
+type SnapshotFile = (PathBuf, DateTime<Local>, SnapshotKind);
+
+/// Bundles in the backup dir, newest first.
+fn snapshot_files(exe_path: &PathBuf) -> Result<Vec<SnapshotFile>, Box<dyn Error>> {
+    let mut found: Vec<_> = fs::read_dir(backup_dir(exe_path)?)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let (created, kind) = parse_file_name(entry.file_name().to_str()?)?;
+            Some((entry.path(), created, kind))
+        })
+        .collect();
+    found.sort_by_key(|(_, created, _)| std::cmp::Reverse(*created));
+    Ok(found)
+}
+
+/// Indices (into `snapshots`, newest first) of the snapshots that rotation
+/// keeps: the newest scheduled snapshot of each of the last `KEEP_DAILY` days,
+/// `KEEP_WEEKLY` ISO weeks and `KEEP_MONTHLY` months that have one, plus the
+/// newest `KEEP_SAFETY` safety snapshots.
+fn retained(snapshots: &[(DateTime<Local>, SnapshotKind)]) -> HashSet<usize> {
+    let mut keep = HashSet::new();
+
+    let scheduled: Vec<(usize, DateTime<Local>)> = snapshots
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, kind))| *kind == SnapshotKind::Scheduled)
+        .map(|(i, (created, _))| (i, *created))
+        .collect();
+
+    let mut keep_newest_per = |period: &dyn Fn(DateTime<Local>) -> (i32, u32, u32), limit: usize| {
+        let mut seen = HashSet::new();
+        for (i, created) in &scheduled {
+            if seen.len() == limit {
+                break;
+            }
+            if seen.insert(period(*created)) {
+                keep.insert(*i);
+            }
+        }
+    };
+    keep_newest_per(&|at| (at.year(), at.month(), at.day()), KEEP_DAILY);
+    keep_newest_per(&|at| (at.iso_week().year(), at.iso_week().week(), 0), KEEP_WEEKLY);
+    keep_newest_per(&|at| (at.year(), at.month(), 0), KEEP_MONTHLY);
+
+    keep.extend(
+        snapshots
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, kind))| *kind != SnapshotKind::Scheduled)
+            .map(|(i, _)| i)
+            .take(KEEP_SAFETY),
+    );
+    keep
+}
+
+/// Delete the snapshots that fell out of every generation. Returns how many.
+pub fn prune_backups(exe_path: &PathBuf) -> Result<usize, Box<dyn Error>> {
+    let files = snapshot_files(exe_path)?;
+    let keys: Vec<_> = files.iter().map(|(_, created, kind)| (*created, *kind)).collect();
+    let keep = retained(&keys);
+
+    let mut removed = 0;
+    for (i, (path, _, _)) in files.iter().enumerate() {
+        if !keep.contains(&i) {
+            fs::remove_file(path)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+fn active_items(bundle: &Bundle) -> Vec<Active> {
+    bundle
+        .files
+        .get("read_at_startup.json")
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default()
+}
+
+fn count_items(bundle: &Bundle) -> ItemCounts {
+    let archived = bundle
+        .files
+        .iter()
+        .filter(|(name, _)| name.starts_with(ARCHIVE_PREFIX) || name.as_str() == LEGACY_ARCHIVE_FILE)
+        .map(|(_, contents)| contents.lines().filter(|line| !line.trim().is_empty()).count())
+        .sum();
+    let colorschemes = bundle
+        .files
+        .get("colorschemes.json")
+        .and_then(|json| serde_json::from_str::<HashMap<String, serde_json::Value>>(json).ok())
+        .map_or(0, |schemes| schemes.len());
+
+    ItemCounts { active: active_items(bundle).len(), archived, colorschemes }
+}
+
+/// Every readable snapshot, newest first, with its item counts. Bundles that
+/// fail to decompress are left out rather than failing the whole listing.
+pub fn list_snapshots(exe_path: &PathBuf) -> Result<Vec<SnapshotInfo>, Box<dyn Error>> {
+    Ok(snapshot_files(exe_path)?
+        .into_iter()
+        .filter_map(|(path, created, kind)| {
+            let bundle = read_bundle(&path).ok()?;
+            let size = fs::metadata(&path).map_or(0, |meta| meta.len());
+            Some(SnapshotInfo { counts: count_items(&bundle), path, created, kind, size })
+        })
+        .collect())
+}
+
+fn diff_bundles(backup: &Bundle, current: &Bundle) -> BackupDiff {
+    let by_id = |items: Vec<Active>| -> BTreeMap<u64, (String, String)> {
+        items
+            .into_iter()
+            .map(|item| (item.id, (item.name.clone(), serde_json::to_string(&item).unwrap_or_default())))
+            .collect()
+    };
+    let (then, now) = (by_id(active_items(backup)), by_id(active_items(current)));
+
+    let mut diff = BackupDiff {
+        archived_in_backup: count_items(backup).archived,
+        archived_now: count_items(current).archived,
+        ..BackupDiff::default()
+    };
+    for (id, (name, json)) in &then {
+        match now.get(id) {
+            None => diff.active_only_in_backup.push(name.clone()),
+            Some((_, current_json)) if current_json != json => diff.active_changed.push(name.clone()),
+            Some(_) => {}
+        }
+    }
+    diff.active_only_now = now.iter().filter(|(id, _)| !then.contains_key(id)).map(|(_, (name, _))| name.clone()).collect();
+
+    for name in ["colorschemes.json", "themes.json", "notepad_text.json", CONFIG_ENTRY] {
+        if backup.files.get(name) != current.files.get(name) {
+            diff.files_changed.push(name.to_string());
+        }
+    }
+    diff
+}
+
+/// Compare a snapshot with the data as it is now.
+pub fn diff_with_current(path: &Path, config_path: &Path, exe_path: &PathBuf) -> Result<BackupDiff, Box<dyn Error>> {
+    let backup = read_bundle(path)?;
+    let current = capture(SnapshotKind::Scheduled, config_path, exe_path)?;
+    Ok(diff_bundles(&backup, &current))
+}
+
+/// Put a snapshot's files back in place. The current state is snapshotted
+/// first (`PreRestore`), so a restore can itself be undone. Files the bundle
+/// doesn't contain are left as they are, except the archive, which is replaced
+/// as a whole. The running app still holds the old state in memory; the caller
+/// is expected to restart.
+pub fn restore_snapshot(path: &Path, config_path: &Path, exe_path: &PathBuf) -> Result<(), Box<dyn Error>> {
+    let bundle = read_bundle(path)?;
+    take_snapshot(SnapshotKind::PreRestore, config_path, exe_path)?;
+
+    let data_dir = tasks::get_data_dir(exe_path)?;
+    for name in DATA_FILES.iter().chain([&LEGACY_ARCHIVE_FILE]) {
+        if let Some(contents) = bundle.files.get(*name) {
+            tasks::write_atomically(&data_dir, &data_dir.join(name), contents.as_bytes())?;
+        }
+    }
+
+    let segments: Vec<(String, String)> = bundle
+        .files
+        .iter()
+        .filter_map(|(name, contents)| {
+            let key = name.strip_prefix(ARCHIVE_PREFIX)?.strip_suffix(".jsonl")?;
+            Some((key.to_string(), contents.clone()))
+        })
+        .collect();
+    tasks::replace_archive(&segments, exe_path)?;
+
+    if let Some(contents) = bundle.files.get(CONFIG_ENTRY) {
+        let config_dir = config_path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        tasks::write_atomically(config_dir, config_path, contents.as_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tasks::fake_exe_with_data_dir;
+
+    fn write_active(names: &[(u64, &str)], exe: &PathBuf) {
+        let items: Vec<Active> = names
+            .iter()
+            .map(|(id, name)| Active {
+                id: *id,
+                importance: Some(1),
+                time_importance: None,
+                name: name.to_string(),
+                created: Local.with_ymd_and_hms(2025, 1, 1, 9, 0, 0).unwrap(),
+                deadline: None,
+                is_event: false,
+                outdoor: false,
+                location: None,
+            })
+            .collect();
+        tasks::oversafe_activesave(&items, exe).unwrap();
+    }
+
+    #[test]
+    fn file_names_round_trip() {
+        let at = Local.with_ymd_and_hms(2025, 6, 1, 8, 30, 5).unwrap();
+        for kind in [SnapshotKind::Scheduled, SnapshotKind::PreMigration, SnapshotKind::PreRestore] {
+            assert_eq!(parse_file_name(&file_name(at, kind)), Some((at, kind)));
+        }
+        assert_eq!(parse_file_name("notes.txt"), None);
+        assert_eq!(parse_file_name("taskdeck-garbage.json.gz"), None);
+    }
+
+    #[test]
+    fn retention_keeps_daily_weekly_and_monthly_generations() {
+        // Two scheduled snapshots a day for a year, newest first, plus a few
+        // safety snapshots mixed in.
+        let now = Local.with_ymd_and_hms(2025, 12, 31, 20, 0, 0).unwrap();
+        let mut snapshots = Vec::new();
+        for half_days in 0..730 {
+            snapshots.push((now - Duration::hours(12 * half_days), SnapshotKind::Scheduled));
+        }
+        for i in 0..8 {
+            snapshots.push((now - Duration::days(40 * i) - Duration::minutes(1), SnapshotKind::PreMigration));
+        }
+        snapshots.sort_by_key(|(created, _)| std::cmp::Reverse(*created));
+
+        let keep = retained(&snapshots);
+        let kept = |kind: SnapshotKind| keep.iter().filter(|i| snapshots[**i].1 == kind).count();
+
+        assert_eq!(kept(SnapshotKind::PreMigration), KEEP_SAFETY);
+        // 7 days, 4 weeks and 12 months overlap in their most recent entries, so
+        // fewer than 7 + 4 + 12 distinct snapshots survive — but at least 12.
+        let scheduled = kept(SnapshotKind::Scheduled);
+        assert!((KEEP_MONTHLY..=KEEP_DAILY + KEEP_WEEKLY + KEEP_MONTHLY).contains(&scheduled), "kept {scheduled}");
+
+        // The newest snapshot always survives, and only one per day is kept.
+        assert!(keep.contains(&0));
+        let days: Vec<_> = keep
+            .iter()
+            .filter(|i| snapshots[**i].1 == SnapshotKind::Scheduled)
+            .map(|i| snapshots[*i].0.date_naive())
+            .collect();
+        assert_eq!(days.iter().collect::<HashSet<_>>().len(), days.len());
+
+        // The oldest kept monthly generation is eleven months back.
+        let oldest = days.iter().min().unwrap();
+        assert_eq!((oldest.year(), oldest.month()), (2025, 1));
+    }
+
+    #[test]
+    fn snapshot_restore_and_diff_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let exe = fake_exe_with_data_dir(&tmp);
+        let config = tmp.path().join("userconfig.toml");
+        fs::write(&config, "calendar_weeks_to_show = 100\n").unwrap();
+
+        write_active(&[(1, "alpha"), (2, "bravo")], &exe);
+        crate::utilities::save_notepad_text("before".to_string(), &exe).unwrap();
+        let archived_at = Local.with_ymd_and_hms(2025, 3, 3, 12, 0, 0).unwrap();
+        let record = Active {
+            id: 9,
+            importance: None,
+            time_importance: Some(1),
+            name: "done".to_string(),
+            created: archived_at,
+            deadline: None,
+            is_event: false,
+            outdoor: false,
+            location: None,
+        }
+        .to_inactive(tasks::ArchiveReason::Completed);
+        tasks::save_inactive(&record, &exe).unwrap();
+
+        let snapshot = take_snapshot(SnapshotKind::Scheduled, &config, &exe).unwrap();
+        let listed = list_snapshots(&exe).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].counts, ItemCounts { active: 2, archived: 1, colorschemes: 0 });
+        assert!(diff_with_current(&snapshot, &config, &exe).unwrap().is_empty());
+
+        // Change things after the snapshot.
+        write_active(&[(2, "bravo (edited)"), (3, "charlie")], &exe);
+        crate::utilities::save_notepad_text("after".to_string(), &exe).unwrap();
+        fs::write(&config, "calendar_weeks_to_show = 10\n").unwrap();
+        tasks::replace_archive(&[], &exe).unwrap();
+
+        let diff = diff_with_current(&snapshot, &config, &exe).unwrap();
+        assert_eq!(diff.active_only_in_backup, vec!["alpha"]);
+        assert_eq!(diff.active_only_now, vec!["charlie"]);
+        assert_eq!(diff.active_changed, vec!["bravo"]);
+        assert_eq!((diff.archived_in_backup, diff.archived_now), (1, 0));
+        assert_eq!(diff.files_changed, vec!["notepad_text.json", "userconfig.toml"]);
+
+        restore_snapshot(&snapshot, &config, &exe).unwrap();
+        assert!(diff_with_current(&snapshot, &config, &exe).unwrap().is_empty(), "restore brings everything back");
+        assert_eq!(tasks::archive_count(&exe).unwrap(), 1, "archive indexes are rebuilt");
+        assert!(
+            snapshot_files(&exe).unwrap().iter().any(|(_, _, kind)| *kind == SnapshotKind::PreRestore),
+            "the replaced state is kept as a safety snapshot"
+        );
+    }
+
+    #[test]
+    fn scheduled_backup_waits_for_the_interval() {
+        let tmp = tempfile::tempdir().unwrap();
+        let exe = fake_exe_with_data_dir(&tmp);
+        let config = tmp.path().join("userconfig.toml");
+
+        assert!(run_scheduled_backup(&config, &exe).unwrap().is_some());
+        assert!(run_scheduled_backup(&config, &exe).unwrap().is_none(), "a fresh snapshot already exists");
+
+        // A safety snapshot doesn't count towards the schedule.
+        take_snapshot(SnapshotKind::PreMigration, &config, &exe).unwrap();
+        assert_eq!(snapshot_files(&exe).unwrap().len(), 2);
+    }
+}
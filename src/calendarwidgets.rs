@@ -3,6 +3,24 @@ use emath;
 use epaint::TextShape;
 use egui::{FontId, Ui, Pos2, FontFamily, vec2, Sense};
 
+use crate::theme;
+
+/// Draws the mark for a palette slot (see `Active::calendar_item_color`)
+/// with its right end at `anchor`, so items can be told apart without their
+/// colour: one to four dots for the importance, a ring for events, nothing
+/// for slot 0.
+fn paint_cue(painter: &egui::Painter, anchor: Pos2, slot: usize, color: Color32) {
+    let radius = 2.0;
+    let step = 6.0;
+    if slot == 5 {
+        painter.circle_stroke(anchor - vec2(3.0, 0.0), 3.0, Stroke::new(1.2, color));
+    } else {
+        for i in 0..slot.min(4) {
+            painter.circle_filled(anchor - vec2(radius + i as f32 * step, 0.0), radius, color);
+        }
+    }
+}
+
 pub struct DayNumber<'a> {
     pub number: &'a str,
     pub is_strong: bool,
@@ -30,7 +48,7 @@ impl<'a> egui::Widget for DayNumber<'a> {
         };
         let font_id = FontId {
             size: 16.0,
-            family: FontFamily::Name("anton".into()),
+            family: FontFamily::Name(theme::NUMBERS.into()),
         };
 
         // Layout number
@@ -52,11 +70,26 @@ pub struct DayHeader<'a> {
     pub is_strong: bool,
     pub hour: &'a str,
     pub color: Color32,
+    /// Width kept free at the right end of the first text line, for a
+    /// `DayWeather` drawn over it.
+    pub trailing_space: f32,
+    /// Palette slot to mark with `paint_cue`, if cues are on.
+    pub cue: Option<usize>,
 }
 
 impl<'a> DayHeader<'a> {
     pub fn new(number: &'a str, text: &'a str, is_strong: bool, hour: &'a str, color: Color32) -> Self {
-        Self { number, text, is_strong, hour, color }
+        Self { number, text, is_strong, hour, color, trailing_space: 0.0, cue: None }
+    }
+
+    pub fn trailing_space(mut self, width: f32) -> Self {
+        self.trailing_space = width;
+        self
+    }
+
+    pub fn cue(mut self, slot: Option<usize>) -> Self {
+        self.cue = slot;
+        self
     }
 }
 
@@ -67,7 +100,7 @@ impl<'a> egui::Widget for DayHeader<'a> {
 
         let painter = ui.painter_at(rect);
 
-        let text_font = FontId::new(11.0, FontFamily::Name("space".into()));
+        let text_font = FontId::new(11.0, FontFamily::Name(theme::DETAIL.into()));
 
         let margin = 5.0;
         let number_pos = Pos2::new(rect.left() + margin, rect.top() + margin);
@@ -80,7 +113,7 @@ impl<'a> egui::Widget for DayHeader<'a> {
                 ui.style().visuals.text_color()
             };
 
-            let font_id = FontId { size: 16.0, family: FontFamily::Name("anton".into()) };
+            let font_id = FontId { size: 16.0, family: FontFamily::Name(theme::NUMBERS.into()) };
             f.layout_no_wrap(self.number.to_string(), font_id, color)
         });
 
@@ -134,7 +167,7 @@ impl<'a> egui::Widget for DayHeader<'a> {
         painter.galley(number_pos, number_galley, Color32::WHITE);
 
         // Layout the text in two lines manually
-        let available_text_width = rect.right() - (number_pos.x + number_size.x + margin - 4.0);
+        let available_text_width = rect.right() - (number_pos.x + number_size.x + margin - 4.0) - self.trailing_space;
         let full_text = self.text;
 
         // Split text into two lines based on available width
@@ -171,7 +204,8 @@ impl<'a> egui::Widget for DayHeader<'a> {
             (line1, line2)
         };
 
-        let color = Color32::from_gray(150);
+        let theme = theme::current(ui.ctx());
+        let color = theme.item_text_color();
 
         let text_offset_x = 2.0; // Push text more to the right
         let text_offset_y = 7.5; // Push text a bit lower
@@ -199,7 +233,7 @@ impl<'a> egui::Widget for DayHeader<'a> {
         let hour_label = self.hour.to_string();
         let hour_font = FontId {
             size: 10.0,
-            family: FontFamily::Name("space".into()),
+            family: FontFamily::Name(theme::DETAIL.into()),
         };
         let hour_size = ui.fonts_mut(|f| f.layout_no_wrap(hour_label.clone(), hour_font.clone(), color).size());
         let hour_padding = 3.0;
@@ -214,8 +248,99 @@ impl<'a> egui::Widget for DayHeader<'a> {
 
         // 4. Draw outside the original bounds safely
         unclipped_painter.rect_filled(bg_rect, 6.0, Color32::from_black_alpha(40));
-        unclipped_painter.rect_stroke(bg_rect, 6.0, Stroke::new(0.1, Color32::from_white_alpha(120)), StrokeKind::Middle);
-        unclipped_painter.text(hourmark_pos, Align2::LEFT_TOP, hour_label, hour_font, Color32::from_white_alpha(150));
+        unclipped_painter.rect_stroke(bg_rect, 6.0, Stroke::new(0.1, theme.stroke_with_alpha(120)), StrokeKind::Middle);
+        unclipped_painter.text(hourmark_pos, Align2::LEFT_TOP, hour_label, hour_font, theme.stroke_with_alpha(150));
+
+
+        if let Some(slot) = self.cue {
+            paint_cue(&painter, Pos2::new(rect.right() - 8.0, rect.bottom() - 8.0), slot, theme.item_text_color());
+        }
+        response
+    }
+}
+
+
+/// The day's forecast at a glance: a small icon with the high and low. Sized
+/// to sit right of the day number, over a `DayHeader`'s first line (see
+/// `DayHeader::trailing_space`); wet days get a blue backing so they stand out.
+pub struct DayWeather<'a> {
+    pub icon: &'a egui::ImageSource<'static>,
+    pub high: &'a str,
+    pub low: &'a str,
+    pub wet: bool,
+}
+
+impl<'a> DayWeather<'a> {
+    pub const WIDTH: f32 = 64.0;
+    pub const HEIGHT: f32 = 18.0;
+
+    pub fn new(icon: &'a egui::ImageSource<'static>, high: &'a str, low: &'a str, wet: bool) -> Self {
+        Self { icon, high, low, wet }
+    }
+}
+
+impl<'a> egui::Widget for DayWeather<'a> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let theme = theme::current(ui.ctx());
+        let font_id = FontId::new(10.0, FontFamily::Name(theme::DETAIL.into()));
+        let high_galley = ui.fonts_mut(|f| f.layout_no_wrap(format!("{}°", self.high), font_id.clone(), theme.heading_text_color()));
+        let low_galley = ui.fonts_mut(|f| f.layout_no_wrap(format!("/{}°", self.low), font_id, theme.weak_text_color()));
+
+        let icon_size = 14.0;
+        let padding = 3.0;
+        let width = (padding * 3.0 + icon_size + high_galley.size().x + low_galley.size().x).min(Self::WIDTH);
+        let (rect, response) = ui.allocate_exact_size(vec2(width, Self::HEIGHT), Sense::hover());
+        let painter = ui.painter_at(rect);
+
+        // Backing, so the badge reads over an event's colour as well
+        let backing = if self.wet { Color32::from_rgba_unmultiplied(60, 110, 200, 110) } else { Color32::from_black_alpha(50) };
+        painter.rect_filled(rect, 6.0, backing);
+        painter.rect_stroke(rect, 6.0, Stroke::new(0.5, theme.stroke_color()), StrokeKind::Inside);
+
+        let icon_rect = Rect::from_center_size(Pos2::new(rect.left() + padding + icon_size / 2.0, rect.center().y), vec2(icon_size, icon_size));
+        egui::Image::new(self.icon.clone()).paint_at(ui, icon_rect);
+
+        let high_pos = Pos2::new(icon_rect.right() + padding, rect.center().y - high_galley.size().y / 2.0);
+        let low_pos = Pos2::new(high_pos.x + high_galley.size().x, rect.center().y - low_galley.size().y / 2.0);
+        painter.galley(high_pos, high_galley, Color32::WHITE);
+        painter.galley(low_pos, low_galley, Color32::WHITE);
+
+        response
+    }
+}
+
+
+/// A small warning triangle for a day with an outdoor event and a weather
+/// alert. Placed like `DayWeather`, left of it.
+pub struct AlertMark {
+    pub color: Color32,
+}
+
+impl AlertMark {
+    pub const SIZE: f32 = 16.0;
+
+    pub fn new(color: Color32) -> Self {
+        Self { color }
+    }
+}
+
+impl egui::Widget for AlertMark {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(vec2(Self::SIZE, Self::SIZE), Sense::hover());
+        let painter = ui.painter_at(rect.expand(1.0));
+
+        let triangle = vec![
+            Pos2::new(rect.center().x, rect.top() + 1.0),
+            Pos2::new(rect.right() - 1.0, rect.bottom() - 1.5),
+            Pos2::new(rect.left() + 1.0, rect.bottom() - 1.5),
+        ];
+        painter.add(Shape::convex_polygon(triangle, self.color, Stroke::new(1.0, Color32::from_black_alpha(120))));
+
+        // The exclamation mark, drawn rather than typed so it doesn't depend on the font
+        let x = rect.center().x;
+        let stroke = Stroke::new(1.6, Color32::from_black_alpha(220));
+        painter.line_segment([Pos2::new(x, rect.top() + 6.0), Pos2::new(x, rect.bottom() - 6.5)], stroke);
+        painter.circle_filled(Pos2::new(x, rect.bottom() - 4.0), 0.9, Color32::from_black_alpha(220));
 
         response
     }
@@ -226,11 +351,18 @@ pub struct MiddleHeader<'a> {
     pub text: &'a str,
     pub hour: Option<&'a str>,
     pub color: Color32,
+    /// Palette slot to mark with `paint_cue`, if cues are on.
+    pub cue: Option<usize>,
 }
 
 impl<'a> MiddleHeader<'a> {
     pub fn new(text: &'a str, hour: Option<&'a str>, color: Color32) -> Self {
-        Self { text, hour, color }
+        Self { text, hour, color, cue: None }
+    }
+
+    pub fn cue(mut self, slot: Option<usize>) -> Self {
+        self.cue = slot;
+        self
     }
 }
 
@@ -249,8 +381,9 @@ impl<'a> egui::Widget for MiddleHeader<'a> {
         let rounding = CornerRadius::same(6);
         painter.rect(rect, rounding, bg_color, stroke, StrokeKind::Inside);
 
-        let text_font = FontId::new(11.0, FontFamily::Name("space".into()));
-        let color = Color32::from_gray(150);
+        let text_font = FontId::new(11.0, FontFamily::Name(theme::DETAIL.into()));
+        let theme = theme::current(ui.ctx());
+        let color = theme.item_text_color();
 
         let margin = 12.0;
         let available_text_width = rect.width() - margin * 2.0;
@@ -305,7 +438,7 @@ impl<'a> egui::Widget for MiddleHeader<'a> {
             let hour_label = hour.to_string();
             let hour_font = FontId {
                 size: 10.0,
-                family: FontFamily::Name("space".into()),
+                family: FontFamily::Name(theme::DETAIL.into()),
             };
             let hour_size = ui.fonts_mut(|f| f.layout_no_wrap(hour_label.clone(), hour_font.clone(), color).size());
             let hour_padding = 3.0;
@@ -320,10 +453,14 @@ impl<'a> egui::Widget for MiddleHeader<'a> {
 
             // 4. Draw outside the original bounds safely
             unclipped_painter.rect_filled(bg_rect, 6.0, Color32::from_black_alpha(40));
-            unclipped_painter.rect_stroke(bg_rect, 6.0, Stroke::new(0.1, Color32::from_white_alpha(120)), StrokeKind::Middle);
-            unclipped_painter.text(hourmark_pos, Align2::LEFT_TOP, hour_label, hour_font, Color32::from_white_alpha(150));
+            unclipped_painter.rect_stroke(bg_rect, 6.0, Stroke::new(0.1, theme.stroke_with_alpha(120)), StrokeKind::Middle);
+            unclipped_painter.text(hourmark_pos, Align2::LEFT_TOP, hour_label, hour_font, theme.stroke_with_alpha(150));
         }        
 
+
+        if let Some(slot) = self.cue {
+            paint_cue(&painter, Pos2::new(rect.right() - 8.0, rect.bottom() - 8.0), slot, theme.item_text_color());
+        }
         response
     }
 }
@@ -357,7 +494,7 @@ impl<'a> Widget for RotatedNumberOnly<'a> {
             };
             let font_id = FontId {
                 size: 16.0,
-                family: FontFamily::Name("anton".into()),
+                family: FontFamily::Name(theme::NUMBERS.into()),
             };
             f.layout_no_wrap(self.number.to_string(), font_id, color)
         });
@@ -398,11 +535,18 @@ pub struct BottomHeaderRotated<'a> {
     pub hour: &'a str,
     pub top_hour: Option<&'a str>,
     pub color: Color32,
+    /// Palette slot to mark with `paint_cue`, if cues are on.
+    pub cue: Option<usize>,
 }
 
 impl<'a> BottomHeaderRotated<'a> {
     pub fn new(number: &'a str, text: &'a str, is_strong: bool, hour: &'a str, top_hour: Option<&'a str>, color: Color32) -> Self {
-        Self { number, text, is_strong, hour, top_hour, color }
+        Self { number, text, is_strong, hour, top_hour, color, cue: None }
+    }
+
+    pub fn cue(mut self, slot: Option<usize>) -> Self {
+        self.cue = slot;
+        self
     }
 }
 
@@ -413,7 +557,7 @@ impl<'a> egui::Widget for BottomHeaderRotated<'a> {
 
         let painter = ui.painter_at(rect);
 
-        let text_font = FontId::new(11.0, FontFamily::Name("space".into()));
+        let text_font = FontId::new(11.0, FontFamily::Name(theme::DETAIL.into()));
 
         // Margin and positioning
         let margin = 7.0;
@@ -427,7 +571,7 @@ impl<'a> egui::Widget for BottomHeaderRotated<'a> {
             };
 
             // let font_id = FontSelection::Default.resolve(ui.style());
-            let font_id = FontId { size: 16.0, family: FontFamily::Name("anton".into()) };
+            let font_id = FontId { size: 16.0, family: FontFamily::Name(theme::NUMBERS.into()) };
             f.layout_no_wrap(self.number.to_string(), font_id, color)
         });
 
@@ -532,7 +676,8 @@ impl<'a> egui::Widget for BottomHeaderRotated<'a> {
             (line1, line2)
         };
 
-        let color = Color32::from_gray(150);
+        let theme = theme::current(ui.ctx());
+        let color = theme.item_text_color();
 
         // Position text on top-left, with some margin
         let text_offset_x = margin + 7.0;
@@ -559,7 +704,7 @@ impl<'a> egui::Widget for BottomHeaderRotated<'a> {
         let hour_label = self.hour.to_string();
         let hour_font = FontId {
             size: 10.0,
-            family: FontFamily::Name("space".into()),
+            family: FontFamily::Name(theme::DETAIL.into()),
         };
         let hour_size = ui.fonts_mut(|f| f.layout_no_wrap(hour_label.clone(), hour_font.clone(), color).size());
         let hour_padding = 3.0;
@@ -574,8 +719,8 @@ impl<'a> egui::Widget for BottomHeaderRotated<'a> {
 
         // 4. Draw outside the original bounds safely
         unclipped_painter.rect_filled(bg_rect, 6.0, Color32::from_black_alpha(40));
-        unclipped_painter.rect_stroke(bg_rect, 6.0, Stroke::new(0.1, Color32::from_white_alpha(120)), StrokeKind::Middle);
-        unclipped_painter.text(hourmark_pos, Align2::LEFT_TOP, hour_label, hour_font, Color32::from_white_alpha(150));
+        unclipped_painter.rect_stroke(bg_rect, 6.0, Stroke::new(0.1, theme.stroke_with_alpha(120)), StrokeKind::Middle);
+        unclipped_painter.text(hourmark_pos, Align2::LEFT_TOP, hour_label, hour_font, theme.stroke_with_alpha(150));
 
 
         if let Some(hour) = self.top_hour {
@@ -587,7 +732,7 @@ impl<'a> egui::Widget for BottomHeaderRotated<'a> {
             let hour_label = hour.to_string();
             let hour_font = FontId {
                 size: 10.0,
-                family: FontFamily::Name("space".into()),
+                family: FontFamily::Name(theme::DETAIL.into()),
             };
             let hour_size = ui.fonts_mut(|f| f.layout_no_wrap(hour_label.clone(), hour_font.clone(), color).size());
             let hour_padding = 3.0;
@@ -600,11 +745,15 @@ impl<'a> egui::Widget for BottomHeaderRotated<'a> {
             let unclipped_painter = ui.painter().with_clip_rect(Rect::EVERYTHING);
 
             unclipped_painter.rect_filled(bg_rect, 6.0, Color32::from_black_alpha(40));
-            unclipped_painter.rect_stroke(bg_rect, 6.0, Stroke::new(0.1, Color32::from_white_alpha(120)), StrokeKind::Middle);
-            unclipped_painter.text(hourmark_pos, Align2::LEFT_TOP, hour_label, hour_font, Color32::from_white_alpha(150));
+            unclipped_painter.rect_stroke(bg_rect, 6.0, Stroke::new(0.1, theme.stroke_with_alpha(120)), StrokeKind::Middle);
+            unclipped_painter.text(hourmark_pos, Align2::LEFT_TOP, hour_label, hour_font, theme.stroke_with_alpha(150));
         }
 
 
+
+        if let Some(slot) = self.cue {
+            paint_cue(&painter, Pos2::new(rect.right() - 8.0, rect.top() + 8.0), slot, theme.item_text_color());
+        }
         response
     }
 }
@@ -617,6 +766,8 @@ pub struct ButtonHeaderRotated<'a> {
     pub hour: &'a str,
     pub top_hour: Option<&'a str>,
     pub color: Color32,
+    /// Palette slot to mark with `paint_cue`, if cues are on.
+    pub cue: Option<usize>,
 }
 
 impl<'a> ButtonHeaderRotated<'a> {
@@ -628,7 +779,12 @@ impl<'a> ButtonHeaderRotated<'a> {
         top_hour: Option<&'a str>,
         color: Color32,
     ) -> Self {
-        Self { number, text, is_strong, hour, top_hour, color }
+        Self { number, text, is_strong, hour, top_hour, color, cue: None }
+    }
+
+    pub fn cue(mut self, slot: Option<usize>) -> Self {
+        self.cue = slot;
+        self
     }
 }
 
@@ -639,7 +795,7 @@ impl<'a> egui::Widget for ButtonHeaderRotated<'a> {
 
         let painter = ui.painter_at(rect);
 
-        let text_font = FontId::new(11.0, FontFamily::Name("space".into()));
+        let text_font = FontId::new(11.0, FontFamily::Name(theme::DETAIL.into()));
 
         let margin = 7.0;
 
@@ -652,7 +808,7 @@ impl<'a> egui::Widget for ButtonHeaderRotated<'a> {
             };
 
             // let font_id = FontSelection::Default.resolve(ui.style());
-            let font_id = FontId { size: 16.0, family: FontFamily::Name("anton".into()) };
+            let font_id = FontId { size: 16.0, family: FontFamily::Name(theme::NUMBERS.into()) };
             f.layout_no_wrap(self.number.to_string(), font_id, color)
         });
 
@@ -759,7 +915,8 @@ impl<'a> egui::Widget for ButtonHeaderRotated<'a> {
             (line1, line2)
         };
 
-        let color = Color32::from_gray(150);
+        let theme = theme::current(ui.ctx());
+        let color = theme.item_text_color();
 
         // Position text on top-left, with some margin
         let text_offset_x = margin + 7.0;
@@ -784,8 +941,8 @@ impl<'a> egui::Widget for ButtonHeaderRotated<'a> {
 
             // Draw the rounded frame (border)
             let rounding = 4.0; // Radius for the corners
-            let stroke = Stroke::new(1.0, Color32::from_white_alpha(100)); // Border thickness and color
-            let fill = Color32::from_white_alpha(20); // Optional background fill (transparent)
+            let stroke = Stroke::new(1.0, theme.stroke_with_alpha(100)); // Border thickness and color
+            let fill = theme.stroke_with_alpha(20); // Optional background fill (transparent)
 
             painter.rect(
                 button_rect,
@@ -802,7 +959,7 @@ impl<'a> egui::Widget for ButtonHeaderRotated<'a> {
                 egui::Align2::CENTER_CENTER,
                 "…",
                 FontId { size: 25.0, family: FontFamily::Monospace },
-                Color32::from_white_alpha(180),
+                theme.stroke_with_alpha(180),
             );
         });
 
@@ -817,7 +974,7 @@ impl<'a> egui::Widget for ButtonHeaderRotated<'a> {
         let hour_label = self.hour.to_string();
         let hour_font = FontId {
             size: 10.0,
-            family: FontFamily::Name("space".into()),
+            family: FontFamily::Name(theme::DETAIL.into()),
         };
         let hour_size = ui.fonts_mut(|f| f.layout_no_wrap(hour_label.clone(), hour_font.clone(), color).size());
         let hour_padding = 3.0;
@@ -832,8 +989,8 @@ impl<'a> egui::Widget for ButtonHeaderRotated<'a> {
 
         // 4. Draw outside the original bounds safely
         unclipped_painter.rect_filled(bg_rect, 6.0, Color32::from_black_alpha(40));
-        unclipped_painter.rect_stroke(bg_rect, 6.0, Stroke::new(0.1, Color32::from_white_alpha(120)), StrokeKind::Middle);
-        unclipped_painter.text(hourmark_pos, Align2::LEFT_TOP, hour_label, hour_font, Color32::from_white_alpha(150));
+        unclipped_painter.rect_stroke(bg_rect, 6.0, Stroke::new(0.1, theme.stroke_with_alpha(120)), StrokeKind::Middle);
+        unclipped_painter.text(hourmark_pos, Align2::LEFT_TOP, hour_label, hour_font, theme.stroke_with_alpha(150));
 
 
         if let Some(hour) = self.top_hour {
@@ -845,7 +1002,7 @@ impl<'a> egui::Widget for ButtonHeaderRotated<'a> {
             let hour_label = hour.to_string();
             let hour_font = FontId {
                 size: 10.0,
-                family: FontFamily::Name("space".into()),
+                family: FontFamily::Name(theme::DETAIL.into()),
             };
             let hour_size = ui.fonts_mut(|f| f.layout_no_wrap(hour_label.clone(), hour_font.clone(), color).size());
             let hour_padding = 3.0;
@@ -858,11 +1015,15 @@ impl<'a> egui::Widget for ButtonHeaderRotated<'a> {
             let unclipped_painter = ui.painter().with_clip_rect(Rect::EVERYTHING);
 
             unclipped_painter.rect_filled(bg_rect, 6.0, Color32::from_black_alpha(40));
-            unclipped_painter.rect_stroke(bg_rect, 6.0, Stroke::new(0.1, Color32::from_white_alpha(120)), StrokeKind::Middle);
-            unclipped_painter.text(hourmark_pos, Align2::LEFT_TOP, hour_label, hour_font, Color32::from_white_alpha(150));
+            unclipped_painter.rect_stroke(bg_rect, 6.0, Stroke::new(0.1, theme.stroke_with_alpha(120)), StrokeKind::Middle);
+            unclipped_painter.text(hourmark_pos, Align2::LEFT_TOP, hour_label, hour_font, theme.stroke_with_alpha(150));
         }
 
 
+
+        if let Some(slot) = self.cue {
+            paint_cue(&painter, Pos2::new(rect.right() - 8.0, rect.top() + 8.0), slot, theme.item_text_color());
+        }
         response
     }
 }
\ No newline at end of file
@@ -0,0 +1,195 @@
+use std::{ffi::OsString, path::PathBuf};
+
+use crate::initialization::{CALENDAR_WEEKS_MAX, CALENDAR_WEEKS_MIN, Config};
+
+/// Printed for `--help`.
+pub const USAGE: &str = "\
+Usage: TaskDeck [options]
+
+  --data-dir <dir>      Keep data, config and images in <dir>
+  --config <file>       Read settings from <file> instead of userconfig.toml
+  --profile <name>      Lay profiles/<name>.toml over the settings
+  --fullscreen          Start in fullscreen
+  --windowed            Start in a window
+  --monitor <monitor>   Open on the monitor with this name, or this number (from 1)
+  --weeks <n>           Show <n> weeks in the calendar
+  --read-only           Don't write anything to the data directory or the config
+  -h, --help            Print this and exit
+
+The options apply to this launch only; none of them is saved.";
+
+/// What was asked for on the command line. The settings given here override
+/// the config files for this launch and are never written back to them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LaunchOptions {
+    pub data_dir: Option<PathBuf>,
+    pub config_file: Option<PathBuf>,
+    /// Name of the overlay in `profiles/` next to the config file.
+    pub profile: Option<String>,
+    pub fullscreen: Option<bool>,
+    /// A monitor name, or its number counting from 1.
+    pub monitor: Option<String>,
+    pub weeks: Option<usize>,
+    pub read_only: bool,
+    pub help: bool,
+}
+
+impl LaunchOptions {
+    /// Puts the overrides into `config`, as read from the files. Also applied
+    /// to a reloaded config, so an edit to the file doesn't undo them.
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(fullscreen) = self.fullscreen {
+            config.start_in_fullscreen = fullscreen;
+        }
+        if let Some(monitor) = &self.monitor {
+            config.selected_monitor_name = monitor.clone();
+        }
+        if let Some(weeks) = self.weeks {
+            config.calendar_weeks_to_show = weeks;
+        }
+    }
+}
+
+/// Reads the arguments after the program name. Values go either after the
+/// flag or after `=` (`--weeks 8`, `--weeks=8`). Anything that can't be used
+/// is skipped and described in the second value, for the error window; the
+/// app still starts.
+pub fn parse_args(args: impl IntoIterator<Item = OsString>) -> (LaunchOptions, Vec<String>) {
+    let mut options = LaunchOptions::default();
+    let mut problems = Vec::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let Some(text) = arg.to_str() else {
+            problems.push(format!("Argument \"{}\" is ignored", arg.to_string_lossy()));
+            continue;
+        };
+        let (flag, mut inline) = match text.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(OsString::from(value))),
+            _ => (text, None),
+        };
+        let mut value = |what: &str| {
+            let value = inline.take().or_else(|| args.next());
+            if value.is_none() {
+                problems.push(format!("{flag} needs {what}"));
+            }
+            value
+        };
+
+        match flag {
+            "--data-dir" => options.data_dir = value("a directory").map(PathBuf::from),
+            "--config" => options.config_file = value("a file").map(PathBuf::from),
+            "--profile" => {
+                if let Some(name) = value("a profile name") {
+                    let name = name.to_string_lossy().into_owned();
+                    if is_profile_name(&name) {
+                        options.profile = Some(name);
+                    } else {
+                        problems.push(format!("--profile \"{name}\" is ignored; profile names use only letters, digits, '-' and '_'"));
+                    }
+                }
+            }
+            "--monitor" => options.monitor = value("a monitor name or number").map(|name| name.to_string_lossy().into_owned()),
+            "--weeks" => {
+                if let Some(weeks) = value("a number of weeks") {
+                    let weeks = weeks.to_string_lossy();
+                    match weeks.parse::<usize>() {
+                        Ok(n) if (CALENDAR_WEEKS_MIN..=CALENDAR_WEEKS_MAX).contains(&n) => options.weeks = Some(n),
+                        _ => problems.push(format!("--weeks {weeks} is ignored; it takes a number from {CALENDAR_WEEKS_MIN} to {CALENDAR_WEEKS_MAX}")),
+                    }
+                }
+            }
+            "--fullscreen" | "--windowed" | "--read-only" | "-h" | "--help" if inline.is_some() => {
+                problems.push(format!("{flag} takes no value; \"{text}\" is ignored"));
+            }
+            "--fullscreen" => options.fullscreen = Some(true),
+            "--windowed" => options.fullscreen = Some(false),
+            "--read-only" => options.read_only = true,
+            "-h" | "--help" => options.help = true,
+            _ => problems.push(format!("Unknown argument \"{text}\" is ignored")),
+        }
+    }
+    (options, problems)
+}
+
+/// Profile names become file names, so nothing that could leave `profiles/`.
+fn is_profile_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Release Windows builds have no console of their own (`windows_subsystem`),
+/// so `--help` and argument problems would print nowhere. This attaches to the
+/// console of the shell that started us, if any; elsewhere it does nothing.
+pub fn attach_parent_console() {
+    #[cfg(windows)]
+    {
+        const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+        #[link(name = "kernel32")]
+        unsafe extern "system" {
+            fn AttachConsole(process_id: u32) -> i32;
+        }
+        // SAFETY: takes no pointers. It fails harmlessly when there is no
+        // parent console (started from Explorer) or one is already attached.
+        unsafe {
+            AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(list: &[&str]) -> (LaunchOptions, Vec<String>) {
+        parse_args(list.iter().map(OsString::from))
+    }
+
+    #[test]
+    fn values_follow_the_flag_or_an_equals_sign() {
+        let (options, problems) = parse(&["--data-dir=/tmp/td", "--config", "/tmp/wall.toml", "--profile", "wall", "--fullscreen", "--monitor=3", "--weeks", "8", "--read-only"]);
+        assert!(problems.is_empty(), "{problems:?}");
+        assert_eq!(
+            options,
+            LaunchOptions {
+                data_dir: Some(PathBuf::from("/tmp/td")),
+                config_file: Some(PathBuf::from("/tmp/wall.toml")),
+                profile: Some("wall".to_string()),
+                fullscreen: Some(true),
+                monitor: Some("3".to_string()),
+                weeks: Some(8),
+                read_only: true,
+                help: false,
+            }
+        );
+
+        // The last of --fullscreen/--windowed wins.
+        assert_eq!(parse(&["--fullscreen", "--windowed"]).0.fullscreen, Some(false));
+        assert_eq!(parse(&[]), (LaunchOptions::default(), Vec::new()));
+    }
+
+    #[test]
+    fn unusable_arguments_are_reported_and_skipped() {
+        let (options, problems) = parse(&["--weeks", "9999", "--profile", "../secrets", "--frobnicate", "--read-only=yes", "--data-dir"]);
+        assert_eq!(options, LaunchOptions::default());
+        assert_eq!(
+            problems,
+            [
+                "--weeks 9999 is ignored; it takes a number from 6 to 520",
+                "--profile \"../secrets\" is ignored; profile names use only letters, digits, '-' and '_'",
+                "Unknown argument \"--frobnicate\" is ignored",
+                "--read-only takes no value; \"--read-only=yes\" is ignored",
+                "--data-dir needs a directory",
+            ]
+        );
+    }
+
+    #[test]
+    fn overrides_replace_only_what_was_given() {
+        let mut config = crate::initialization::check_config("start_in_fullscreen = true\ncalendar_weeks_to_show = 12\nselected_monitor_name = \"DP-1\"\n").unwrap().0;
+        let options = LaunchOptions { fullscreen: Some(false), weeks: Some(8), ..Default::default() };
+        options.apply(&mut config);
+        assert!(!config.start_in_fullscreen);
+        assert_eq!(config.calendar_weeks_to_show, 8);
+        assert_eq!(config.selected_monitor_name, "DP-1");
+    }
+}
@@ -1,12 +1,13 @@
 use palette::{Srgb};
-use std::{collections::HashMap, error::Error, fs::{self, File}, io::{BufReader, BufWriter, Write}, path::PathBuf};
+use std::{collections::HashMap, error::Error, fs::{self, File}, io::{BufReader, BufWriter, Write}, path::{Path, PathBuf}};
 use serde::{Deserialize, Serialize};
 use tempfile::NamedTempFile;
-use image::{GenericImageView, Pixel};
-use kmeans_colors::{get_kmeans_hamerly};
-use palette::{FromColor, Lab};
+use image::DynamicImage;
+use palette::{FromColor, Lab, LinSrgb};
 
-#[derive(Serialize, Deserialize, Clone)]
+use crate::quantize::{self, PaletteAlgorithm, PaletteOptions};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ColorScheme {
     pub name: String,
     pub colors: [[u8; 4]; 6],
@@ -72,9 +73,9 @@ pub fn read_colorschemes(exe_path: &PathBuf) -> Result<HashMap<u32, ColorScheme>
     
     let file_path = dir_path.join("colorschemes.json");
     
+    // As with `tasks::read_at_startup`, a missing file is left for the first save.
     if !file_path.exists() {
-        let mut file = File::create(&file_path).expect("failed to create colorschemes JSON file");
-        file.write_all(b"{}").expect("failed to write to colorschemes JSON file");
+        return Ok(HashMap::new());
     }
 
     let file = File::open(&file_path)?;
@@ -85,73 +86,48 @@ pub fn read_colorschemes(exe_path: &PathBuf) -> Result<HashMap<u32, ColorScheme>
     return Ok(schemes);
 }
 
-pub fn generate_colorscheme(name: String) -> Option<ColorScheme> {
-    // Confine the lookup to `images/` (defends against path traversal).
-    let path = crate::utilities::safe_image_path(&name)?;
-
+/// Loads a background from the images directory. The lookup is confined to
+/// `images_dir` (defends against path traversal).
+pub fn load_background_image(images_dir: &Path, name: &str) -> Option<DynamicImage> {
+    let path = crate::utilities::safe_image_path(images_dir, name)?;
     let image_bytes = fs::read(&path).ok()?;
-    let image = image::load_from_memory(&image_bytes).ok()?;
-
-    // --- 1. Resize to suppress noise ---
-    let image = image.resize(200, 200, image::imageops::FilterType::Triangle);
-
-    // --- 2. Collect Lab pixels ---
-    let mut pixels = Vec::new();
-
-    for (_, _, pixel) in image.pixels() {
-        let rgba = pixel.to_rgba();
-        let alpha = rgba[3];
-
-        // Ignore transparent pixels
-        if alpha < 200 {
-            continue;
-        }
+    image::load_from_memory(&image_bytes).ok()
+}
 
-        let srgb = Srgb::new(
-            rgba[0] as f32 / 255.0,
-            rgba[1] as f32 / 255.0,
-            rgba[2] as f32 / 255.0,
-        );
+/// Makes a scheme from the colours of a background, picked by
+/// `options.algorithm`. Each fill is then moved in lightness until the text
+/// drawn on it meets `goal` over the part of the image the calendar covers
+/// (see [`adjust_for_contrast`]).
+pub fn generate_colorscheme(images_dir: &Path, name: String, backdrop: &Backdrop, goal: &ContrastGoal, options: &PaletteOptions) -> Option<ColorScheme> {
+    let image = load_background_image(images_dir, &name)?;
+    scheme_from_image(&image, &name, &backdrop.sample(Some(&image)), goal, options)
+}
 
-        pixels.push(Lab::from_color(srgb));
-    }
+/// What every algorithm makes of the background with the same options, for
+/// comparing them before one is kept. Algorithms that find nothing are left out.
+pub fn preview_colorschemes(images_dir: &Path, name: &str, backdrop: &Backdrop, goal: &ContrastGoal, options: &PaletteOptions) -> Vec<(PaletteAlgorithm, ColorScheme)> {
+    let Some(image) = load_background_image(images_dir, name) else { return Vec::new() };
+    let samples = backdrop.sample(Some(&image));
+    PaletteAlgorithm::ALL
+        .into_iter()
+        .filter_map(|algorithm| {
+            let scheme = scheme_from_image(&image, name, &samples, goal, &PaletteOptions { algorithm, ..*options })?;
+            Some((algorithm, scheme))
+        })
+        .collect()
+}
 
-    if pixels.len() < 500 {
+fn scheme_from_image(image: &DynamicImage, name: &str, backdrop: &[[u8; 3]], goal: &ContrastGoal, options: &PaletteOptions) -> Option<ColorScheme> {
+    let Some(mut clusters) = quantize::extract_palette(image, options) else {
         #[cfg(debug_assertions)]
-        eprintln!("Not enough usable pixels in {:?}", path);
+        eprintln!("Not enough usable pixels in {:?}", name);
         return None;
-    }
-
-    // --- 3. K-means in Lab space ---
-    let kmeans = get_kmeans_hamerly(
-        6,      // number of clusters
-        20,     // max iterations
-        0.002,  // convergence threshold
-        false,  // no verbose output
-        &pixels,
-        42,     // deterministic seed
-    );
+    };
 
-    // --- 4. Compute cluster populations ---
-    let mut counts = vec![0usize; kmeans.centroids.len()];
-    for &cluster_idx in &kmeans.indices {
-        counts[cluster_idx as usize] += 1;
-    }
-
-    let mut clusters: Vec<(Lab, usize)> = kmeans
-        .centroids
-        .into_iter()
-        .zip(counts)
-        .collect();
-
-    // --- 5. Sort by UI visual significance (least → most) ---
-    clusters.sort_by(|(a_lab, a_count), (b_lab, b_count)| {
-        let a_score = cluster_score(*a_lab, *a_count);
-        let b_score = cluster_score(*b_lab, *b_count);
-        a_score.partial_cmp(&b_score).unwrap()
-    });
+    // Sort by UI visual significance (least → most)
+    clusters.sort_by(|(a_lab, a_weight), (b_lab, b_weight)| cluster_score(*a_lab, *a_weight).total_cmp(&cluster_score(*b_lab, *b_weight)));
 
-    // --- 6. Convert to RGBA fills ---
+    // Convert to RGBA fills
     let colors: [[u8; 4]; 6] = clusters
         .iter()
         .map(|(lab, _)| {
@@ -162,22 +138,22 @@ pub fn generate_colorscheme(name: String) -> Option<ColorScheme> {
             let b = (srgb.blue.clamp(0.0, 1.0) * 255.0) as u8;
 
             // Tuned for background UI overlays
-            [r, g, b, 80]
+            adjust_for_contrast([r, g, b, 80], backdrop, goal)
         })
         .collect::<Vec<_>>()
         .try_into()
         .ok()?;
 
-    Some(ColorScheme {
-        colors,
-        name: format!("Scheme from \"{}\"", name),
-        is_user_configurable: true,
-    })
+    // k-means keeps the name schemes always had.
+    let name = match options.algorithm {
+        PaletteAlgorithm::KMeans => format!("Scheme from \"{}\"", name),
+        algorithm => format!("Scheme from \"{}\" ({})", name, algorithm.label()),
+    };
+    Some(ColorScheme { colors, name, is_user_configurable: true })
 }
 
 /// Higher score = more visually prominent
-fn cluster_score(lab: Lab, population: usize) -> f32 {
-    let pop = population as f32;
+fn cluster_score(lab: Lab, pop: f32) -> f32 {
 
     let saturation = (lab.a * lab.a + lab.b * lab.b).sqrt();
     let luminance = lab.l;
@@ -186,4 +162,715 @@ fn cluster_score(lab: Lab, population: usize) -> f32 {
     pop * 0.6
         + saturation * 0.2
         + (luminance - 50.0).abs() * 0.2
-}
\ No newline at end of file
+}
+/// WCAG 2 minimum contrast for normal text (level AA).
+pub const WCAG_AA: f32 = 4.5;
+/// WCAG 2 minimum contrast for large text (level AA).
+pub const WCAG_AA_LARGE: f32 = 3.0;
+
+/// Share of the sampled backdrop a colour pair has to reach its ratio on, so
+/// a few bright specks in the image don't fail a whole scheme.
+const CONTRAST_COVERAGE: f32 = 0.9;
+/// Samples taken across the calendar area, per side.
+const BACKDROP_GRID: u32 = 16;
+
+/// What lies under the calendar's fills: part of the background image,
+/// drawn at `tint` over the panel colour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backdrop {
+    /// The calendar area as fractions of the window: left, top, right, bottom.
+    pub region: [f32; 4],
+    /// The background tint, 0.0–1.0.
+    pub tint: f32,
+    pub panel: [u8; 3],
+}
+
+impl Backdrop {
+    /// Colours of the calendar area as they end up on screen, on a grid.
+    /// Without an image that's just the panel.
+    pub fn sample(&self, image: Option<&DynamicImage>) -> Vec<[u8; 3]> {
+        let Some(image) = image else { return vec![self.panel] };
+        // Shrinking first makes each sample an average of its cell rather
+        // than a single pixel. The image is stretched over the window anyway.
+        let small = image.resize_exact(BACKDROP_GRID * 8, BACKDROP_GRID * 8, image::imageops::FilterType::Triangle).to_rgba8();
+        let [left, top, right, bottom] = self.region.map(|f| f.clamp(0.0, 1.0));
+        let at = |from: f32, to: f32, i: u32| {
+            let f = from + (to - from) * (i as f32 + 0.5) / BACKDROP_GRID as f32;
+            ((f * small.width() as f32) as u32).min(small.width() - 1)
+        };
+
+        let mut samples = Vec::with_capacity((BACKDROP_GRID * BACKDROP_GRID) as usize);
+        for j in 0..BACKDROP_GRID {
+            for i in 0..BACKDROP_GRID {
+                let [r, g, b, a] = small.get_pixel(at(left, right, i), at(top, bottom, j)).0;
+                let alpha = (a as f32 / 255.0 * self.tint * 255.0).round() as u8;
+                samples.push(over([r, g, b, alpha], self.panel));
+            }
+        }
+        samples
+    }
+}
+
+/// The text colours that must stay readable on every fill, and how well.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContrastGoal {
+    /// What each colour is used for, for the editor's tooltips.
+    pub texts: Vec<(&'static str, [u8; 3])>,
+    pub ratio: f32,
+}
+
+/// WCAG relative luminance of an sRGB colour.
+pub fn relative_luminance([r, g, b]: [u8; 3]) -> f32 {
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b)
+}
+
+/// WCAG contrast ratio between two colours, from 1.0 to 21.0.
+pub fn contrast_ratio(a: [u8; 3], b: [u8; 3]) -> f32 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// `fill` blended over `under` the way egui does it, in gamma space.
+fn over([r, g, b, a]: [u8; 4], under: [u8; 3]) -> [u8; 3] {
+    let a = a as f32 / 255.0;
+    let mix = |top: u8, bottom: u8| (top as f32 * a + bottom as f32 * (1.0 - a)).round() as u8;
+    [mix(r, under[0]), mix(g, under[1]), mix(b, under[2])]
+}
+
+/// The contrast `text` has on `fill` over the sampled backdrop: the ratio it
+/// reaches on `CONTRAST_COVERAGE` of the samples.
+pub fn fill_contrast(fill: [u8; 4], text: [u8; 3], backdrop: &[[u8; 3]]) -> f32 {
+    let mut ratios: Vec<f32> = backdrop.iter().map(|under| contrast_ratio(text, over(fill, *under))).collect();
+    ratios.sort_by(f32::total_cmp);
+    let index = ((1.0 - CONTRAST_COVERAGE) * ratios.len() as f32) as usize;
+    ratios.get(index).copied().unwrap_or(1.0)
+}
+
+/// The contrast of the least readable text in `goal` on `fill`.
+pub fn worst_contrast(fill: [u8; 4], backdrop: &[[u8; 3]], goal: &ContrastGoal) -> f32 {
+    goal.texts.iter().map(|(_, text)| fill_contrast(fill, *text, backdrop)).fold(f32::INFINITY, f32::min)
+}
+
+/// `fill` with its Lab lightness moved as little as possible for every text
+/// in `goal` to reach the ratio; hue, chroma and alpha stay. If no lightness
+/// does, the most readable one is used.
+pub fn adjust_for_contrast(fill: [u8; 4], backdrop: &[[u8; 3]], goal: &ContrastGoal) -> [u8; 4] {
+    let mut best = (worst_contrast(fill, backdrop, goal), fill);
+    if best.0 >= goal.ratio {
+        return fill;
+    }
+
+    let [r, g, b, alpha] = fill;
+    let lab = Lab::from_color(Srgb::new(r, g, b).into_format::<f32>());
+    for step in 1..=100 {
+        for lightness in [lab.l - step as f32, lab.l + step as f32] {
+            if !(0.0..=100.0).contains(&lightness) {
+                continue;
+            }
+            let [r, g, b]: [u8; 3] = Srgb::from_color(Lab::new(lightness, lab.a, lab.b)).into_format::<u8>().into();
+            let candidate = [r, g, b, alpha];
+            let ratio = worst_contrast(candidate, backdrop, goal);
+            if ratio >= goal.ratio {
+                return candidate;
+            }
+            if ratio > best.0 {
+                best = (ratio, candidate);
+            }
+        }
+    }
+    best.1
+}
+
+/// What each of a scheme's six colours is used for (see `Active::calendar_item_color`).
+pub const SLOT_NAMES: [&str; 6] = ["No importance", "Importance 1", "Importance 2", "Importance 3", "Importance 4", "Events"];
+
+/// Below this ΔE (CIE76) two fills, as drawn, read as the same colour.
+pub const CONFUSABLE_DELTA_E: f32 = 6.0;
+
+/// The Okabe–Ito palette without its black: colours picked to stay apart
+/// with each kind of colour-vision deficiency.
+const COLOR_BLIND_SAFE: [[u8; 3]; 7] = [
+    [230, 159, 0],   // orange
+    [86, 180, 233],  // sky blue
+    [0, 158, 115],   // bluish green
+    [240, 228, 66],  // yellow
+    [0, 114, 178],   // blue
+    [213, 94, 0],    // vermillion
+    [204, 121, 167], // reddish purple
+];
+
+/// Kinds of colour-vision deficiency the scheme editor can simulate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorVision {
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+impl ColorVision {
+    pub const ALL: [ColorVision; 3] = [ColorVision::Deuteranopia, ColorVision::Protanopia, ColorVision::Tritanopia];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ColorVision::Deuteranopia => "Deuteranopia (no green cones)",
+            ColorVision::Protanopia => "Protanopia (no red cones)",
+            ColorVision::Tritanopia => "Tritanopia (no blue cones)",
+        }
+    }
+
+    /// Machado, Oliveira & Fernandes (2009) at full severity, for linear RGB.
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            ColorVision::Deuteranopia => [[0.367322, 0.860646, -0.227968], [0.280085, 0.672501, 0.047413], [-0.011820, 0.042940, 0.968881]],
+            ColorVision::Protanopia => [[0.152286, 1.052583, -0.204868], [0.114503, 0.786281, 0.099216], [-0.003882, -0.048116, 1.051998]],
+            ColorVision::Tritanopia => [[1.255528, -0.076749, -0.178779], [-0.078411, 0.930809, 0.147602], [0.004733, 0.691367, 0.303900]],
+        }
+    }
+
+    /// How `rgb` looks with this deficiency.
+    pub fn simulate(self, rgb: [u8; 3]) -> [u8; 3] {
+        let linear: LinSrgb = Srgb::from(rgb).into_format::<f32>().into_linear();
+        let linear = [linear.red, linear.green, linear.blue];
+        let seen = self.matrix().map(|row| row.iter().zip(linear).map(|(m, c)| m * c).sum::<f32>().clamp(0.0, 1.0));
+        let seen: Srgb<u8> = Srgb::from_linear(LinSrgb::new(seen[0], seen[1], seen[2]));
+        seen.into()
+    }
+}
+
+fn lab_of(rgb: [u8; 3]) -> Lab {
+    Lab::from_color(Srgb::from(rgb).into_format::<f32>())
+}
+
+/// Pairs of slots whose fills, drawn over `under`, look alike with `vision`
+/// (or with normal vision for `None`), with how far apart they are.
+pub fn confusable_slots(colors: &[[u8; 4]; 6], vision: Option<ColorVision>, under: [u8; 3]) -> Vec<(usize, usize, f32)> {
+    let seen = colors.map(|fill| {
+        let drawn = over(fill, under);
+        lab_of(vision.map_or(drawn, |vision| vision.simulate(drawn)))
+    });
+    let mut pairs = Vec::new();
+    for a in 0..seen.len() {
+        for b in a + 1..seen.len() {
+            let distance = quantize::delta_e(seen[a], seen[b]);
+            if distance < CONFUSABLE_DELTA_E {
+                pairs.push((a, b, distance));
+            }
+        }
+    }
+    pairs
+}
+
+/// The fill `vision` sees in place of `fill` drawn over `under`, opaque.
+pub fn simulate_fill(fill: [u8; 4], vision: ColorVision, under: [u8; 3]) -> [u8; 3] {
+    vision.simulate(over(fill, under))
+}
+
+/// `colors` moved onto the Okabe–Ito palette, which stays distinguishable
+/// with each deficiency. Each slot gets the palette colour nearest its own,
+/// as a whole: the assignment with the least total ΔE. Alpha is kept, except
+/// that clear slots get `IMPORTED_ALPHA` so they show at all.
+pub fn color_blind_safe(colors: [[u8; 4]; 6]) -> [[u8; 4]; 6] {
+    let wanted = colors.map(|[r, g, b, _]| lab_of([r, g, b]));
+    let safe = COLOR_BLIND_SAFE.map(lab_of);
+
+    // 7 × 6 × 5 × 4 × 3 × 2 orders: few enough to try them all.
+    fn search(slot: usize, used: &mut [bool; 7], cost: f32, chosen: &mut [usize; 6], best: &mut (f32, [usize; 6]), wanted: &[Lab; 6], safe: &[Lab; 7]) {
+        if cost >= best.0 {
+            return;
+        }
+        if slot == chosen.len() {
+            *best = (cost, *chosen);
+            return;
+        }
+        for candidate in 0..safe.len() {
+            if !used[candidate] {
+                used[candidate] = true;
+                chosen[slot] = candidate;
+                search(slot + 1, used, cost + quantize::delta_e(wanted[slot], safe[candidate]), chosen, best, wanted, safe);
+                used[candidate] = false;
+            }
+        }
+    }
+    let mut best = (f32::INFINITY, [0; 6]);
+    search(0, &mut [false; 7], 0.0, &mut [0; 6], &mut best, &wanted, &safe);
+
+    std::array::from_fn(|slot| {
+        let [r, g, b] = COLOR_BLIND_SAFE[best.1[slot]];
+        let alpha = if colors[slot][3] == 0 { IMPORTED_ALPHA } else { colors[slot][3] };
+        [r, g, b, alpha]
+    })
+}
+
+/// Version of the JSON/TOML sharing format, stored as `taskdeck_colorscheme`.
+const SCHEME_FILE_VERSION: u32 = 1;
+
+/// Alpha given to colours from formats without one (`.gpl`, `.ase`): that
+/// of generated schemes, which suits fills over the background.
+const IMPORTED_ALPHA: u8 = 80;
+
+/// File formats a single scheme can be shared in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemeFormat {
+    /// Our own, with the name and `#RRGGBBAA` colours.
+    Json,
+    /// The same as TOML.
+    Toml,
+    /// GIMP palette. No alpha.
+    Gpl,
+    /// Adobe Swatch Exchange. No alpha.
+    Ase,
+}
+
+impl SchemeFormat {
+    pub const ALL: [SchemeFormat; 4] = [SchemeFormat::Json, SchemeFormat::Toml, SchemeFormat::Gpl, SchemeFormat::Ase];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            SchemeFormat::Json => "json",
+            SchemeFormat::Toml => "toml",
+            SchemeFormat::Gpl => "gpl",
+            SchemeFormat::Ase => "ase",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SchemeFormat::Json => "JSON",
+            SchemeFormat::Toml => "TOML",
+            SchemeFormat::Gpl => "GIMP palette",
+            SchemeFormat::Ase => "Adobe swatches",
+        }
+    }
+
+    /// The format a file is in, going by its extension.
+    pub fn of_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        SchemeFormat::ALL.into_iter().find(|format| format.extension() == extension)
+    }
+}
+
+/// A scheme as written to a `.json` or `.toml` file.
+#[derive(Serialize, Deserialize)]
+struct SchemeFile {
+    taskdeck_colorscheme: u32,
+    name: String,
+    colors: Vec<String>,
+}
+
+/// A scheme read from a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedScheme {
+    pub scheme: ColorScheme,
+    /// How many colours the file had; only the first six are used.
+    pub colors_in_file: usize,
+}
+
+/// `scheme` in `format`. Formats without alpha leave it out.
+pub fn export_scheme(scheme: &ColorScheme, format: SchemeFormat) -> Vec<u8> {
+    match format {
+        SchemeFormat::Json | SchemeFormat::Toml => {
+            let file = SchemeFile {
+                taskdeck_colorscheme: SCHEME_FILE_VERSION,
+                name: scheme.name.clone(),
+                colors: scheme.colors.iter().map(|[r, g, b, a]| format!("#{r:02x}{g:02x}{b:02x}{a:02x}")).collect(),
+            };
+            let text = if format == SchemeFormat::Json {
+                serde_json::to_string_pretty(&file).expect("a scheme serializes")
+            } else {
+                toml::to_string(&file).expect("a scheme serializes")
+            };
+            text.into_bytes()
+        }
+        SchemeFormat::Gpl => {
+            let mut text = format!("GIMP Palette\nName: {}\nColumns: {}\n#\n", scheme.name.replace('\n', " "), scheme.colors.len());
+            for (i, [r, g, b, _]) in scheme.colors.iter().enumerate() {
+                text.push_str(&format!("{r:3} {g:3} {b:3}\tColour {}\n", i + 1));
+            }
+            text.into_bytes()
+        }
+        SchemeFormat::Ase => {
+            // One group named after the scheme, holding its colours as RGB.
+            let mut out = b"ASEF".to_vec();
+            out.extend(1u16.to_be_bytes());
+            out.extend(0u16.to_be_bytes());
+            out.extend((scheme.colors.len() as u32 + 2).to_be_bytes());
+            ase_block(&mut out, ASE_GROUP_START, &ase_name(&scheme.name));
+            for (i, [r, g, b, _]) in scheme.colors.iter().enumerate() {
+                let mut entry = ase_name(&format!("Colour {}", i + 1));
+                entry.extend(b"RGB ");
+                for channel in [r, g, b] {
+                    entry.extend((*channel as f32 / 255.0).to_be_bytes());
+                }
+                entry.extend(ASE_NORMAL_COLOR.to_be_bytes());
+                ase_block(&mut out, ASE_COLOR_ENTRY, &entry);
+            }
+            ase_block(&mut out, ASE_GROUP_END, &[]);
+            out
+        }
+    }
+}
+
+/// Reads a scheme in `format` from `bytes`. A palette without a name is
+/// named `fallback_name` (the file's name). A palette needs at least six
+/// colours; any after the sixth are left out.
+pub fn import_scheme(format: SchemeFormat, bytes: &[u8], fallback_name: &str) -> Result<ImportedScheme, Box<dyn Error>> {
+    let (name, colors) = match format {
+        SchemeFormat::Json | SchemeFormat::Toml => {
+            let text = std::str::from_utf8(bytes)?;
+            let file: SchemeFile = if format == SchemeFormat::Json { serde_json::from_str(text)? } else { toml::from_str(text)? };
+            if file.taskdeck_colorscheme > SCHEME_FILE_VERSION {
+                return Err(format!("the file is from a newer TaskDeck (format {})", file.taskdeck_colorscheme).into());
+            }
+            let colors = file.colors.iter().map(|hex| parse_hex_color(hex)).collect::<Result<Vec<_>, _>>()?;
+            (Some(file.name), colors)
+        }
+        SchemeFormat::Gpl => read_gpl(std::str::from_utf8(bytes)?)?,
+        SchemeFormat::Ase => read_ase(bytes)?,
+    };
+
+    let colors_in_file = colors.len();
+    let colors: [[u8; 4]; 6] = colors
+        .get(..6)
+        .and_then(|six| six.try_into().ok())
+        .ok_or_else(|| format!("it has {colors_in_file} colour(s) and a scheme needs 6"))?;
+    let name = name.filter(|name| !name.trim().is_empty()).unwrap_or_else(|| fallback_name.to_string());
+    Ok(ImportedScheme { scheme: ColorScheme { name, colors, is_user_configurable: true }, colors_in_file })
+}
+
+/// An id no scheme in `schemes` has: one past the highest, or the lowest
+/// free one if that would overflow.
+pub fn fresh_scheme_id(schemes: &HashMap<u32, ColorScheme>) -> u32 {
+    match schemes.keys().max() {
+        None => 0,
+        Some(max) => max.checked_add(1).unwrap_or_else(|| (0..).find(|id| !schemes.contains_key(id)).unwrap_or(0)),
+    }
+}
+
+/// Adds `scheme` under a fresh id, unless a scheme with the same colours is
+/// already there. `Ok` with the new id, or `Err` with the existing one.
+pub fn add_scheme(schemes: &mut HashMap<u32, ColorScheme>, scheme: ColorScheme) -> Result<u32, u32> {
+    if let Some((id, _)) = schemes.iter().filter(|(_, existing)| existing.colors == scheme.colors).min_by_key(|(id, _)| **id) {
+        return Err(*id);
+    }
+    let id = fresh_scheme_id(schemes);
+    schemes.insert(id, scheme);
+    Ok(id)
+}
+
+/// Writes `scheme` in `format` to `colorscheme-<name>.<ext>` in the data
+/// directory, replacing an earlier export of it. Returns the path.
+pub fn export_scheme_to_data_dir(scheme: &ColorScheme, format: SchemeFormat, exe_path: &PathBuf) -> Result<PathBuf, Box<dyn Error>> {
+    let data_dir = crate::tasks::get_data_dir(exe_path)?;
+    let slug: String = scheme
+        .name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    let path = data_dir.join(format!("colorscheme-{}.{}", slug.trim_matches('_'), format.extension()));
+    crate::tasks::write_atomically(&data_dir, &path, &export_scheme(scheme, format))?;
+    Ok(path)
+}
+
+/// `#RRGGBB` or `#RRGGBBAA`; the `#` is optional.
+fn parse_hex_color(hex: &str) -> Result<[u8; 4], String> {
+    let digits = hex.trim().trim_start_matches('#');
+    let channel = |i: usize| digits.get(i..i + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok());
+    match (digits.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(r), Some(g), Some(b)) => Ok([r, g, b, 255]),
+        (8, Some(r), Some(g), Some(b)) => channel(6).map(|a| [r, g, b, a]).ok_or_else(|| format!("\"{hex}\" is not a colour")),
+        _ => Err(format!("\"{hex}\" is not a colour")),
+    }
+}
+
+/// A palette file's name, if it has one, and its colours.
+type Palette = (Option<String>, Vec<[u8; 4]>);
+
+/// The name and colours of a GIMP palette.
+fn read_gpl(text: &str) -> Result<Palette, Box<dyn Error>> {
+    let mut lines = text.lines().enumerate();
+    if lines.next().map(|(_, line)| line.trim()) != Some("GIMP Palette") {
+        return Err("it is not a GIMP palette (no \"GIMP Palette\" header)".into());
+    }
+
+    let mut name = None;
+    let mut colors = Vec::new();
+    for (number, line) in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("Columns:") {
+            continue;
+        }
+        if let Some(value) = line.strip_prefix("Name:") {
+            name = Some(value.trim().to_string());
+            continue;
+        }
+        let channels: Vec<u8> = line.split_whitespace().take(3).map_while(|value| value.parse().ok()).collect();
+        let [r, g, b] = channels[..] else {
+            return Err(format!("line {}, \"{line}\", is not a colour", number + 1).into());
+        };
+        colors.push([r, g, b, IMPORTED_ALPHA]);
+    }
+    Ok((name, colors))
+}
+
+const ASE_GROUP_START: u16 = 0xC001;
+const ASE_GROUP_END: u16 = 0xC002;
+const ASE_COLOR_ENTRY: u16 = 0x0001;
+/// Colour type "normal", as opposed to global or spot.
+const ASE_NORMAL_COLOR: u16 = 2;
+
+fn ase_block(out: &mut Vec<u8>, kind: u16, data: &[u8]) {
+    out.extend(kind.to_be_bytes());
+    out.extend((data.len() as u32).to_be_bytes());
+    out.extend(data);
+}
+
+/// A length-prefixed, NUL-terminated UTF-16BE name.
+fn ase_name(name: &str) -> Vec<u8> {
+    let units: Vec<u16> = name.encode_utf16().chain([0]).collect();
+    let mut out = (units.len() as u16).to_be_bytes().to_vec();
+    for unit in units {
+        out.extend(unit.to_be_bytes());
+    }
+    out
+}
+
+/// Reads big-endian values off the front of a slice.
+struct AseReader<'a>(&'a [u8]);
+
+impl<'a> AseReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], &'static str> {
+        if self.0.len() < n {
+            return Err("the swatch file is cut short");
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+    fn u16(&mut self) -> Result<u16, &'static str> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+    fn u32(&mut self) -> Result<u32, &'static str> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn f32(&mut self) -> Result<f32, &'static str> {
+        Ok(f32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn name(&mut self) -> Result<String, &'static str> {
+        let units = (0..self.u16()?).map(|_| self.u16()).collect::<Result<Vec<_>, _>>()?;
+        Ok(String::from_utf16_lossy(&units).trim_end_matches('\0').to_string())
+    }
+}
+
+/// The first group's name and the colours of an Adobe Swatch Exchange file.
+/// RGB, CMYK, Lab and grey swatches are read; CMYK converts naively, with
+/// no colour profile.
+fn read_ase(bytes: &[u8]) -> Result<Palette, Box<dyn Error>> {
+    let mut reader = AseReader(bytes);
+    if reader.take(4).ok() != Some(b"ASEF".as_slice()) {
+        return Err("it is not an Adobe swatch exchange file (no \"ASEF\" signature)".into());
+    }
+    reader.take(4)?;
+    let blocks = reader.u32()?;
+
+    let mut name = None;
+    let mut colors = Vec::new();
+    for _ in 0..blocks {
+        let kind = reader.u16()?;
+        let length = reader.u32()? as usize;
+        let mut block = AseReader(reader.take(length)?);
+        match kind {
+            ASE_GROUP_START if name.is_none() => name = Some(block.name()?),
+            ASE_COLOR_ENTRY => {
+                block.name()?;
+                let model = block.take(4)?;
+                let [r, g, b] = match model {
+                    b"RGB " => [block.f32()?, block.f32()?, block.f32()?],
+                    b"Gray" => [block.f32()?; 3],
+                    b"CMYK" => {
+                        let [c, m, y, k] = [block.f32()?, block.f32()?, block.f32()?, block.f32()?];
+                        [(1.0 - c) * (1.0 - k), (1.0 - m) * (1.0 - k), (1.0 - y) * (1.0 - k)]
+                    }
+                    b"LAB " => {
+                        let srgb = Srgb::from_color(Lab::new(block.f32()? * 100.0, block.f32()?, block.f32()?));
+                        [srgb.red, srgb.green, srgb.blue]
+                    }
+                    _ => return Err(format!("swatch colour model \"{}\" is not supported", String::from_utf8_lossy(model)).into()),
+                };
+                let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                colors.push([channel(r), channel(g), channel(b), IMPORTED_ALPHA]);
+            }
+            _ => {}
+        }
+    }
+    Ok((name, colors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> ColorScheme {
+        ColorScheme {
+            name: "Fjord at dusk".to_string(),
+            colors: [[10, 20, 30, 80], [40, 50, 60, 255], [70, 80, 90, 0], [100, 110, 120, 80], [130, 140, 150, 80], [255, 0, 128, 80]],
+            is_user_configurable: true,
+        }
+    }
+
+    #[test]
+    fn every_format_reads_back_what_it_wrote() {
+        let scheme = sample();
+        for format in SchemeFormat::ALL {
+            let imported = import_scheme(format, &export_scheme(&scheme, format), "file").unwrap();
+            assert_eq!(imported.scheme.name, scheme.name, "{format:?}");
+            assert_eq!(imported.colors_in_file, 6);
+            let alpha = |colors: [[u8; 4]; 6]| colors.map(|[r, g, b, _]| [r, g, b]);
+            assert_eq!(alpha(imported.scheme.colors), alpha(scheme.colors), "{format:?}");
+        }
+        // Only our own formats keep the alpha.
+        assert_eq!(import_scheme(SchemeFormat::Toml, &export_scheme(&scheme, SchemeFormat::Toml), "file").unwrap().scheme, scheme);
+        assert_eq!(import_scheme(SchemeFormat::Gpl, &export_scheme(&scheme, SchemeFormat::Gpl), "file").unwrap().scheme.colors[1][3], IMPORTED_ALPHA);
+
+        let json = String::from_utf8(export_scheme(&scheme, SchemeFormat::Json)).unwrap();
+        assert!(json.contains("\"taskdeck_colorscheme\": 1") && json.contains("\"#0a141e50\""), "{json}");
+        assert_eq!(SchemeFormat::of_path(Path::new("/tmp/Fjord.GPL")), Some(SchemeFormat::Gpl));
+        assert_eq!(SchemeFormat::of_path(Path::new("fjord.png")), None);
+    }
+
+    #[test]
+    fn palettes_from_other_programs_are_read() {
+        let gpl = "GIMP Palette\nName: Eight\nColumns: 4\n# comment\n255   0   0\tRed\n0 255 0 Green\n0 0 255\n1 1 1\n2 2 2\n3 3 3\n4 4 4\n5 5 5\n";
+        let imported = import_scheme(SchemeFormat::Gpl, gpl.as_bytes(), "eight").unwrap();
+        assert_eq!(imported.colors_in_file, 8);
+        assert_eq!((imported.scheme.name.as_str(), imported.scheme.colors[0]), ("Eight", [255, 0, 0, IMPORTED_ALPHA]));
+
+        // Swatches outside a group, in grey, CMYK and Lab: named after the file.
+        let mut ase = b"ASEF\x00\x01\x00\x00".to_vec();
+        ase.extend(6u32.to_be_bytes());
+        let swatch = |model: &[u8], values: &[f32]| {
+            let mut entry = ase_name("swatch");
+            entry.extend(model);
+            values.iter().for_each(|value| entry.extend(value.to_be_bytes()));
+            entry.extend(0u16.to_be_bytes());
+            entry
+        };
+        ase_block(&mut ase, ASE_COLOR_ENTRY, &swatch(b"Gray", &[0.5]));
+        ase_block(&mut ase, ASE_COLOR_ENTRY, &swatch(b"CMYK", &[0.0, 1.0, 1.0, 0.0]));
+        ase_block(&mut ase, ASE_COLOR_ENTRY, &swatch(b"LAB ", &[1.0, 0.0, 0.0]));
+        for _ in 0..3 {
+            ase_block(&mut ase, ASE_COLOR_ENTRY, &swatch(b"RGB ", &[0.0, 0.0, 1.0]));
+        }
+        let imported = import_scheme(SchemeFormat::Ase, &ase, "swatches").unwrap();
+        assert_eq!(imported.scheme.name, "swatches");
+        assert_eq!(&imported.scheme.colors[..3], &[[128, 128, 128, IMPORTED_ALPHA], [255, 0, 0, IMPORTED_ALPHA], [255, 255, 255, IMPORTED_ALPHA]]);
+
+        // Too few colours, or not the format at all.
+        let short = import_scheme(SchemeFormat::Gpl, b"GIMP Palette\n1 2 3\n", "x").unwrap_err();
+        assert_eq!(short.to_string(), "it has 1 colour(s) and a scheme needs 6");
+        assert!(import_scheme(SchemeFormat::Ase, &ase[..40], "x").is_err());
+        assert!(import_scheme(SchemeFormat::Ase, b"PK\x03\x04", "x").unwrap_err().to_string().contains("ASEF"));
+        assert!(import_scheme(SchemeFormat::Json, br##"{"taskdeck_colorscheme":1,"name":"x","colors":["#zz0000"]}"##, "x").is_err());
+    }
+
+    #[test]
+    fn imports_get_fresh_ids_and_duplicates_are_not_added() {
+        let mut schemes = HashMap::from([(0, ColorScheme::default_scheme()), (7, sample())]);
+
+        assert_eq!(add_scheme(&mut schemes, ColorScheme { name: "Same colours".to_string(), ..sample() }), Err(7));
+        let mut other = sample();
+        other.colors[0] = [1, 2, 3, 4];
+        assert_eq!(add_scheme(&mut schemes, other.clone()), Ok(8));
+        assert_eq!(schemes.len(), 3);
+
+        schemes.insert(u32::MAX, ColorScheme::default_scheme());
+        assert_eq!(fresh_scheme_id(&schemes), 1);
+        assert_eq!(fresh_scheme_id(&HashMap::new()), 0);
+    }
+
+    #[test]
+    fn contrast_follows_wcag_and_counts_the_backdrop() {
+        assert!((contrast_ratio([0, 0, 0], [255, 255, 255]) - 21.0).abs() < 0.01);
+        assert_eq!(contrast_ratio([90, 30, 200], [90, 30, 200]), 1.0);
+        // The WCAG example: #777 on white is just under AA.
+        assert!((4.4..4.5).contains(&contrast_ratio([119, 119, 119], [255, 255, 255])));
+
+        // An opaque fill hides the backdrop; a clear one shows it.
+        let dark = [[20, 20, 20]; 10];
+        let light = [[240, 240, 240]; 10];
+        let text = [150, 150, 150];
+        assert_eq!(fill_contrast([20, 20, 20, 255], text, &light), fill_contrast([0, 0, 0, 0], text, &dark));
+        // One bright speck in ten samples is overlooked, two are not.
+        let mut specks = dark.to_vec();
+        specks[0] = [255, 255, 255];
+        assert_eq!(fill_contrast([0, 0, 0, 0], text, &specks), fill_contrast([0, 0, 0, 0], text, &dark));
+        specks[1] = [255, 255, 255];
+        assert_eq!(fill_contrast([0, 0, 0, 0], text, &specks), contrast_ratio(text, [255, 255, 255]));
+    }
+
+    #[test]
+    fn fills_are_moved_in_lightness_until_the_text_is_readable() {
+        let goal = ContrastGoal { texts: vec![("Item names", [150, 150, 150]), ("Today", [255, 255, 255])], ratio: WCAG_AA };
+        let backdrop = [[60, 55, 45]; 4];
+
+        let fill = [190, 170, 60, 80];
+        assert!(worst_contrast(fill, &backdrop, &goal) < WCAG_AA);
+        let adjusted = adjust_for_contrast(fill, &backdrop, &goal);
+        assert!(worst_contrast(adjusted, &backdrop, &goal) >= WCAG_AA);
+        assert_eq!(adjusted[3], 80);
+        // Darker, since lighter can't get there with grey text.
+        assert!(relative_luminance([adjusted[0], adjusted[1], adjusted[2]]) < relative_luminance([190, 170, 60]));
+
+        // Readable fills are left alone.
+        let navy = [20, 30, 60, 80];
+        assert!(worst_contrast(navy, &backdrop, &goal) >= WCAG_AA);
+        assert_eq!(adjust_for_contrast(navy, &backdrop, &goal), navy);
+    }
+
+    #[test]
+    fn the_backdrop_is_the_tinted_image_over_the_panel() {
+        let mut image = image::RgbaImage::from_pixel(40, 20, image::Rgba([255, 0, 0, 255]));
+        for x in 20..40 {
+            for y in 0..20 {
+                image.put_pixel(x, y, image::Rgba([0, 0, 255, 255]));
+            }
+        }
+        let image = DynamicImage::ImageRgba8(image);
+        let left_half = Backdrop { region: [0.0, 0.0, 0.4, 1.0], tint: 0.5, panel: [0, 0, 0] };
+
+        let samples = left_half.sample(Some(&image));
+        assert_eq!(samples.len(), (BACKDROP_GRID * BACKDROP_GRID) as usize);
+        assert!(samples.iter().all(|&c| c == [128, 0, 0]), "{:?}", &samples[..4]);
+        assert_eq!(Backdrop { tint: 0.0, ..left_half.clone() }.sample(Some(&image)), vec![[0, 0, 0]; samples.len()]);
+        assert_eq!(left_half.sample(None), vec![[0, 0, 0]]);
+    }
+
+    #[test]
+    fn colour_blind_safe_schemes_stay_apart_for_everyone() {
+        let panel = [27, 27, 27];
+        // Red and olive of similar lightness: apart for most, not with deuteranopia.
+        let mut scheme = sample().colors;
+        scheme[2] = [200, 70, 60, 80];
+        scheme[3] = [120, 130, 40, 80];
+        assert!(confusable_slots(&scheme, None, panel).is_empty());
+        assert!(confusable_slots(&scheme, Some(ColorVision::Deuteranopia), panel).iter().any(|(a, b, _)| (*a, *b) == (2, 3)));
+
+        let safe = color_blind_safe(scheme);
+        for vision in ColorVision::ALL {
+            assert!(confusable_slots(&safe, Some(vision), panel).is_empty(), "{vision:?}");
+        }
+        // Each slot moves to the nearest free safe colour; alpha stays unless it was clear.
+        assert_eq!(safe[2], [213, 94, 0, 80]);
+        assert_eq!(safe[1][3], 255);
+        assert_eq!(sample().colors[2][3], 0);
+        assert_eq!(color_blind_safe(sample().colors)[2][3], IMPORTED_ALPHA);
+
+        // Greys look the same to everyone; pure red loses to deuteranopia.
+        assert_eq!(ColorVision::Protanopia.simulate([128, 128, 128]), [128, 128, 128]);
+        let red = ColorVision::Deuteranopia.simulate([255, 0, 0]);
+        assert!(red[0] < 200 && red[1] > 100, "{red:?}");
+    }
+}
@@ -0,0 +1,261 @@
+use std::sync::OnceLock;
+
+/// The embedded place list: one tab-separated row per place (see the header
+/// of `gazetteer.tsv` for the columns). Compiled into the binary so the
+/// coordinates picker can search without network access.
+const GAZETTEER_TSV: &str = include_str!("../gazetteer.tsv");
+
+/// Results the map picker lists under its search box.
+pub const SEARCH_LIMIT: usize = 8;
+
+/// A named place from the gazetteer.
+#[derive(Debug)]
+pub struct Place {
+    pub name: &'static str,
+    /// Other spellings the place is searchable by ("Bangalore", "Peking").
+    pub alternate_names: Vec<&'static str>,
+    pub country: &'static str,
+    /// ISO 3166 alpha-2 code.
+    pub country_code: &'static str,
+    pub latitude: f32,
+    pub longitude: f32,
+    /// IANA time zone name, e.g. `Europe/Oslo`.
+    pub timezone: &'static str,
+    /// Rough population, only used to rank otherwise equal matches; zero for
+    /// the small time-zone reference places.
+    pub population: u32,
+    /// `name` followed by `alternate_names`, folded for matching.
+    folded_names: Vec<String>,
+    folded_country: String,
+}
+
+impl Place {
+    pub fn coordinates(&self) -> [f32; 2] {
+        [self.latitude, self.longitude]
+    }
+}
+
+/// Every place in the gazetteer, largest first.
+pub fn places() -> &'static [Place] {
+    static PLACES: OnceLock<Vec<Place>> = OnceLock::new();
+    PLACES.get_or_init(|| GAZETTEER_TSV.lines().filter(|line| !line.is_empty() && !line.starts_with('#')).map(parse_place).collect())
+}
+
+fn parse_place(line: &'static str) -> Place {
+    let fields: Vec<&'static str> = line.split('\t').collect();
+    let [name, alternates, country, country_code, latitude, longitude, timezone, population] = fields[..] else {
+        panic!("Malformed gazetteer row: {line}");
+    };
+    let alternate_names: Vec<&'static str> = alternates.split(',').filter(|s| !s.is_empty()).collect();
+    let folded_names = std::iter::once(name).chain(alternate_names.iter().copied()).map(fold).collect();
+
+    Place {
+        name,
+        alternate_names,
+        country,
+        country_code,
+        latitude: latitude.parse().expect("gazetteer latitude"),
+        longitude: longitude.parse().expect("gazetteer longitude"),
+        timezone,
+        population: population.parse().expect("gazetteer population"),
+        folded_names,
+        folded_country: fold(country),
+    }
+}
+
+/// Lowercases, strips diacritics and turns punctuation into spaces, so
+/// "São Paulo", "sao paulo" and "Sao-Paulo" all compare equal.
+fn fold(text: &str) -> String {
+    const FOLDS: &[(&str, &str)] = &[
+        ("àáâãäåāăąǎ", "a"), ("çćĉċč", "c"), ("ďđ", "d"), ("èéêëēĕėęě", "e"), ("ĝğġģ", "g"), ("ĥħ", "h"),
+        ("ìíîïĩīĭįı", "i"), ("ĵ", "j"), ("ķ", "k"), ("ĺļľŀł", "l"), ("ñńņňŉ", "n"), ("òóôõöøōŏő", "o"),
+        ("ŕŗř", "r"), ("śŝşšș", "s"), ("ţťŧț", "t"), ("ùúûüũūŭůűųǔ", "u"), ("ŵ", "w"), ("ýÿŷ", "y"), ("źżž", "z"),
+        ("ß", "ss"), ("æ", "ae"), ("œ", "oe"), ("þ", "th"), ("ð", "d"),
+    ];
+
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            folded.push(c);
+        } else if let Some((_, plain)) = FOLDS.iter().find(|(accented, _)| accented.contains(c)) {
+            folded.push_str(plain);
+        } else if c.is_alphanumeric() {
+            folded.push(c);
+        } else if matches!(c, '\'' | 'ʻ' | '’') {
+            // "Xi'an", "Nukuʻalofa": apostrophes join rather than split words.
+        } else if !folded.ends_with(' ') && !folded.is_empty() {
+            folded.push(' ');
+        }
+    }
+    folded.truncate(folded.trim_end().len());
+    folded
+}
+
+/// How well `query` matches `candidate`, lower is better: exact, prefix,
+/// word prefix, substring, in-order letters, then a typo or two.
+fn match_rank(query: &str, candidate: &str) -> Option<u32> {
+    if candidate == query {
+        return Some(0);
+    }
+    if candidate.starts_with(query) {
+        return Some(1);
+    }
+    if candidate.split(' ').any(|word| word.starts_with(query)) {
+        return Some(2);
+    }
+    if candidate.contains(query) {
+        return Some(3);
+    }
+
+    let query_len = query.chars().count();
+    if query_len >= 3 && query.chars().next() == candidate.chars().next() {
+        let mut letters = candidate.chars();
+        if query.chars().all(|q| letters.any(|c| c == q)) {
+            return Some(4);
+        }
+    }
+
+    let allowed_typos = match query_len {
+        0..=3 => return None,
+        4..=7 => 1,
+        _ => 2,
+    };
+    let candidate_prefix: String = candidate.chars().take(query_len).collect();
+    let mut typos = edit_distance(query, &candidate_prefix) + 1;
+    if candidate.chars().count().abs_diff(query_len) <= allowed_typos {
+        typos = typos.min(edit_distance(query, candidate));
+    }
+    (typos <= allowed_typos).then_some(4 + typos as u32)
+}
+
+/// Optimal string alignment distance: insertions, deletions, substitutions
+/// and swaps of neighbouring letters each cost one.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Whether `qualifier` (the words after a place name, as in "brest belarus"
+/// or "portland, us") names the place's country.
+fn matches_country(qualifier: &str, place: &Place) -> bool {
+    const ALIASES: &[(&str, &str)] = &[("uk", "GB"), ("usa", "US"), ("uae", "AE")];
+
+    qualifier.eq_ignore_ascii_case(place.country_code)
+        || ALIASES.iter().any(|(alias, code)| qualifier == *alias && place.country_code == *code)
+        || match_rank(qualifier, &place.folded_country).is_some_and(|rank| rank <= 2 || (rank >= 5 && qualifier.len() >= 5))
+}
+
+/// Best rank of `place` for `query`: either the whole query against the
+/// place's names, or a leading part of it against the names with the rest
+/// naming the country.
+fn place_rank(query: &str, place: &Place) -> Option<u32> {
+    let name_rank = |name_query: &str| place.folded_names.iter().filter_map(|name| match_rank(name_query, name)).min();
+
+    let mut best = name_rank(query);
+    for (split, _) in query.match_indices(' ') {
+        let (name_query, qualifier) = (&query[..split], &query[split + 1..]);
+        if matches_country(qualifier, place) {
+            best = best.into_iter().chain(name_rank(name_query)).min();
+        }
+    }
+    best
+}
+
+/// Places matching `query`, best first: closer matches before looser ones,
+/// bigger places before smaller ones. Accents, case and punctuation don't
+/// matter, small typos are forgiven, and a trailing country name or code
+/// narrows the search ("paris us", "Brest, Belarus").
+pub fn search(query: &str, limit: usize) -> Vec<&'static Place> {
+    let query = fold(query);
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ranked: Vec<(u32, &'static Place)> = places().iter().filter_map(|place| Some((place_rank(&query, place)?, place))).collect();
+    ranked.sort_by(|(a_rank, a), (b_rank, b)| a_rank.cmp(b_rank).then(b.population.cmp(&a.population)).then(a.name.cmp(b.name)));
+    ranked.into_iter().take(limit).map(|(_, place)| place).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weather;
+
+    fn first(query: &str) -> &'static Place {
+        search(query, SEARCH_LIMIT).into_iter().next().unwrap_or_else(|| panic!("nothing found for {query:?}"))
+    }
+
+    #[test]
+    fn gazetteer_rows_are_sane() {
+        let places = places();
+        assert!(places.len() > 1000);
+        for place in places {
+            assert!(!place.name.is_empty() && !place.country.is_empty());
+            assert_eq!(place.country_code.len(), 2, "{}", place.name);
+            assert!((-90.0..=90.0).contains(&place.latitude), "{}", place.name);
+            assert!((-180.0..=180.0).contains(&place.longitude), "{}", place.name);
+            assert!(place.timezone.contains('/'), "{}: {}", place.name, place.timezone);
+        }
+        assert!(places.windows(2).all(|pair| pair[0].population >= pair[1].population));
+    }
+
+    #[test]
+    fn matching_ignores_accents_case_and_punctuation() {
+        assert_eq!(fold("São Paulo"), "sao paulo");
+        assert_eq!(fold("Rostov-on-Don"), "rostov on don");
+        assert_eq!(fold("Xi'an"), "xian");
+
+        let sao_paulo = first("sao paulo");
+        assert_eq!((sao_paulo.name, sao_paulo.country_code, sao_paulo.timezone), ("São Paulo", "BR", "America/Sao_Paulo"));
+        assert_eq!(first("KRAKOW").name, "Kraków");
+        assert_eq!(first("Bangalore").name, "Bengaluru");
+    }
+
+    #[test]
+    fn typos_still_find_the_place() {
+        assert_eq!(first("Helsniki").name, "Helsinki");
+        assert_eq!(first("Reykjavik").name, "Reykjavík");
+        assert_eq!(first("Amsterdm").name, "Amsterdam");
+        assert!(search("qqqq", SEARCH_LIMIT).is_empty());
+    }
+
+    #[test]
+    fn bigger_places_rank_first_and_a_country_narrows_the_search() {
+        assert_eq!(first("Paris").country_code, "FR");
+        assert_eq!(first("Brest").country_code, "BY");
+        assert_eq!(first("brest france").country_code, "FR");
+        assert_eq!(first("Brest, FR").country_code, "FR");
+
+        let portland = first("portland usa");
+        assert_eq!((portland.country_code, portland.timezone), ("US", "America/Los_Angeles"));
+        assert_eq!(first("hamilton new zealand").timezone, "Pacific/Auckland");
+    }
+
+    #[test]
+    fn every_map_city_can_be_found() {
+        for city in weather::CITIES {
+            let found = search(city.name, SEARCH_LIMIT)
+                .into_iter()
+                .any(|place| (place.latitude - city.latitude).abs() < 0.5 && (place.longitude - city.longitude).abs() < 0.5);
+            assert!(found, "{} is missing from the gazetteer", city.name);
+        }
+    }
+}
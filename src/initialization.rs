@@ -3,113 +3,314 @@ use egui_wgpu::wgpu::{StoreOp};
 use egui_wgpu::{wgpu, Renderer, RendererOptions, ScreenDescriptor};
 use egui_winit::{ActionRequested, State};
 use serde::{Deserialize, Serialize};
+use crate::alerts::{self, AlertRules, MAX_ALERT_HORIZON_HOURS};
 use crate::ui::TaskApp;
+use crate::units::{PrecipitationUnit, Rounding, TemperatureUnit, Units, WindUnit};
+use crate::weather::{self, AGGREGATION_WINDOWS, Location, MAX_FORECAST_DAYS, RefreshPolicy, WeatherProviderKind};
 use wgpu::{Color, ExperimentalFeatures, LoadOp};
 use winit::event::WindowEvent;
+#[cfg(windows)]
 use winit::platform::windows::{WindowAttributesExtWindows};
 use winit::window::{Window, WindowId};
 use egui_wgpu::wgpu::CurrentSurfaceTexture;
-use std::collections::HashMap;
-use std::{fs, time};
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+use std::{fmt, fs, time};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 use winit::application::ApplicationHandler;
 use winit::dpi::{LogicalPosition, LogicalSize, PhysicalSize};
 use winit::event_loop::ActiveEventLoop;
-use toml::Value;
 
-/// Reads a TOML config file and extracts all valid values, including arrays.
-/// If parsing fails, falls back to line-by-line extraction.
-fn read_config(path: &PathBuf) -> HashMap<String, String> {
-    let contents = match fs::read_to_string(path) {
-        Ok(thing) => thing,
-        Err(_) => {
-            if let Some(parent) = path.parent() {
-                let _ = fs::create_dir_all(parent);
-            }
-            let _ = fs::File::create(path);
-            " ".to_string()
-        },
-    };
-    let mut config = HashMap::new();
-
-    // Try parsing with the TOML crate first
-    match contents.parse::<Value>() {
-        Ok(toml_value) => {
-            fn extract_values(value: &Value, prefix: &str, config: &mut HashMap<String, String>) {
-                match value {
-                    Value::Table(table) => {
-                        for (k, v) in table {
-                            let new_prefix = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
-                            extract_values(v, &new_prefix, config);
-                        }
-                    }
-                    Value::Array(arr) => {
-                        let mut items = vec![];
-                        for item in arr {
-                            match item {
-                                Value::String(s) => items.push(format!("\"{}\"", s)),
-                                Value::Integer(i) => items.push(i.to_string()),
-                                Value::Float(f) => items.push(f.to_string()),
-                                Value::Boolean(b) => items.push(b.to_string()),
-                                _ => {}
-                            }
-                        }
-                        config.insert(prefix.to_string(), format!("[{}]", items.join(", ")));
-                    }
-                    Value::String(s) => { config.insert(prefix.to_string(), s.clone()); }
-                    Value::Integer(i) => { config.insert(prefix.to_string(), i.to_string()); }
-                    Value::Float(f) => { config.insert(prefix.to_string(), f.to_string()); }
-                    Value::Boolean(b) => { config.insert(prefix.to_string(), b.to_string()); }
-                    _ => {} // ignore datetime for simplicity
-                }
-            }
+/// What `write_normalized_config` stamps into the file as `config_version`.
+/// Files without one predate it and are version 0 (see `migrate_config`).
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Something wrong with `userconfig.toml`, found while loading it, for the
+/// error window. Errors are values that could not be used as written (the
+/// setting falls back or is clamped); warnings change nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    pub severity: Severity,
+    pub message: String,
+}
 
-            extract_values(&toml_value, "", &mut config);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl ConfigIssue {
+    fn error(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Error, message: message.into() }
+    }
+    fn warning(message: impl Into<String>) -> Self {
+        Self { severity: Severity::Warning, message: message.into() }
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Error => write!(f, "Error: {}", self.message),
+            Severity::Warning => write!(f, "Warning: {}", self.message),
         }
-        Err(_) => {
-            // Fallback: line-by-line extraction
-            for line in contents.lines() {
-                let line = line.split('#').next().unwrap().trim(); // remove comments
-                if let Some(pos) = line.find('=') {
-                    let key = line[..pos].trim();
-                    let value = line[pos + 1..].trim();
-
-                    let parsed_value = if let Ok(i) = value.parse::<i64>() {
-                        i.to_string()
-                    } else if let Ok(f) = value.parse::<f64>() {
-                        f.to_string()
-                    } else if let Ok(b) = value.parse::<bool>() {
-                        b.to_string()
-                    } else if value.starts_with('"') && value.ends_with('"') && value.len() > 1 {
-                        value[1..value.len() - 1].to_string()
-                    } else if value.starts_with('[') && value.ends_with(']') {
-                        value.to_string() // crude fallback: keep array as-is
-                    } else if
-                        (value.starts_with('"') && value.ends_with('"')) ||
-                        (value.starts_with('\'') && value.ends_with('\''))
-                    {
-                        value[1..value.len() - 1].to_string()
-                    } else {
-                        continue; // skip invalid
-                    };
-
-                    config.insert(key.to_string(), parsed_value);
-                }
+    }
+}
+
+/// An `alert_*` threshold as written: a number, or `"off"`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum ThresholdSetting {
+    Number(f64),
+    Text(String),
+}
+
+impl From<Option<f64>> for ThresholdSetting {
+    fn from(threshold: Option<f64>) -> Self {
+        match threshold {
+            Some(x) => ThresholdSetting::Number(x),
+            None => ThresholdSetting::Text("off".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ThresholdSetting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThresholdSetting::Number(x) => write!(f, "{x}"),
+            ThresholdSetting::Text(text) => write!(f, "\"{text}\""),
+        }
+    }
+}
+
+const DEFAULT_WINDOW_SIZE: [f32; 2] = [1280.0, 720.0];
+
+/// `userconfig.toml` as written: one field per key, with its default.
+/// Numbers are read as `i64` and choices as text so `validate_config` can
+/// say what was wrong with them; `Config` is the checked, typed result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct ConfigFile {
+    config_version: i64,
+    start_in_fullscreen: bool,
+    /// Kept as values so one bad entry only loses that location.
+    locations: Vec<toml::Value>,
+    weather_location: String,
+    background: String,
+    enable_fps_counter: bool,
+    window_size_startup: [f32; 2],
+    calendar_weeks_to_show: i64,
+    selected_monitor_name: String,
+    selected_colorscheme_id: i64,
+    selected_theme_id: i64,
+    three_day_weather: bool,
+    weather_details_row: bool,
+    calendar_weather: bool,
+    night_bands: bool,
+    night_dimming: bool,
+    item_cues: bool,
+    alert_frost_below: ThresholdSetting,
+    alert_rain_above: ThresholdSetting,
+    alert_gusts_above: ThresholdSetting,
+    alert_thunderstorms: bool,
+    alert_horizon_hours: i64,
+    weather_provider: String,
+    weather_refresh_minutes: i64,
+    weather_retry_attempts: i64,
+    weather_retry_backoff_seconds: i64,
+    temperature_unit: String,
+    wind_speed_unit: String,
+    precipitation_unit: String,
+    temperature_rounding: String,
+    weather_window_hours: i64,
+    forecast_days: i64,
+    background_image_tint_percent: i64,
+    archive_past_events_after_days: i64,
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        let alert_rules = AlertRules::default();
+        let refresh = RefreshPolicy::default();
+        let units = Units::default();
+        Self {
+            config_version: i64::from(CONFIG_VERSION),
+            start_in_fullscreen: false,
+            locations: Vec::new(),
+            weather_location: String::new(),
+            background: String::new(),
+            enable_fps_counter: false,
+            window_size_startup: DEFAULT_WINDOW_SIZE,
+            calendar_weeks_to_show: 100,
+            selected_monitor_name: String::new(),
+            selected_colorscheme_id: 0,
+            selected_theme_id: 0,
+            three_day_weather: false,
+            weather_details_row: false,
+            calendar_weather: true,
+            night_bands: true,
+            night_dimming: false,
+            item_cues: false,
+            alert_frost_below: alert_rules.frost_below.into(),
+            alert_rain_above: alert_rules.rain_above.into(),
+            alert_gusts_above: alert_rules.gusts_above.into(),
+            alert_thunderstorms: alert_rules.thunderstorms,
+            alert_horizon_hours: i64::from(alert_rules.horizon_hours),
+            weather_provider: WeatherProviderKind::default().key().to_string(),
+            weather_refresh_minutes: i64::from(refresh.interval_minutes),
+            weather_retry_attempts: i64::from(refresh.attempts),
+            weather_retry_backoff_seconds: i64::from(refresh.backoff_seconds),
+            temperature_unit: units.temperature.key().to_string(),
+            wind_speed_unit: units.wind.key().to_string(),
+            precipitation_unit: units.precipitation.key().to_string(),
+            temperature_rounding: Rounding::default().key().to_string(),
+            weather_window_hours: 2,
+            forecast_days: 3,
+            background_image_tint_percent: 30,
+            archive_past_events_after_days: 0,
+        }
+    }
+}
+
+/// Reads the settings in `table` (`userconfig.toml`, migrated, with any
+/// profile laid over it). Each key is checked on its own, so a bad value
+/// only costs that setting; what went wrong goes into `issues`.
+fn read_config(table: toml::Table, issues: &mut Vec<ConfigIssue>) -> ConfigFile {
+    let defaults = toml::Table::try_from(ConfigFile::default()).expect("the default config serializes");
+    let mut checked = toml::Table::new();
+    for (key, value) in table {
+        let Some(default) = defaults.get(&key) else {
+            issues.push(ConfigIssue::warning(format!("Unknown key \"{key}\" is ignored")));
+            continue;
+        };
+        match read_setting(&key, value, default) {
+            Ok(value) => {
+                checked.insert(key, value);
             }
+            Err(e) => issues.push(ConfigIssue::error(format!("{key}: {e}; using {default}"))),
         }
     }
+    // Every key fits on its own, so together they do too.
+    toml::Value::Table(checked).try_into().unwrap_or_default()
+}
 
-    config
+/// `value` if `key` can hold it. Quoted numbers and flags, which older
+/// versions wrote for some settings, are read as what they say.
+fn read_setting(key: &str, value: toml::Value, default: &toml::Value) -> Result<toml::Value, String> {
+    let fits = |value: &toml::Value| {
+        toml::Value::Table(toml::Table::from_iter([(key.to_string(), value.clone())])).try_into::<ConfigFile>().map(|_| ())
+    };
+    let Err(e) = fits(&value) else {
+        return Ok(value);
+    };
+    let unquoted = match (&value, default) {
+        (toml::Value::String(text), toml::Value::Integer(_)) => text.trim().parse().ok().map(toml::Value::Integer),
+        (toml::Value::String(text), toml::Value::Float(_)) => text.trim().parse().ok().map(toml::Value::Float),
+        (toml::Value::String(text), toml::Value::Boolean(_)) => parse_config_bool(text).map(toml::Value::Boolean),
+        _ => None,
+    };
+    unquoted.filter(|value| fits(value).is_ok()).ok_or_else(|| e.message().trim().to_string())
 }
 
-/// Parse a boolean config value. Accepts the canonical `true`/`false` written by
-/// the config writer plus a few common variants (case-insensitive, surrounding
-/// whitespace ignored); anything unrecognised defaults to `false`. The old
-/// `contains("t")` heuristic treated e.g. `"east"` or `"set"` as `true`.
-fn parse_config_bool(text: &str) -> bool {
-    matches!(text.trim().to_ascii_lowercase().as_str(), "true" | "1" | "yes" | "on")
+/// Brings a file written by an older version up to `CONFIG_VERSION`.
+/// Version 0 had a single `coordinates` pair where there is now the
+/// `locations` list; it becomes a location called "Home".
+fn migrate_config(table: &mut toml::Table) {
+    if config_version(table) < 1 && let Some(coordinates) = table.remove("coordinates") && !table.contains_key("locations") {
+        let home = toml::Table::from_iter([("name".to_string(), toml::Value::from("Home")), ("coordinates".to_string(), coordinates)]);
+        table.insert("locations".to_string(), toml::Value::Array(vec![toml::Value::Table(home)]));
+    }
+}
+
+/// The file's `config_version`; 0 when it has none.
+fn config_version(table: &toml::Table) -> i64 {
+    table.get("config_version").and_then(toml::Value::as_integer).unwrap_or(0)
+}
+
+/// Whether `get_check_and_set_config` will migrate the file at `config_path`
+/// and write it back in the new format, so startup can snapshot it first. A
+/// missing or empty file is new rather than old, and one that isn't TOML is
+/// left alone.
+pub fn config_needs_migration(config_path: &Path) -> bool {
+    let contents = fs::read_to_string(config_path).unwrap_or_default();
+    contents.parse::<toml::Table>().is_ok_and(|table| !table.is_empty() && config_version(&table) < i64::from(CONFIG_VERSION))
+}
+
+/// Parse a boolean config value written as text. Accepts the canonical
+/// `true`/`false` plus a few common variants (case-insensitive, surrounding
+/// whitespace ignored); anything else is `None`. The old `contains("t")`
+/// heuristic treated e.g. `"east"` or `"set"` as `true`.
+fn parse_config_bool(text: &str) -> Option<bool> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// `value` limited to `range`, noting it if that changed it.
+fn clamp_setting(issues: &mut Vec<ConfigIssue>, key: &str, value: i64, range: RangeInclusive<i64>) -> i64 {
+    let clamped = value.clamp(*range.start(), *range.end());
+    if clamped != value {
+        issues.push(ConfigIssue::error(format!("{key} = {value} clamped to {clamped}")));
+    }
+    clamped
+}
+
+/// A `u32` range as `clamp_setting` takes it.
+fn wide(range: RangeInclusive<u32>) -> RangeInclusive<i64> {
+    i64::from(*range.start())..=i64::from(*range.end())
+}
+
+/// The one of `all` whose key is `text`, else the default, noting it.
+fn choose_setting<T: Copy + Default>(issues: &mut Vec<ConfigIssue>, key: &str, text: &str, all: &[T], key_of: fn(T) -> &'static str) -> T {
+    if let Some(choice) = all.iter().copied().find(|choice| key_of(*choice) == text.trim()) {
+        return choice;
+    }
+    let options: Vec<String> = all.iter().map(|choice| format!("\"{}\"", key_of(*choice))).collect();
+    let default = T::default();
+    issues.push(ConfigIssue::error(format!("{key} = \"{text}\" is not one of {}; using \"{}\"", options.join(", "), key_of(default))));
+    default
+}
+
+/// A threshold from `setting`, or `default` (noted) if it is neither a
+/// finite number nor `"off"`.
+fn threshold_setting(issues: &mut Vec<ConfigIssue>, key: &str, setting: &ThresholdSetting, default: Option<f64>) -> Option<f64> {
+    let parsed = match setting {
+        ThresholdSetting::Number(x) => x.is_finite().then_some(Some(*x)),
+        ThresholdSetting::Text(text) => alerts::parse_threshold(text),
+    };
+    parsed.unwrap_or_else(|| {
+        issues.push(ConfigIssue::error(format!("{key} = {setting} is neither a number nor \"off\"; using {}", ThresholdSetting::from(default))));
+        default
+    })
+}
+
+/// The weather locations, never empty: entries that aren't a name and a
+/// coordinate pair are skipped, and with none left there is one location at
+/// `[0, 0]`.
+fn validate_locations(issues: &mut Vec<ConfigIssue>, entries: Vec<toml::Value>) -> Vec<Location> {
+    let mut locations = Vec::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        match entry.try_into::<Location>() {
+            Ok(location) => {
+                let [latitude, longitude] = location.coordinates;
+                let coordinates = [latitude.clamp(-90.0, 90.0), longitude.clamp(-180.0, 180.0)];
+                if coordinates != location.coordinates {
+                    issues.push(ConfigIssue::error(format!("locations[{index}] coordinates {:?} clamped to {coordinates:?}", location.coordinates)));
+                }
+                locations.push(Location { name: location.name.trim().to_string(), coordinates });
+            }
+            Err(e) => issues.push(ConfigIssue::error(format!("locations[{index}]: {}; skipped", e.message().trim()))),
+        }
+    }
+    if locations.is_empty() {
+        locations.push(Location { name: "Home".to_string(), coordinates: [0.0, 0.0] });
+    }
+    locations
 }
 
 /// Allowed range for `calendar_weeks_to_show`. Shared by the startup loader and
@@ -119,79 +320,281 @@ fn parse_config_bool(text: &str) -> bool {
 pub const CALENDAR_WEEKS_MIN: usize = 6;
 pub const CALENDAR_WEEKS_MAX: usize = 520;
 
-pub fn get_check_and_set_config() -> Config {
-    let config_path = PathBuf::from("taskdeck_data").join(PathBuf::from("userconfig.toml"));
-    let extracted = read_config(&config_path);
-
-    let config = Config {
-        window_size_startup: extracted
-            .get("window_size_startup")
-            .and_then(|v| {
-                v.trim_matches(|c| c == '[' || c == ']') // remove brackets if present
-                    .split(',')
-                    .map(|s| s.trim().parse::<f32>().ok())
-                    .collect::<Option<Vec<_>>>() // only succeeds if both parse correctly
-                    .and_then(|nums| {
-                        if nums.len() == 2 {
-                            Some([nums[0], nums[1]])
-                        } else {
-                            None
-                        }
-                    })
-            })
-            .filter(|v| !v.iter().any(|x| x < &200.0))
-            .unwrap_or([1280.0, 720.0]),
-        start_in_fullscreen: extracted
-            .get("start_in_fullscreen")
-            .map(|s| parse_config_bool(s))
-            .unwrap_or(false),
-        enable_fps_counter: extracted
-            .get("enable_fps_counter")
-            .map(|s| parse_config_bool(s))
-            .unwrap_or(false),
-        three_day_weather: extracted
-            .get("three_day_weather")
-            .map(|s| parse_config_bool(s))
-            .unwrap_or(false),
-        background: extracted
-            .get("background")
-            .unwrap_or(&"".to_string()).to_string(),
-        coordinates: extracted
-            .get("coordinates")
-            .and_then(|v| {
-                v.trim_matches(|c| c == '[' || c == ']') // remove brackets if present
-                    .split(',')
-                    .map(|s| s.trim().parse::<f32>().ok())
-                    .collect::<Option<Vec<_>>>() // only succeeds if both parse correctly
-                    .and_then(|nums| {
-                        if nums.len() == 2 {
-                            Some([nums[0], nums[1]])
-                        } else {
-                            None
-                        }
-                    })
-            })
-            .unwrap_or([0.0, 0.0]),
-        calendar_weeks_to_show: extracted
-            .get("calendar_weeks_to_show")
-            .and_then(|n| n.parse::<usize>().ok().map(|x| x.clamp(CALENDAR_WEEKS_MIN, CALENDAR_WEEKS_MAX)))
-            .unwrap_or(100),
-        background_image_tint_percent: extracted
-            .get("background_image_tint_percent")
-            .and_then(|n| n.parse::<u32>().ok().and_then(|x| Some(x.clamp(1, 100))))
-            .unwrap_or(30),
-        selected_monitor_name: extracted
-            .get("selected_monitor_name")
-            .unwrap_or(&"".to_string()).to_string(),
-        selected_colorscheme_id: extracted
-            .get("selected_colorscheme_id")
-            .and_then(|n| n.parse::<u32>().ok().and_then(|x| Some(x.clamp(0, 200000))))
-            .unwrap_or(0),
+/// Upper bound for `archive_past_events_after_days` (0 disables the sweep).
+/// Shared with `TaskApp::set_archive_events_after` like the week bounds above.
+pub const ARCHIVE_EVENTS_AFTER_DAYS_MAX: u32 = 3650;
+
+/// Checks every value of `file`, clamping or falling back to the default
+/// where it can't be used, and says so in `issues`.
+fn validate_config(file: ConfigFile, issues: &mut Vec<ConfigIssue>) -> Config {
+    if file.config_version > i64::from(CONFIG_VERSION) {
+        issues.push(ConfigIssue::warning(format!(
+            "config_version = {} is from a newer TaskDeck (this one writes {CONFIG_VERSION}); settings it doesn't know are ignored",
+            file.config_version
+        )));
+    }
+
+    let locations = validate_locations(issues, file.locations);
+    let weather_location = if locations.iter().any(|location| location.name == file.weather_location) {
+        file.weather_location
+    } else {
+        if !file.weather_location.is_empty() {
+            issues.push(ConfigIssue::error(format!(
+                "weather_location = \"{}\" is not one of the locations; using \"{}\"",
+                file.weather_location, locations[0].name
+            )));
+        }
+        locations[0].name.clone()
+    };
+
+    let window_size_startup = if file.window_size_startup.iter().all(|x| *x >= 200.0) {
+        file.window_size_startup
+    } else {
+        issues.push(ConfigIssue::error(format!(
+            "window_size_startup = {:?} is smaller than 200 × 200; using {DEFAULT_WINDOW_SIZE:?}",
+            file.window_size_startup
+        )));
+        DEFAULT_WINDOW_SIZE
     };
 
-    write_normalized_config(&config_path, &config);
+    let weather_window_hours = match u32::try_from(file.weather_window_hours) {
+        Ok(hours) if AGGREGATION_WINDOWS.contains(&hours) => hours,
+        _ => {
+            issues.push(ConfigIssue::error(format!(
+                "weather_window_hours = {} is not one of {:?}; using 2",
+                file.weather_window_hours, AGGREGATION_WINDOWS
+            )));
+            2
+        }
+    };
+
+    let alert_defaults = AlertRules::default();
+    Config {
+        start_in_fullscreen: file.start_in_fullscreen,
+        locations,
+        weather_location,
+        background: file.background,
+        enable_fps_counter: file.enable_fps_counter,
+        window_size_startup,
+        calendar_weeks_to_show: clamp_setting(issues, "calendar_weeks_to_show", file.calendar_weeks_to_show, CALENDAR_WEEKS_MIN as i64..=CALENDAR_WEEKS_MAX as i64) as usize,
+        selected_monitor_name: file.selected_monitor_name,
+        selected_colorscheme_id: clamp_setting(issues, "selected_colorscheme_id", file.selected_colorscheme_id, 0..=200000) as u32,
+        selected_theme_id: clamp_setting(issues, "selected_theme_id", file.selected_theme_id, 0..=200000) as u32,
+        three_day_weather: file.three_day_weather,
+        weather_details_row: file.weather_details_row,
+        calendar_weather: file.calendar_weather,
+        night_bands: file.night_bands,
+        night_dimming: file.night_dimming,
+        item_cues: file.item_cues,
+        alert_rules: AlertRules {
+            frost_below: threshold_setting(issues, "alert_frost_below", &file.alert_frost_below, alert_defaults.frost_below),
+            rain_above: threshold_setting(issues, "alert_rain_above", &file.alert_rain_above, alert_defaults.rain_above),
+            gusts_above: threshold_setting(issues, "alert_gusts_above", &file.alert_gusts_above, alert_defaults.gusts_above),
+            thunderstorms: file.alert_thunderstorms,
+            horizon_hours: clamp_setting(issues, "alert_horizon_hours", file.alert_horizon_hours, wide(1..=MAX_ALERT_HORIZON_HOURS)) as u32,
+        },
+        weather_provider: choose_setting(issues, "weather_provider", &file.weather_provider, &WeatherProviderKind::ALL, WeatherProviderKind::key),
+        weather_refresh: RefreshPolicy {
+            interval_minutes: clamp_setting(issues, "weather_refresh_minutes", file.weather_refresh_minutes, wide(weather::REFRESH_MINUTES)) as u32,
+            attempts: clamp_setting(issues, "weather_retry_attempts", file.weather_retry_attempts, wide(weather::RETRY_ATTEMPTS)) as u32,
+            backoff_seconds: clamp_setting(issues, "weather_retry_backoff_seconds", file.weather_retry_backoff_seconds, wide(weather::RETRY_BACKOFF_SECONDS)) as u32,
+        },
+        units: Units {
+            temperature: choose_setting(issues, "temperature_unit", &file.temperature_unit, &TemperatureUnit::ALL, TemperatureUnit::key),
+            wind: choose_setting(issues, "wind_speed_unit", &file.wind_speed_unit, &WindUnit::ALL, WindUnit::key),
+            precipitation: choose_setting(issues, "precipitation_unit", &file.precipitation_unit, &PrecipitationUnit::ALL, PrecipitationUnit::key),
+        },
+        temperature_rounding: choose_setting(issues, "temperature_rounding", &file.temperature_rounding, &Rounding::ALL, Rounding::key),
+        weather_window_hours,
+        forecast_days: clamp_setting(issues, "forecast_days", file.forecast_days, 1..=MAX_FORECAST_DAYS as i64) as usize,
+        background_image_tint_percent: clamp_setting(issues, "background_image_tint_percent", file.background_image_tint_percent, 1..=100) as u32,
+        archive_past_events_after_days: clamp_setting(issues, "archive_past_events_after_days", file.archive_past_events_after_days, wide(0..=ARCHIVE_EVENTS_AFTER_DAYS_MAX)) as u32,
+    }
+}
+
+/// The config in `contents` (of `userconfig.toml`) and what was wrong with
+/// it, without touching the file. `Err` if it isn't TOML at all.
+pub fn check_config(contents: &str) -> Result<(Config, Vec<ConfigIssue>), ConfigIssue> {
+    check_profile_config(contents, None)
+}
+
+/// As `check_config`, with a profile's `overlay` on top: every key the
+/// overlay sets replaces the one in `contents`. `Err` if either isn't TOML.
+pub fn check_profile_config(contents: &str, overlay: Option<&str>) -> Result<(Config, Vec<ConfigIssue>), ConfigIssue> {
+    let mut table = contents
+        .parse::<toml::Table>()
+        .map_err(|e| ConfigIssue::error(format!("userconfig.toml is not valid TOML. {e}")))?;
+    migrate_config(&mut table);
+    if let Some(overlay) = overlay {
+        let overlay = overlay
+            .parse::<toml::Table>()
+            .map_err(|e| ConfigIssue::error(format!("The profile is not valid TOML. {e}")))?;
+        table.extend(overlay);
+    }
+
+    let mut issues = Vec::new();
+    let file = read_config(table, &mut issues);
+    let config = validate_config(file, &mut issues);
+    Ok((config, issues))
+}
+
+/// `issues` one per line, for the error window.
+pub fn issues_text(issues: &[ConfigIssue]) -> String {
+    issues.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n")
+}
 
-    config
+/// Where the settings come from: `userconfig.toml` (or `--config`) and,
+/// with `--profile`, that profile's overlay, whose keys win.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigFiles {
+    pub base: PathBuf,
+    pub profile: Option<PathBuf>,
+}
+
+impl ConfigFiles {
+    pub fn new(base: PathBuf) -> Self {
+        Self { base, profile: None }
+    }
+
+    /// The file a changed `key` is saved to: the profile's overlay if it
+    /// sets `key` (otherwise the change would be hidden by it), else the
+    /// base file.
+    pub fn file_for(&self, key: &str) -> &PathBuf {
+        match &self.profile {
+            Some(profile) if overlay_keys(&fs::read_to_string(profile).unwrap_or_default()).contains(key) => profile,
+            _ => &self.base,
+        }
+    }
+}
+
+/// The keys set in a profile overlay; none if it isn't TOML.
+fn overlay_keys(contents: &str) -> HashSet<String> {
+    contents.parse::<toml::Table>().map(|table| table.into_iter().map(|(key, _)| key).collect()).unwrap_or_default()
+}
+
+/// Load `userconfig.toml` from `files.base` (see `paths::data_paths`),
+/// creating it if there is none, lay the profile over it, check every value
+/// and write the normalised files back: each key to the file it came from.
+/// Also returns what was wrong, for the error window. A file that isn't TOML
+/// is not rewritten, so the user can fix it; meanwhile a broken profile is
+/// left out and a broken base file means the defaults. In `read_only` mode
+/// nothing is created or written.
+pub fn get_check_and_set_config(files: &ConfigFiles, read_only: bool) -> (Config, Vec<ConfigIssue>) {
+    let contents = match fs::read_to_string(&files.base) {
+        Ok(thing) => thing,
+        Err(_) => {
+            if !read_only {
+                if let Some(parent) = files.base.parent() {
+                    let _ = fs::create_dir_all(parent);
+                }
+                let _ = fs::File::create(&files.base);
+            }
+            String::new()
+        },
+    };
+
+    let mut profile_issues = Vec::new();
+    let overlay = files.profile.as_ref().and_then(|path| {
+        let read = fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|overlay| {
+            overlay.parse::<toml::Table>().map(|_| overlay).map_err(|e| format!("It is not valid TOML. {e}"))
+        });
+        read.map_err(|e| profile_issues.push(ConfigIssue::error(format!("Profile {}: {e} It is left out.", path.display()))))
+            .ok()
+    });
+
+    match check_profile_config(&contents, overlay.as_deref()) {
+        Ok((config, issues)) => {
+            if !read_only {
+                let profile_keys = overlay_keys(overlay.as_deref().unwrap_or_default());
+                write_normalized_config(&files.base, &config, |key| !profile_keys.contains(key));
+                if let Some(profile) = files.profile.as_ref().filter(|_| overlay.is_some()) {
+                    write_normalized_config(profile, &config, |key| profile_keys.contains(key));
+                }
+            }
+            profile_issues.extend(issues);
+            (config, profile_issues)
+        }
+        Err(issue) => {
+            let mut issues = vec![ConfigIssue { message: format!("{} It is left alone and the defaults are used.", issue.message), ..issue }];
+            let config = validate_config(ConfigFile::default(), &mut issues);
+            profile_issues.extend(issues);
+            (config, profile_issues)
+        }
+    }
+}
+
+/// How often `ConfigWatcher::poll` looks at the file.
+const CONFIG_POLL_INTERVAL: time::Duration = time::Duration::from_secs(1);
+
+/// Notices edits made to `userconfig.toml` while the app runs, by polling
+/// its modification time and size. The app's own writes are passed to
+/// `wrote`, so they don't come back as edits.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    /// The contents as last read or written by the app.
+    known: String,
+    stamp: Option<(time::SystemTime, u64)>,
+    /// A changed stamp seen once; the file is read when it is seen again,
+    /// so an editor halfway through saving isn't read.
+    pending: Option<(time::SystemTime, u64)>,
+    last_poll: Instant,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let known = fs::read_to_string(&path).unwrap_or_default();
+        let stamp = file_stamp(&path);
+        Self { path, known, stamp, pending: None, last_poll: Instant::now() }
+    }
+
+    /// Records `contents` as written by the app itself.
+    pub fn wrote(&mut self, contents: String) {
+        self.known = contents;
+        self.stamp = file_stamp(&self.path);
+        self.pending = None;
+    }
+
+    /// The contents as last read or written by the app.
+    pub fn contents(&self) -> &str {
+        &self.known
+    }
+
+    /// `check`, at most once per `CONFIG_POLL_INTERVAL`.
+    pub fn poll(&mut self) -> Option<String> {
+        if self.last_poll.elapsed() < CONFIG_POLL_INTERVAL {
+            return None;
+        }
+        self.last_poll = Instant::now();
+        self.check()
+    }
+
+    /// The new contents if someone else has changed the file and it has
+    /// stayed the same since the previous check. A missing file (an editor
+    /// replacing it) is waited out.
+    fn check(&mut self) -> Option<String> {
+        let stamp = file_stamp(&self.path)?;
+        if self.stamp == Some(stamp) {
+            self.pending = None;
+            return None;
+        }
+        if self.pending != Some(stamp) {
+            self.pending = Some(stamp);
+            return None;
+        }
+        let contents = fs::read_to_string(&self.path).ok()?;
+        self.stamp = Some(stamp);
+        self.pending = None;
+        if contents == self.known {
+            return None;
+        }
+        self.known = contents.clone();
+        Some(contents)
+    }
+}
+
+fn file_stamp(path: &PathBuf) -> Option<(time::SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
 }
 
 /// Persist the normalized/clamped config back to disk using `toml_edit`, so the
@@ -201,24 +604,59 @@ pub fn get_check_and_set_config() -> Config {
 /// in the file; it only updates the keys we own, and writes numbers as real
 /// integers/float-arrays rather than strings. A missing or unparseable file
 /// falls back to a fresh document (the same self-healing the old code did).
-fn write_normalized_config(path: &PathBuf, config: &Config) {
-    use toml_edit::{value, DocumentMut};
+/// Only the keys `owns` accepts are written, so the base file and a profile
+/// overlay each keep their own.
+fn write_normalized_config(path: &PathBuf, config: &Config, owns: impl Fn(&str) -> bool) {
+    use toml_edit::{value, DocumentMut, Item};
 
     let mut doc = fs::read_to_string(path)
         .ok()
         .and_then(|c| c.parse::<DocumentMut>().ok())
         .unwrap_or_default();
 
-    doc["start_in_fullscreen"] = value(config.start_in_fullscreen);
-    doc["coordinates"] = value(crate::utilities::float_pair_array(config.coordinates));
-    doc["background"] = value(config.background.clone());
-    doc["enable_fps_counter"] = value(config.enable_fps_counter);
-    doc["window_size_startup"] = value(crate::utilities::float_pair_array(config.window_size_startup));
-    doc["calendar_weeks_to_show"] = value(config.calendar_weeks_to_show as i64);
-    doc["selected_monitor_name"] = value(config.selected_monitor_name.clone());
-    doc["selected_colorscheme_id"] = value(config.selected_colorscheme_id as i64);
-    doc["three_day_weather"] = value(config.three_day_weather);
-    doc["background_image_tint_percent"] = value(config.background_image_tint_percent as i64);
+    if owns("locations") {
+        // `locations` replaced the single `coordinates` pair.
+        doc.remove("coordinates");
+    }
+    let mut set = |key: &str, item: Item| {
+        if owns(key) {
+            doc[key] = item;
+        }
+    };
+    set("config_version", value(i64::from(CONFIG_VERSION)));
+    set("start_in_fullscreen", value(config.start_in_fullscreen));
+    set("locations", value(weather::locations_to_toml(&config.locations)));
+    set("weather_location", value(config.weather_location.clone()));
+    set("background", value(config.background.clone()));
+    set("enable_fps_counter", value(config.enable_fps_counter));
+    set("window_size_startup", value(crate::utilities::float_pair_array(config.window_size_startup)));
+    set("calendar_weeks_to_show", value(config.calendar_weeks_to_show as i64));
+    set("selected_monitor_name", value(config.selected_monitor_name.clone()));
+    set("selected_colorscheme_id", value(config.selected_colorscheme_id as i64));
+    set("selected_theme_id", value(config.selected_theme_id as i64));
+    set("three_day_weather", value(config.three_day_weather));
+    set("weather_details_row", value(config.weather_details_row));
+    set("calendar_weather", value(config.calendar_weather));
+    set("night_bands", value(config.night_bands));
+    set("night_dimming", value(config.night_dimming));
+    set("item_cues", value(config.item_cues));
+    set("alert_frost_below", value(alerts::threshold_to_toml(config.alert_rules.frost_below)));
+    set("alert_rain_above", value(alerts::threshold_to_toml(config.alert_rules.rain_above)));
+    set("alert_gusts_above", value(alerts::threshold_to_toml(config.alert_rules.gusts_above)));
+    set("alert_thunderstorms", value(config.alert_rules.thunderstorms));
+    set("alert_horizon_hours", value(config.alert_rules.horizon_hours as i64));
+    set("weather_provider", value(config.weather_provider.key()));
+    set("weather_refresh_minutes", value(config.weather_refresh.interval_minutes as i64));
+    set("weather_retry_attempts", value(config.weather_refresh.attempts as i64));
+    set("weather_retry_backoff_seconds", value(config.weather_refresh.backoff_seconds as i64));
+    set("temperature_unit", value(config.units.temperature.key()));
+    set("wind_speed_unit", value(config.units.wind.key()));
+    set("precipitation_unit", value(config.units.precipitation.key()));
+    set("temperature_rounding", value(config.temperature_rounding.key()));
+    set("weather_window_hours", value(config.weather_window_hours as i64));
+    set("forecast_days", value(config.forecast_days as i64));
+    set("background_image_tint_percent", value(config.background_image_tint_percent as i64));
+    set("archive_past_events_after_days", value(config.archive_past_events_after_days as i64));
 
     let _ = fs::write(path, doc.to_string());
 }
@@ -226,15 +664,49 @@ fn write_normalized_config(path: &PathBuf, config: &Config) {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
     pub start_in_fullscreen: bool,
-    pub coordinates: [f32; 2],
+    /// Named places to forecast; never empty.
+    pub locations: Vec<Location>,
+    /// Name of the location the forecast panel shows, one of `locations`.
+    pub weather_location: String,
     pub background: String,
     pub enable_fps_counter: bool,
     pub window_size_startup: [f32; 2],
     pub calendar_weeks_to_show: usize,
     pub selected_monitor_name: String,
     pub selected_colorscheme_id: u32,
+    /// Key of the UI theme in `themes.json`; 0 and 1 are the built-in dark and light ones.
+    pub selected_theme_id: u32,
     pub three_day_weather: bool,
+    /// Precipitation and wind under each forecast slot.
+    pub weather_details_row: bool,
+    /// Each calendar day's forecast (icon, high and low) in its cell.
+    pub calendar_weather: bool,
+    /// Shade forecast slots between sunset and sunrise.
+    pub night_bands: bool,
+    /// Dim the whole UI after sunset at the shown location.
+    pub night_dimming: bool,
+    /// Dots per importance level and a ring for events on calendar items, so
+    /// they don't rely on the tints alone.
+    pub item_cues: bool,
+    /// Weather alert thresholds, stored as `alert_frost_below`,
+    /// `alert_rain_above`, `alert_gusts_above` (each a number or `"off"`),
+    /// `alert_thunderstorms` and `alert_horizon_hours`.
+    pub alert_rules: AlertRules,
+    pub weather_provider: WeatherProviderKind,
+    /// When the weather thread refetches and retries.
+    pub weather_refresh: RefreshPolicy,
+    /// Forecast display units, stored as `temperature_unit`, `wind_speed_unit`
+    /// and `precipitation_unit`.
+    pub units: Units,
+    pub temperature_rounding: Rounding,
+    /// Hours per forecast slot, one of `weather::AGGREGATION_WINDOWS`.
+    pub weather_window_hours: u32,
+    /// Days in the forecast panel, `1..=weather::MAX_FORECAST_DAYS`.
+    pub forecast_days: usize,
     pub background_image_tint_percent: u32,
+    /// Events whose deadline passed more than this many days ago are moved to
+    /// the archive automatically. `0` keeps them until completed or deleted.
+    pub archive_past_events_after_days: u32,
 }
 
 pub struct AppState<'a> {
@@ -615,27 +1087,30 @@ impl<'a> App<'a> {
     }
 }
 
+/// Which of `monitors` (their names, in the system's order) `wanted` means:
+/// the one with that name, else the `wanted`th counting from 1, as in
+/// `--monitor 3`, else the first.
+fn monitor_index(monitors: &[Option<String>], wanted: &str) -> usize {
+    monitors
+        .iter()
+        .position(|name| name.as_deref() == Some(wanted))
+        .or_else(|| wanted.trim().parse::<usize>().ok().filter(|n| (1..=monitors.len()).contains(n)).map(|n| n - 1))
+        .unwrap_or(0)
+}
+
 impl ApplicationHandler for App<'_> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         let window = event_loop
             .create_window({
-                let available_monitors: Vec<String> = event_loop
-                    .available_monitors()
-                    .flat_map(|m| m.name())
-                    .collect();
-
-                self.task_app.monitor_options = available_monitors;
-
-                let target_monitor = match event_loop
-                    .available_monitors()
-                    .find(|m| {
-                        if let Some(name) = m.name() {
-                            name == self.selected_monitor_name
-                        } else { false }
-                    }) {
-                        None => event_loop.available_monitors().nth(0).unwrap(),
-                        Some(monitor) => monitor,
-                    };
+                let monitors: Vec<_> = event_loop.available_monitors().collect();
+                let monitor_names: Vec<Option<String>> = monitors.iter().map(|m| m.name()).collect();
+
+                self.task_app.monitor_options = monitor_names.iter().flatten().cloned().collect();
+
+                let target_monitor = match monitors.get(monitor_index(&monitor_names, &self.selected_monitor_name)) {
+                    None => event_loop.available_monitors().nth(0).unwrap(),
+                    Some(monitor) => monitor.clone(),
+                };
 
                 let monitor_position = target_monitor.position();
                 let monitor_size = target_monitor.size();
@@ -658,13 +1133,18 @@ impl ApplicationHandler for App<'_> {
 
                 let minimum_size = LogicalSize::new(200.0, 200.0);
 
-                Window::default_attributes()
+                let attributes = Window::default_attributes()
                     .with_title(window_title)
                     .with_window_icon(Some(icon_data.clone()))
-                    .with_taskbar_icon(Some(icon_data))
                     .with_position(window_position)
                     .with_min_inner_size(minimum_size)
-                    .with_active(false)
+                    .with_active(false);
+
+                // The taskbar icon is a Windows-only attribute.
+                #[cfg(windows)]
+                let attributes = attributes.with_taskbar_icon(Some(icon_data));
+
+                attributes
             })
             .unwrap();
         pollster::block_on(self.set_window(window));
@@ -770,29 +1250,251 @@ mod tests {
     fn sample_config() -> Config {
         Config {
             start_in_fullscreen: true,
-            coordinates: [60.17, 24.94],
+            locations: vec![
+                Location { name: "Helsinki".to_string(), coordinates: [60.17, 24.94] },
+                Location { name: "Berlin".to_string(), coordinates: [52.52, 13.41] },
+            ],
+            weather_location: "Berlin".to_string(),
             background: "pic.png".to_string(),
             enable_fps_counter: false,
             window_size_startup: [1280.0, 720.0],
             calendar_weeks_to_show: 100,
             selected_monitor_name: "Main".to_string(),
             selected_colorscheme_id: 3,
+            selected_theme_id: 1,
             three_day_weather: true,
+            weather_details_row: true,
+            calendar_weather: false,
+            night_bands: true,
+            night_dimming: true,
+            item_cues: true,
+            alert_rules: AlertRules { frost_below: Some(-5.0), rain_above: None, gusts_above: Some(18.0), thunderstorms: false, horizon_hours: 36 },
+            weather_provider: WeatherProviderKind::MetNorway,
+            weather_refresh: RefreshPolicy { interval_minutes: 30, attempts: 5, backoff_seconds: 4 },
+            units: Units { temperature: TemperatureUnit::Fahrenheit, wind: WindUnit::Knots, precipitation: PrecipitationUnit::Millimetres },
+            temperature_rounding: Rounding::Tenths,
+            weather_window_hours: 3,
+            forecast_days: 5,
             background_image_tint_percent: 30,
+            archive_past_events_after_days: 7,
         }
     }
 
     #[test]
     fn parse_config_bool_only_true_for_real_truthy_values() {
         for t in ["true", "TRUE", " True ", "1", "yes", "on"] {
-            assert!(parse_config_bool(t), "{t:?} should parse true");
+            assert_eq!(parse_config_bool(t), Some(true), "{t:?} should parse true");
+        }
+        for f in ["false", "0", "no", "off"] {
+            assert_eq!(parse_config_bool(f), Some(false), "{f:?} should parse false");
         }
         // The old contains("t") heuristic wrongly returned true for these.
-        for f in ["false", "east", "set", "", "0", "no", "off"] {
-            assert!(!parse_config_bool(f), "{f:?} should parse false");
+        for neither in ["east", "set", ""] {
+            assert_eq!(parse_config_bool(neither), None, "{neither:?} is not a flag");
         }
     }
 
+    #[test]
+    fn locations_come_from_the_list_or_the_old_coordinates() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("userconfig.toml");
+
+        // What the writer produces reads back, bad entries skipped and reported.
+        fs::write(
+            &path,
+            "locations = [{ name = \"Home\", coordinates = [59.91, 10.75] }, { name = \"Broken\" }, \
+             { name = \"Berlin\", coordinates = [52.52, 13.41] }]\n",
+        )
+        .unwrap();
+        let (config, issues) = get_check_and_set_config(&ConfigFiles::new(path.clone()), false);
+        assert_eq!(config.locations, vec![
+            Location { name: "Home".to_string(), coordinates: [59.91, 10.75] },
+            Location { name: "Berlin".to_string(), coordinates: [52.52, 13.41] },
+        ]);
+        assert_eq!(issues.len(), 1, "{issues:?}");
+        assert!(issues[0].message.starts_with("locations[1]: missing field `coordinates`"), "{}", issues[0]);
+
+        // `[[locations]]` tables work as well.
+        fs::write(&path, "[[locations]]\nname = \"Office\"\ncoordinates = [60.17, 24.94]\n").unwrap();
+        assert_eq!(get_check_and_set_config(&ConfigFiles::new(path.clone()), false).0.locations[0].name, "Office");
+
+        // A version 0 config's single pair becomes "Home", once.
+        fs::write(&path, "coordinates = [60.17, 24.94]\n").unwrap();
+        assert!(config_needs_migration(&path));
+        let (config, issues) = get_check_and_set_config(&ConfigFiles::new(path.clone()), false);
+        assert!(!config_needs_migration(&path));
+        assert_eq!(config.locations, vec![Location { name: "Home".to_string(), coordinates: [60.17, 24.94] }]);
+        assert_eq!(config.weather_location, "Home");
+        assert!(issues.is_empty(), "{issues:?}");
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(!written.contains("\ncoordinates") && written.contains("config_version = 1"), "{written}");
+        assert_eq!(get_check_and_set_config(&ConfigFiles::new(path.clone()), false).0.locations, config.locations);
+    }
+
+    #[test]
+    fn only_an_old_config_needs_migrating() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("userconfig.toml");
+
+        assert!(!config_needs_migration(&path), "a missing file is a fresh install");
+        fs::write(&path, "").unwrap();
+        assert!(!config_needs_migration(&path), "so is an empty one");
+        fs::write(&path, "background = [unclosed").unwrap();
+        assert!(!config_needs_migration(&path), "a file that isn't TOML is never rewritten");
+        fs::write(&path, "background = \"a.jpg\"\n").unwrap();
+        assert!(config_needs_migration(&path));
+        fs::write(&path, format!("config_version = {CONFIG_VERSION}\nbackground = \"a.jpg\"\n")).unwrap();
+        assert!(!config_needs_migration(&path));
+    }
+
+    #[test]
+    fn weather_refresh_is_read_and_clamped() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("userconfig.toml");
+
+        fs::write(&path, "weather_refresh_minutes = 1\nweather_retry_attempts = 4\n").unwrap();
+        let (config, issues) = get_check_and_set_config(&ConfigFiles::new(path.clone()), false);
+        assert_eq!(config.weather_refresh, RefreshPolicy { interval_minutes: 5, attempts: 4, backoff_seconds: 1 });
+        assert_eq!(issues, vec![ConfigIssue::error("weather_refresh_minutes = 1 clamped to 5")]);
+        assert!(fs::read_to_string(&path).unwrap().contains("weather_refresh_minutes = 5"));
+    }
+
+    #[test]
+    fn problems_with_the_file_are_reported_not_silently_fixed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("userconfig.toml");
+
+        fs::write(
+            &path,
+            "calendar_weeks_to_show = 9999\n\
+             forecast_days = \"4\"\n\
+             night_bands = \"off\"\n\
+             enable_fps_counter = 3\n\
+             temperature_unit = \"kelvin\"\n\
+             alert_frost_below = \"cold\"\n\
+             weather_location = \"Atlantis\"\n\
+             calender_weeks = 12\n",
+        )
+        .unwrap();
+        let (config, issues) = get_check_and_set_config(&ConfigFiles::new(path.clone()), false);
+        let messages: Vec<String> = issues.iter().map(ToString::to_string).collect();
+
+        assert_eq!(config.calendar_weeks_to_show, CALENDAR_WEEKS_MAX);
+        assert!(messages.contains(&"Error: calendar_weeks_to_show = 9999 clamped to 520".to_string()), "{messages:#?}");
+        // Quoted numbers and flags, as older versions wrote them, just work.
+        assert_eq!((config.forecast_days, config.night_bands), (4, false));
+        assert!(!config.enable_fps_counter);
+        assert!(messages.iter().any(|m| m.starts_with("Error: enable_fps_counter: invalid type: integer `3`, expected a boolean")), "{messages:#?}");
+        assert_eq!(config.units.temperature, TemperatureUnit::Celsius);
+        assert!(messages.contains(&"Error: temperature_unit = \"kelvin\" is not one of \"celsius\", \"fahrenheit\"; using \"celsius\"".to_string()), "{messages:#?}");
+        assert_eq!(config.alert_rules.frost_below, AlertRules::default().frost_below);
+        assert!(messages.iter().any(|m| m.starts_with("Error: alert_frost_below = \"cold\" is neither a number nor \"off\"")), "{messages:#?}");
+        assert_eq!(config.weather_location, "Home");
+        assert!(messages.contains(&"Warning: Unknown key \"calender_weeks\" is ignored".to_string()), "{messages:#?}");
+        assert_eq!(issues.len(), 6, "{messages:#?}");
+
+        // Unknown keys are reported every time rather than dropped.
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.contains("calender_weeks = 12") && written.contains("calendar_weeks_to_show = 520"), "{written}");
+
+        // A file from a newer version is read as far as possible.
+        fs::write(&path, "config_version = 7\n").unwrap();
+        assert_eq!(get_check_and_set_config(&ConfigFiles::new(path.clone()), false).1[0].severity, Severity::Warning);
+
+        // Not TOML at all: defaults, and the file is left for the user to fix.
+        fs::write(&path, "calendar_weeks_to_show = [\n").unwrap();
+        let (config, issues) = get_check_and_set_config(&ConfigFiles::new(path.clone()), false);
+        assert_eq!(config.calendar_weeks_to_show, 100);
+        assert!(issues.len() == 1 && issues[0].message.starts_with("userconfig.toml is not valid TOML"), "{issues:?}");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "calendar_weeks_to_show = [\n");
+    }
+
+    #[test]
+    fn a_profile_overlays_the_base_file_and_keeps_its_own_keys() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path().join("userconfig.toml");
+        let wall = tmp.path().join("profiles").join("wall.toml");
+        fs::create_dir_all(wall.parent().unwrap()).unwrap();
+        fs::write(&base, "start_in_fullscreen = false\ncalendar_weeks_to_show = 12\n").unwrap();
+        fs::write(&wall, "# the kitchen display\nstart_in_fullscreen = true\nselected_monitor_name = \"3\"\ncalendar_weeks_to_show = 9999\n").unwrap();
+        let files = ConfigFiles { base: base.clone(), profile: Some(wall.clone()) };
+
+        let (config, issues) = get_check_and_set_config(&files, false);
+        assert!(config.start_in_fullscreen);
+        assert_eq!(config.selected_monitor_name, "3");
+        assert_eq!(config.calendar_weeks_to_show, CALENDAR_WEEKS_MAX);
+        assert_eq!(issues.len(), 1, "{issues:?}");
+
+        // Each file is normalised with only its own keys.
+        let written = fs::read_to_string(&base).unwrap();
+        assert!(written.contains("start_in_fullscreen = false") && written.contains("calendar_weeks_to_show = 12"), "{written}");
+        assert!(!written.contains("selected_monitor_name") && written.contains("night_bands"), "{written}");
+        let overlay = fs::read_to_string(&wall).unwrap();
+        assert!(overlay.starts_with("# the kitchen display\n") && overlay.contains("calendar_weeks_to_show = 520"), "{overlay}");
+        assert!(!overlay.contains("config_version") && !overlay.contains("locations"), "{overlay}");
+        assert_eq!(files.file_for("start_in_fullscreen"), &wall);
+        assert_eq!(files.file_for("night_bands"), &base);
+
+        // A profile that is missing or broken is left out, with an error.
+        fs::write(&wall, "start_in_fullscreen = [\n").unwrap();
+        let (config, issues) = get_check_and_set_config(&files, false);
+        assert!(!config.start_in_fullscreen);
+        assert!(issues.len() == 1 && issues[0].message.contains("It is not valid TOML"), "{issues:?}");
+        assert_eq!(fs::read_to_string(&wall).unwrap(), "start_in_fullscreen = [\n");
+    }
+
+    #[test]
+    fn read_only_loading_writes_nothing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("userconfig.toml");
+
+        let (config, _) = get_check_and_set_config(&ConfigFiles::new(path.clone()), true);
+        assert_eq!(config.calendar_weeks_to_show, 100);
+        assert!(!path.exists());
+
+        fs::write(&path, "calendar_weeks_to_show = \"12\"\n").unwrap();
+        assert_eq!(get_check_and_set_config(&ConfigFiles::new(path.clone()), true).0.calendar_weeks_to_show, 12);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "calendar_weeks_to_show = \"12\"\n");
+    }
+
+    #[test]
+    fn monitors_are_picked_by_name_then_number() {
+        let monitors = [Some("DP-1".to_string()), None, Some("HDMI-1".to_string())];
+        assert_eq!(monitor_index(&monitors, "HDMI-1"), 2);
+        assert_eq!(monitor_index(&monitors, "3"), 2);
+        assert_eq!(monitor_index(&monitors, "2"), 1);
+        assert_eq!(monitor_index(&monitors, "4"), 0);
+        assert_eq!(monitor_index(&monitors, "0"), 0);
+        assert_eq!(monitor_index(&monitors, ""), 0);
+    }
+
+    #[test]
+    fn config_watcher_reports_outside_edits_once_settled_but_not_our_writes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("userconfig.toml");
+        fs::write(&path, "forecast_days = 3\n").unwrap();
+        let mut watcher = ConfigWatcher::new(path.clone());
+        assert_eq!(watcher.check(), None);
+
+        // An edit is read once it has been seen unchanged twice.
+        fs::write(&path, "forecast_days = 5\n# edited\n").unwrap();
+        assert_eq!(watcher.check(), None);
+        assert_eq!(watcher.check().as_deref(), Some("forecast_days = 5\n# edited\n"));
+        assert_eq!(watcher.check(), None);
+
+        // The app's own writes are not.
+        fs::write(&path, "forecast_days = 4\n").unwrap();
+        watcher.wrote("forecast_days = 4\n".to_string());
+        assert_eq!((watcher.check(), watcher.check()), (None, None));
+
+        // Nor is a file that is gone for the moment.
+        fs::remove_file(&path).unwrap();
+        assert_eq!((watcher.check(), watcher.check()), (None, None));
+        fs::write(&path, "forecast_days = 2\n").unwrap();
+        assert_eq!(watcher.check(), None);
+        assert_eq!(watcher.check().as_deref(), Some("forecast_days = 2\n"));
+    }
+
     #[test]
     fn write_normalized_config_preserves_comments_and_writes_typed_values() {
         let tmp = tempfile::tempdir().unwrap();
@@ -802,7 +1504,7 @@ mod tests {
         // runtime setters used to write.
         fs::write(&path, "# keep me\ncalendar_weeks_to_show = \"100\"\n").unwrap();
 
-        write_normalized_config(&path, &sample_config());
+        write_normalized_config(&path, &sample_config(), |_| true);
 
         let written = fs::read_to_string(&path).unwrap();
         // The comment survives (the old `toml::to_string` rewrite dropped it).
@@ -810,11 +1512,36 @@ mod tests {
 
         // Values come back with their real TOML types, not as quoted strings.
         let doc = written.parse::<toml_edit::DocumentMut>().unwrap();
+        assert_eq!(doc["config_version"].as_integer(), Some(i64::from(CONFIG_VERSION)));
         assert_eq!(doc["calendar_weeks_to_show"].as_integer(), Some(100));
         assert_eq!(doc["selected_colorscheme_id"].as_integer(), Some(3));
+        assert_eq!(doc["selected_theme_id"].as_integer(), Some(1));
         assert_eq!(doc["background_image_tint_percent"].as_integer(), Some(30));
+        assert_eq!(doc["archive_past_events_after_days"].as_integer(), Some(7));
         assert_eq!(doc["start_in_fullscreen"].as_bool(), Some(true));
-        assert!(doc["coordinates"].is_array(), "coordinates should be an array");
+        assert_eq!(doc["calendar_weather"].as_bool(), Some(false));
+        assert_eq!(doc["night_dimming"].as_bool(), Some(true));
+        assert_eq!(doc["item_cues"].as_bool(), Some(true));
+        assert_eq!(doc["alert_frost_below"].as_float(), Some(-5.0));
+        assert_eq!(doc["alert_rain_above"].as_str(), Some("off"));
+        assert_eq!(doc["alert_horizon_hours"].as_integer(), Some(36));
+        assert_eq!(doc["weather_provider"].as_str(), Some("met-norway"));
+        assert_eq!(doc["weather_refresh_minutes"].as_integer(), Some(30));
+        assert_eq!(doc["weather_retry_attempts"].as_integer(), Some(5));
+        assert_eq!(doc["weather_retry_backoff_seconds"].as_integer(), Some(4));
+        assert_eq!(doc["temperature_unit"].as_str(), Some("fahrenheit"));
+        assert_eq!(doc["wind_speed_unit"].as_str(), Some("kn"));
+        assert_eq!(doc["precipitation_unit"].as_str(), Some("mm"));
+        assert_eq!(doc["temperature_rounding"].as_str(), Some("tenths"));
+        assert_eq!(doc["weather_window_hours"].as_integer(), Some(3));
+        assert_eq!(doc["forecast_days"].as_integer(), Some(5));
+        assert!(doc.get("coordinates").is_none(), "coordinates is replaced by locations");
+        let locations = doc["locations"].as_array().expect("locations should be an array");
+        assert_eq!(locations.len(), 2);
+        let berlin = locations.get(1).and_then(|v| v.as_inline_table()).unwrap();
+        assert_eq!(berlin.get("name").and_then(|v| v.as_str()), Some("Berlin"));
+        assert_eq!(berlin.get("coordinates").and_then(|v| v.as_array()).map(|a| a.len()), Some(2));
+        assert_eq!(doc["weather_location"].as_str(), Some("Berlin"));
         assert!(doc["window_size_startup"].is_array(), "window size should be an array");
     }
 }
\ No newline at end of file
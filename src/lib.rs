@@ -4,4 +4,13 @@ pub mod utilities;
 pub mod weather;
 pub mod calendarwidgets;
 pub mod initialization;
-pub mod color;
\ No newline at end of file
+pub mod color;
+pub mod quantize;
+pub mod theme;
+pub mod backup;
+pub mod paths;
+pub mod cli;
+pub mod units;
+pub mod solar;
+pub mod alerts;
+pub mod gazetteer;
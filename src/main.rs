@@ -1,8 +1,8 @@
 #![cfg_attr(all(windows, not(debug_assertions)), windows_subsystem = "windows")]
 
-use std::{fs, path::PathBuf};
+use std::fs;
 use mimalloc::MiMalloc;
-use task_deck::{color::{self, ColorScheme}, initialization::{App, Config, get_check_and_set_config}, utilities, tasks::{self, Active}, ui::{TaskApp, TaskAppConfig}, weather::get_weather};
+use task_deck::{backup::{self, SnapshotKind}, cli, color::{self, ColorScheme}, theme, initialization::{self, App, Config, ConfigFiles, config_needs_migration, get_check_and_set_config}, paths::{self, DataPaths}, utilities, tasks::{self, Active}, ui::{TaskApp, TaskAppConfig}, weather::{WeatherEnvironment, get_weather}};
 use winit::event_loop::{ControlFlow, EventLoop};
 
 #[global_allocator]
@@ -16,37 +16,116 @@ fn main() {
 }
 
 async fn run() {    
+    // Overrides for this launch only; see `cli::USAGE`.
+    let (launch, arg_problems) = cli::parse_args(std::env::args_os().skip(1));
+    if launch.help || !arg_problems.is_empty() {
+        cli::attach_parent_console();
+    }
+    if launch.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    for problem in &arg_problems {
+        eprintln!("{problem}");
+    }
+
     let event_loop = EventLoop::new().unwrap();
     let proxy = event_loop.create_proxy();
 
     event_loop.set_control_flow(ControlFlow::Wait);
 
-    let Config { start_in_fullscreen, coordinates, background, enable_fps_counter, window_size_startup, calendar_weeks_to_show, selected_monitor_name, mut selected_colorscheme_id, three_day_weather, background_image_tint_percent } = get_check_and_set_config();
-
     //this allows us to use the debug exe as though it was located in the final folder structure
     let exe_file_path = std::env::current_exe().expect("error finding exe path");
 
     // Collected non-fatal startup recovery messages (e.g. quarantined corrupt
     // files), surfaced in the error window once the UI is up.
     let mut startup_errors: Vec<String> = Vec::new();
+    if !arg_problems.is_empty() {
+        startup_errors.push(format!("Problems with the command line:\n{}", arg_problems.join("\n")));
+    }
+
+    // Resolve (and on first run create, unless read-only) the data, config and
+    // images locations before anything reads them. `--data-dir` has to be
+    // recorded first.
+    if let Some(dir) = launch.data_dir.clone() {
+        paths::set_data_dir_override(dir);
+    }
+    let data_paths = match paths::data_paths(&exe_file_path, launch.read_only) {
+        Ok(paths) => paths,
+        Err(e) => {
+            startup_errors.push(format!("Could not prepare the data directory ({e})."));
+            DataPaths::beside_exe(&exe_file_path)
+        }
+    };
+    paths::set_resolved(data_paths.clone());
+    let DataPaths { config_file, images_dir, .. } = data_paths;
+
+    // `--config` replaces the resolved file; a profile's overlay sits beside it.
+    let userconfig_path = launch.config_file.clone().unwrap_or(config_file);
+    let config_files = ConfigFiles {
+        profile: launch.profile.as_deref().map(|name| paths::profile_file(&userconfig_path, name)),
+        base: userconfig_path.clone(),
+    };
+
+    // Read-only mode leaves even a corrupt file where it is.
+    let set_aside = |file_name: &str, e: &dyn std::error::Error| {
+        if launch.read_only {
+            format!("Could not read {file_name} ({e}). Started from defaults; the file is left alone (read-only).")
+        } else {
+            tasks::quarantine_corrupt_file(&exe_file_path, file_name, e)
+        }
+    };
 
     // A corrupt/unreadable active set must not abort the boot; quarantine the
     // bad file and start from an empty set instead.
     let active_items: Vec<Active> = match tasks::read_at_startup(&exe_file_path) {
         Ok(items) => items,
         Err(e) => {
-            startup_errors.push(tasks::quarantine_corrupt_file(
-                &exe_file_path,
-                "read_at_startup.json",
-                e.as_ref(),
-            ));
+            startup_errors.push(set_aside("read_at_startup.json", e.as_ref()));
             Vec::new()
         }
     };
 
-    let images_path = PathBuf::from("images");
+    // Snapshot the data before anything rewrites it in a new format: an older
+    // userconfig.toml (rewritten just below), the legacy archive conversion, or
+    // the id backfill in `TaskApp::new`. If the snapshot fails, say so but carry
+    // on — the migrations are themselves safe. In read-only mode all of this
+    // waits for a normal start.
+    let migration_pending = !launch.read_only
+        && (config_needs_migration(&userconfig_path)
+            || tasks::legacy_archive_pending(&exe_file_path)
+            || active_items.iter().any(|item| item.id == 0));
+    if migration_pending && let Err(e) = backup::take_snapshot(SnapshotKind::PreMigration, &userconfig_path, &exe_file_path) {
+        startup_errors.push(format!("Could not back up the data before migrating it ({e})."));
+    }
+
+    let (mut config, config_issues) = get_check_and_set_config(&config_files, launch.read_only);
+    if !config_issues.is_empty() {
+        let files = match &config_files.profile {
+            Some(profile) => format!("{} and {}", userconfig_path.display(), profile.display()),
+            None => userconfig_path.display().to_string(),
+        };
+        startup_errors.push(format!("Problems in {files}:\n{}", initialization::issues_text(&config_issues)));
+    }
+    launch.apply(&mut config);
+    let Config { start_in_fullscreen, locations, weather_location, background, enable_fps_counter, window_size_startup, calendar_weeks_to_show, selected_monitor_name, mut selected_colorscheme_id, mut selected_theme_id, three_day_weather, weather_details_row, calendar_weather, night_bands, night_dimming, item_cues, alert_rules, weather_provider, weather_refresh, units, temperature_rounding, weather_window_hours, forecast_days, background_image_tint_percent, archive_past_events_after_days } = config;
+
+    // Convert a pre-segment `archived.jsonl` into the indexed archive. Failing
+    // here leaves the legacy file untouched; it is retried on the next start.
+    if !launch.read_only {
+        match tasks::migrate_legacy_archive(&exe_file_path) {
+            Ok(Some(message)) => startup_errors.push(message),
+            Ok(None) => {}
+            Err(e) => startup_errors.push(format!("Could not convert archived.jsonl to the indexed archive ({e}). It will be retried on the next start.")),
+        }
+
+        if let Err(e) = backup::run_scheduled_backup(&userconfig_path, &exe_file_path) {
+            startup_errors.push(format!("Scheduled backup failed ({e})."));
+        }
+    }
+
     // Try reading the directory, if it fails, return an empty vector
-    let background_options: Vec<String> = match fs::read_dir(&images_path) {
+    let background_options: Vec<String> = match fs::read_dir(&images_dir) {
         Ok(entries) => entries
             .filter_map(|entry| entry.ok()) // ignore entries that caused errors
             .filter_map(|entry| entry.file_name().into_string().ok()) // convert OsString to String
@@ -59,11 +138,7 @@ async fn run() {
     let mut colorschemes = match color::read_colorschemes(&exe_file_path) {
         Ok(schemes) => schemes,
         Err(e) => {
-            startup_errors.push(tasks::quarantine_corrupt_file(
-                &exe_file_path,
-                "colorschemes.json",
-                e.as_ref(),
-            ));
+            startup_errors.push(set_aside("colorschemes.json", e.as_ref()));
             std::collections::HashMap::new()
         }
     };
@@ -73,24 +148,67 @@ async fn run() {
         selected_colorscheme_id = 0;
     }
 
+    // The built-in themes are always there, so a bad file or an unknown id
+    // just means the dark one.
+    let themes = match theme::read_themes(&exe_file_path) {
+        Ok(themes) => themes,
+        Err(e) => {
+            startup_errors.push(set_aside("themes.json", e.as_ref()));
+            theme::builtin_themes().into_iter().collect()
+        }
+    };
+
+    if !themes.contains_key(&selected_theme_id) {
+        selected_theme_id = theme::DARK_THEME_ID;
+    }
+
     let textbox_text = utilities::read_notepad_text(&exe_file_path).unwrap_or("There was something wrong with data/notepad_text.json!".to_string());
 
+    // Shows the cached forecast (if any) at once, then refreshes in the background.
+    let weather_service = get_weather(locations.iter().map(|location| location.coordinates).collect(), weather_provider, units, alert_rules, weather_refresh, exe_file_path.clone(), WeatherEnvironment {
+        wake: Box::new(move || {
+            let _ = proxy.send_event(());
+        }),
+        cache: !launch.read_only,
+        ..Default::default()
+    });
+
     let setup_config = TaskAppConfig {
         colorschemes,
         selected_colorscheme_id,
+        themes,
+        selected_theme_id,
         active_items,
         exe_file_path,
+        config_files,
+        images_dir,
+        launch,
         background,
         background_options,
-        coordinates,
+        locations,
+        weather_location,
         start_in_fullscreen,
+        window_size_startup,
         enable_fps_counter,
         calendar_weeks_to_show,
         selected_monitor_name: selected_monitor_name.clone(),
         textbox_text,
         three_day_weather,
+        weather_details_row,
+        calendar_weather,
+        night_bands,
+        night_dimming,
+        item_cues,
+        alert_rules,
+        weather_refresh,
+        units,
+        temperature_rounding,
+        weather_window_hours,
+        forecast_days,
+        weather_provider,
         background_image_tint_percent,
-        weather_service: get_weather(coordinates, proxy),
+        archive_past_events_after_days,
+        weather_service,
         startup_error: if startup_errors.is_empty() {
             None
         } else {
@@ -102,6 +220,7 @@ async fn run() {
 
     //Perform sort before initializing app
     task_app.summarize_calendar();
+    task_app.archive_expired_events();
 
     let mut app = App::new(task_app, window_size_startup, selected_monitor_name);
 
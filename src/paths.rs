@@ -0,0 +1,224 @@
+use std::{env, error::Error, ffi::OsString, fs, path::{Path, PathBuf}, sync::OnceLock};
+
+/// Folder holding the data files in the portable and dev layouts.
+const DATA_FOLDER: &str = "taskdeck_data";
+/// An empty file with this name next to the executable selects portable mode:
+/// data, config and images all live beside the exe, as in the release zip.
+pub const PORTABLE_MARKER: &str = "taskdeck.portable";
+/// Environment variable naming the data directory (below `--data-dir`).
+pub const DATA_DIR_ENV: &str = "TASKDECK_DATA";
+/// Folder name under the platform config/data directories.
+const APP_FOLDER: &str = "TaskDeck";
+const CONFIG_FILE: &str = "userconfig.toml";
+const IMAGES_FOLDER: &str = "images";
+/// Folder next to the config file holding the `--profile` overlays.
+const PROFILES_FOLDER: &str = "profiles";
+
+/// Set from `--data-dir` (see `cli::parse_args`) at startup; wins over every other source.
+static DATA_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+/// What startup settled on (see `set_resolved`); every later lookup reads it
+/// instead of walking the resolution order again.
+static RESOLVED: OnceLock<DataPaths> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataDirSource {
+    CommandLine,
+    Environment,
+    Portable,
+    DevLayout,
+    Platform,
+}
+
+/// Where everything the user owns lives. Resolved by `data_paths`, the single
+/// strategy behind `tasks::get_data_dir`, the config loader and the images list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataPaths {
+    pub data_dir: PathBuf,
+    pub config_file: PathBuf,
+    pub images_dir: PathBuf,
+    pub source: DataDirSource,
+}
+
+impl DataPaths {
+    /// Everything under one root: `--data-dir`, `TASKDECK_DATA`.
+    fn single(dir: PathBuf, source: DataDirSource) -> Self {
+        DataPaths { config_file: dir.join(CONFIG_FILE), images_dir: dir.join(IMAGES_FOLDER), data_dir: dir, source }
+    }
+
+    /// The portable layout around `exe_path`, without touching the disk. Used
+    /// as a last resort when `data_paths` fails.
+    pub fn beside_exe(exe_path: &Path) -> Self {
+        let exe_dir = exe_path.parent().unwrap_or(Path::new("."));
+        DataPaths::side_by_side(exe_dir, DataDirSource::Portable)
+    }
+
+    /// The release/dev layout: `taskdeck_data/` (holding the config) and
+    /// `images/` side by side under `root`.
+    fn side_by_side(root: &Path, source: DataDirSource) -> Self {
+        let data_dir = root.join(DATA_FOLDER);
+        DataPaths { config_file: data_dir.join(CONFIG_FILE), images_dir: root.join(IMAGES_FOLDER), data_dir, source }
+    }
+}
+
+/// Record the `--data-dir` value. Only the first call has any effect.
+pub fn set_data_dir_override(dir: PathBuf) {
+    let _ = DATA_DIR_OVERRIDE.set(dir);
+}
+
+/// Record the paths startup resolved (or fell back to), so `data_dir` returns
+/// them from then on. Only the first call has any effect.
+pub fn set_resolved(paths: DataPaths) {
+    let _ = RESOLVED.set(paths);
+}
+
+/// The overlay file for `--profile <name>`: `profiles/<name>.toml` beside
+/// `config_file`.
+pub fn profile_file(config_file: &Path, name: &str) -> PathBuf {
+    config_file.parent().unwrap_or(Path::new(".")).join(PROFILES_FOLDER).join(format!("{name}.toml"))
+}
+
+/// The resolution order, with every input passed in so it can be tested:
+/// 1. `--data-dir`;
+/// 2. `TASKDECK_DATA` (ignored when empty);
+/// 3. portable mode — a `taskdeck.portable` marker next to the exe, or an
+///    existing `taskdeck_data/` there (installs from before this existed);
+/// 4. the dev layout, `<exe>/../../taskdeck_data`, when running from `target/`;
+/// 5. the platform directories (XDG config/data on Linux, `%APPDATA%` on
+///    Windows, Application Support on macOS), given as `(config, data)`;
+/// 6. if the platform has none, portable mode after all.
+fn resolve_with(
+    exe_path: &Path,
+    flag: Option<&Path>,
+    env_dir: Option<OsString>,
+    platform: Option<(PathBuf, PathBuf)>,
+) -> Result<DataPaths, Box<dyn Error>> {
+    if let Some(dir) = flag {
+        return Ok(DataPaths::single(std::path::absolute(dir)?, DataDirSource::CommandLine));
+    }
+    if let Some(dir) = env_dir.filter(|dir| !dir.is_empty()) {
+        return Ok(DataPaths::single(std::path::absolute(dir)?, DataDirSource::Environment));
+    }
+
+    let exe_dir = exe_path.parent().ok_or("Could not find exe directory")?;
+    if exe_dir.join(PORTABLE_MARKER).exists() || exe_dir.join(DATA_FOLDER).is_dir() {
+        return Ok(DataPaths::side_by_side(exe_dir, DataDirSource::Portable));
+    }
+
+    // Development: target/debug/app -> project root.
+    if let Some(dev_root) = exe_dir.parent().and_then(Path::parent)
+        && dev_root.join(DATA_FOLDER).is_dir()
+    {
+        return Ok(DataPaths::side_by_side(dev_root, DataDirSource::DevLayout));
+    }
+
+    match platform {
+        Some((config_dir, data_dir)) => {
+            let data_dir = data_dir.join(APP_FOLDER);
+            Ok(DataPaths {
+                config_file: config_dir.join(APP_FOLDER).join(CONFIG_FILE),
+                images_dir: data_dir.join(IMAGES_FOLDER),
+                data_dir,
+                source: DataDirSource::Platform,
+            })
+        }
+        None => Ok(DataPaths::side_by_side(exe_dir, DataDirSource::Portable)),
+    }
+}
+
+/// Resolve the data, config and images locations for this process and create
+/// any that don't exist yet, so a first run starts cleanly instead of failing
+/// on the first save. In `read_only` mode nothing is created.
+pub fn data_paths(exe_path: &Path, read_only: bool) -> Result<DataPaths, Box<dyn Error>> {
+    let paths = resolve(exe_path)?;
+    if read_only {
+        return Ok(paths);
+    }
+
+    fs::create_dir_all(&paths.data_dir)?;
+    fs::create_dir_all(&paths.images_dir)?;
+    if let Some(config_dir) = paths.config_file.parent() {
+        fs::create_dir_all(config_dir)?;
+    }
+    Ok(paths)
+}
+
+/// The data directory recorded by `set_resolved`. Before that (and in tests,
+/// which never record one) it is resolved afresh, without creating anything.
+pub fn data_dir(exe_path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    if let Some(paths) = RESOLVED.get() {
+        return Ok(paths.data_dir.clone());
+    }
+    Ok(resolve(exe_path)?.data_dir)
+}
+
+/// `resolve_with` fed from this process's flag, environment and platform.
+fn resolve(exe_path: &Path) -> Result<DataPaths, Box<dyn Error>> {
+    let platform = dirs::config_dir().zip(dirs::data_dir());
+    resolve_with(exe_path, DATA_DIR_OVERRIDE.get().map(PathBuf::as_path), env::var_os(DATA_DIR_ENV), platform)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolution_follows_priority_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let bin = tmp.path().join("install").join("target").join("debug");
+        fs::create_dir_all(&bin).unwrap();
+        let exe = bin.join("app.exe");
+        let platform = Some((tmp.path().join("xdg-config"), tmp.path().join("xdg-data")));
+        let flag = tmp.path().join("flag");
+
+        // Nothing on disk: the platform directories, config kept apart from data.
+        let paths = resolve_with(&exe, None, None, platform.clone()).unwrap();
+        assert_eq!(paths.source, DataDirSource::Platform);
+        assert_eq!(paths.data_dir, tmp.path().join("xdg-data").join("TaskDeck"));
+        assert_eq!(paths.config_file, tmp.path().join("xdg-config").join("TaskDeck").join("userconfig.toml"));
+        assert_eq!(paths.images_dir, paths.data_dir.join("images"));
+
+        // An existing dev layout beats the platform directories.
+        fs::create_dir_all(tmp.path().join("install").join("taskdeck_data")).unwrap();
+        assert_eq!(resolve_with(&exe, None, None, platform.clone()).unwrap().source, DataDirSource::DevLayout);
+
+        // A portable marker beats the dev layout and keeps images beside the exe.
+        fs::write(bin.join(PORTABLE_MARKER), "").unwrap();
+        let paths = resolve_with(&exe, None, None, platform.clone()).unwrap();
+        assert_eq!(paths.source, DataDirSource::Portable);
+        assert_eq!(paths.data_dir, bin.join("taskdeck_data"));
+        assert_eq!(paths.config_file, bin.join("taskdeck_data").join("userconfig.toml"));
+        assert_eq!(paths.images_dir, bin.join("images"));
+
+        // The environment variable beats all of those, unless it is empty.
+        let env_dir = tmp.path().join("env");
+        let paths = resolve_with(&exe, None, Some(env_dir.clone().into()), platform.clone()).unwrap();
+        assert_eq!((paths.source, paths.data_dir), (DataDirSource::Environment, env_dir));
+        assert_eq!(resolve_with(&exe, None, Some(OsString::new()), platform.clone()).unwrap().source, DataDirSource::Portable);
+
+        // And the command line beats everything, with config and images inside it.
+        let paths = resolve_with(&exe, Some(&flag), Some(tmp.path().join("env").into()), platform).unwrap();
+        assert_eq!(paths, DataPaths::single(flag, DataDirSource::CommandLine));
+    }
+
+    #[test]
+    fn existing_data_folder_next_to_exe_stays_portable() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("taskdeck_data")).unwrap();
+        let paths = resolve_with(&tmp.path().join("app.exe"), None, None, Some((tmp.path().join("c"), tmp.path().join("d")))).unwrap();
+        assert_eq!(paths.source, DataDirSource::Portable);
+        assert_eq!(paths.data_dir, tmp.path().join("taskdeck_data"));
+    }
+
+    #[test]
+    fn data_paths_creates_missing_directories() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join(PORTABLE_MARKER), "").unwrap();
+
+        let paths = data_paths(&tmp.path().join("app.exe"), true).unwrap();
+        assert!(!paths.data_dir.exists(), "read-only mode creates nothing");
+
+        let paths = data_paths(&tmp.path().join("app.exe"), false).unwrap();
+        assert!(paths.data_dir.is_dir());
+        assert!(paths.images_dir.is_dir());
+    }
+}
@@ -0,0 +1,424 @@
+use std::collections::BTreeMap;
+
+use image::{DynamicImage, GenericImageView, Pixel};
+use kmeans_colors::get_kmeans_hamerly;
+use palette::{FromColor, Lab, Srgb};
+
+/// Colours in a scheme, and so the swatches every algorithm is asked for.
+pub const PALETTE_SIZE: usize = 6;
+/// Fewer opaque pixels than this and the image is not worth reading.
+const MIN_PIXELS: usize = 500;
+/// Pixels with less chroma (distance from the grey axis in Lab) count as grey.
+const GREY_CHROMA: f32 = 10.0;
+/// Smallest ΔE (CIE76) between two swatches from `KMeansSpread`; about where
+/// two small fills stop looking like the same colour.
+pub const MIN_SWATCH_DISTANCE: f32 = 20.0;
+/// Depth the octree starts from: the top six bits of each channel.
+const OCTREE_DEPTH: u8 = 6;
+/// Every algorithm is deterministic, so a background always gives the same scheme.
+const SEED: u64 = 42;
+
+/// How the swatches are picked from the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PaletteAlgorithm {
+    /// k-means in Lab (Hamerly's), seeded randomly. What generation always used.
+    #[default]
+    KMeans,
+    /// Splits the colour box along its widest side at the median until there are six.
+    MedianCut,
+    /// Buckets by the leading bits of RGB and merges the lightest buckets.
+    Octree,
+    /// k-means++ seeding, then swatches closer than `MIN_SWATCH_DISTANCE` are replaced.
+    KMeansSpread,
+}
+
+impl PaletteAlgorithm {
+    pub const ALL: [PaletteAlgorithm; 4] = [PaletteAlgorithm::KMeans, PaletteAlgorithm::MedianCut, PaletteAlgorithm::Octree, PaletteAlgorithm::KMeansSpread];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PaletteAlgorithm::KMeans => "k-means",
+            PaletteAlgorithm::MedianCut => "Median cut",
+            PaletteAlgorithm::Octree => "Octree",
+            PaletteAlgorithm::KMeansSpread => "k-means++ (spread out)",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PaletteOptions {
+    pub algorithm: PaletteAlgorithm,
+    /// Count pixels that stand out from the image's average colour for more.
+    pub saliency: bool,
+    /// Leave out near-grey pixels, unless that leaves too few.
+    pub exclude_greys: bool,
+}
+
+/// The `PALETTE_SIZE` colours of `image`, each with the weight of the pixels
+/// nearest to it. `None` if the image has too few opaque pixels.
+pub fn extract_palette(image: &DynamicImage, options: &PaletteOptions) -> Option<Vec<(Lab, f32)>> {
+    // Resize to suppress noise
+    let image = image.resize(200, 200, image::imageops::FilterType::Triangle);
+
+    let mut pixels = Vec::new();
+    for (_, _, pixel) in image.pixels() {
+        let rgba = pixel.to_rgba();
+        // Ignore transparent pixels
+        if rgba[3] < 200 {
+            continue;
+        }
+        pixels.push(Lab::from_color(Srgb::new(rgba[0], rgba[1], rgba[2]).into_format::<f32>()));
+    }
+    if pixels.len() < MIN_PIXELS {
+        return None;
+    }
+
+    if options.exclude_greys {
+        let colourful: Vec<Lab> = pixels.iter().copied().filter(|lab| chroma(*lab) >= GREY_CHROMA).collect();
+        if colourful.len() >= MIN_PIXELS {
+            pixels = colourful;
+        }
+    }
+    let weights = if options.saliency { saliency(&pixels) } else { vec![1.0; pixels.len()] };
+
+    let mut centroids = match options.algorithm {
+        PaletteAlgorithm::KMeans => kmeans(&pixels, &weights),
+        PaletteAlgorithm::MedianCut => median_cut(&pixels, &weights),
+        PaletteAlgorithm::Octree => octree(&pixels, &weights),
+        PaletteAlgorithm::KMeansSpread => kmeans_spread(&pixels, &weights),
+    };
+    // Images with fewer distinct colours than swatches repeat the last one.
+    while let Some(&last) = centroids.last().filter(|_| centroids.len() < PALETTE_SIZE) {
+        centroids.push(last);
+    }
+    Some(populations(&pixels, &weights, &centroids))
+}
+
+/// CIE76 colour difference.
+pub fn delta_e(a: Lab, b: Lab) -> f32 {
+    ((a.l - b.l).powi(2) + (a.a - b.a).powi(2) + (a.b - b.b).powi(2)).sqrt()
+}
+
+fn chroma(lab: Lab) -> f32 {
+    (lab.a * lab.a + lab.b * lab.b).sqrt()
+}
+
+fn channels(lab: Lab) -> [f32; 3] {
+    [lab.l, lab.a, lab.b]
+}
+
+/// Global-contrast saliency: each pixel's distance from the image's average
+/// colour, plus a floor so nothing drops out. Scaled to average 1.0, which
+/// keeps the populations comparable with unweighted ones.
+fn saliency(pixels: &[Lab]) -> Vec<f32> {
+    let mean = weighted_mean(pixels, &vec![1.0; pixels.len()], 0..pixels.len());
+    let distances: Vec<f32> = pixels.iter().map(|p| delta_e(*p, mean)).collect();
+    let farthest = distances.iter().copied().fold(0.0, f32::max).max(f32::EPSILON);
+    let raw: Vec<f32> = distances.iter().map(|d| 0.1 + d / farthest).collect();
+    let average = raw.iter().sum::<f32>() / raw.len() as f32;
+    raw.into_iter().map(|w| w / average).collect()
+}
+
+fn weighted_mean(pixels: &[Lab], weights: &[f32], indices: impl IntoIterator<Item = usize>) -> Lab {
+    let (mut sum, mut total) = ([0.0f32; 3], 0.0f32);
+    for i in indices {
+        for (s, c) in sum.iter_mut().zip(channels(pixels[i])) {
+            *s += c * weights[i];
+        }
+        total += weights[i];
+    }
+    let total = total.max(f32::EPSILON);
+    Lab::new(sum[0] / total, sum[1] / total, sum[2] / total)
+}
+
+fn nearest(pixel: Lab, centroids: &[Lab]) -> usize {
+    (0..centroids.len()).min_by(|&a, &b| delta_e(pixel, centroids[a]).total_cmp(&delta_e(pixel, centroids[b]))).unwrap_or(0)
+}
+
+/// Pairs each centroid with the weight of the pixels nearest to it.
+fn populations(pixels: &[Lab], weights: &[f32], centroids: &[Lab]) -> Vec<(Lab, f32)> {
+    let mut totals = vec![0.0; centroids.len()];
+    for (pixel, weight) in pixels.iter().zip(weights) {
+        totals[nearest(*pixel, centroids)] += weight;
+    }
+    centroids.iter().copied().zip(totals).collect()
+}
+
+fn kmeans(pixels: &[Lab], weights: &[f32]) -> Vec<Lab> {
+    // The crate doesn't take weights, so salient pixels go in more than once.
+    let samples: Vec<Lab> = if weights.iter().all(|w| *w == 1.0) {
+        pixels.to_vec()
+    } else {
+        pixels.iter().zip(weights).flat_map(|(p, w)| std::iter::repeat_n(*p, (w * 2.0).round().max(1.0) as usize)).collect()
+    };
+    get_kmeans_hamerly(
+        PALETTE_SIZE, // number of clusters
+        20,           // max iterations
+        0.002,        // convergence threshold
+        false,        // no verbose output
+        &samples,
+        SEED,
+    )
+    .centroids
+}
+
+fn median_cut(pixels: &[Lab], weights: &[f32]) -> Vec<Lab> {
+    let spread = |indices: &[usize]| -> (usize, f32) {
+        (0..3)
+            .map(|axis| {
+                let values = indices.iter().map(|&i| channels(pixels[i])[axis]);
+                let (low, high) = values.fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+                (axis, high - low)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap_or((0, 0.0))
+    };
+
+    let mut boxes: Vec<Vec<usize>> = vec![(0..pixels.len()).collect()];
+    while boxes.len() < PALETTE_SIZE {
+        // The box with the widest side; a box of one colour can't be split.
+        let Some((index, (axis, _))) = boxes.iter().map(|b| spread(b)).enumerate().filter(|(_, (_, range))| *range > 0.0).max_by(|a, b| a.1.1.total_cmp(&b.1.1)) else {
+            break;
+        };
+        let value = |i: usize| channels(pixels[i])[axis];
+        let cut = &mut boxes[index];
+        cut.sort_by(|&a, &b| value(a).total_cmp(&value(b)));
+
+        // Split at the weighted median, moved off any run of equal values so
+        // one colour doesn't end up in both halves.
+        let half = cut.iter().map(|&i| weights[i]).sum::<f32>() / 2.0;
+        let mut running = 0.0;
+        let mut at = cut.iter().position(|&i| {
+            running += weights[i];
+            running >= half
+        }).unwrap_or(0) + 1;
+        while at < cut.len() && value(cut[at - 1]) == value(cut[at]) {
+            at += 1;
+        }
+        if at == cut.len() {
+            at = cut.iter().rposition(|&i| value(i) != value(cut[cut.len() - 1])).map_or(1, |p| p + 1);
+        }
+        let upper = cut.split_off(at);
+        boxes.push(upper);
+    }
+    boxes.into_iter().map(|b| weighted_mean(pixels, weights, b)).collect()
+}
+
+fn octree(pixels: &[Lab], weights: &[f32]) -> Vec<Lab> {
+    // Leaves keyed by depth and the path down to them (three bits a level).
+    // A BTreeMap so ties always break the same way.
+    let mut leaves: BTreeMap<(u8, u32), (f32, [f32; 3])> = BTreeMap::new();
+    for (pixel, weight) in pixels.iter().zip(weights) {
+        let rgb: [u8; 3] = Srgb::from_color(*pixel).into_format::<u8>().into();
+        let path = (0..OCTREE_DEPTH).fold(0u32, |path, level| {
+            let bit = 7 - level;
+            let child = rgb.iter().fold(0u32, |child, c| child << 1 | (*c as u32 >> bit & 1));
+            path << 3 | child
+        });
+        add_to_leaf(&mut leaves, (OCTREE_DEPTH, path), *weight, rgb.map(|c| c as f32 * weight));
+    }
+
+    while leaves.len() > PALETTE_SIZE {
+        let Some(depth) = leaves.keys().map(|(depth, _)| *depth).max().filter(|depth| *depth > 0) else { break };
+        let mut parents: BTreeMap<u32, (f32, usize)> = BTreeMap::new();
+        for ((_, path), (weight, _)) in leaves.range((depth, 0)..) {
+            let parent = parents.entry(path >> 3).or_insert((0.0, 0));
+            parent.0 += weight;
+            parent.1 += 1;
+        }
+
+        // A level of only children: lift them a level as they are.
+        if parents.values().all(|(_, children)| *children == 1) {
+            for ((_, path), (weight, sum)) in leaves.split_off(&(depth, 0)) {
+                add_to_leaf(&mut leaves, (depth - 1, path >> 3), weight, sum);
+            }
+            continue;
+        }
+
+        // Merge the lightest parent that doesn't leave fewer than six leaves.
+        let Some((parent, _)) = parents
+            .into_iter()
+            .filter(|(_, (_, children))| *children > 1 && leaves.len() - (children - 1) >= PALETTE_SIZE)
+            .min_by(|a, b| a.1.0.total_cmp(&b.1.0))
+        else {
+            break;
+        };
+        let children: Vec<(u8, u32)> = leaves.range((depth, parent << 3)..=(depth, parent << 3 | 7)).map(|(key, _)| *key).collect();
+        for key in children {
+            if let Some((weight, sum)) = leaves.remove(&key) {
+                add_to_leaf(&mut leaves, (depth - 1, parent), weight, sum);
+            }
+        }
+    }
+
+    // Anything past six is the lightest; `populations` folds it into the nearest.
+    let mut heaviest: Vec<(f32, [f32; 3])> = leaves.into_values().collect();
+    heaviest.sort_by(|a, b| b.0.total_cmp(&a.0));
+    heaviest
+        .into_iter()
+        .take(PALETTE_SIZE)
+        .map(|(weight, sum)| {
+            let [r, g, b] = sum.map(|s| s / weight.max(f32::EPSILON) / 255.0);
+            Lab::from_color(Srgb::new(r, g, b))
+        })
+        .collect()
+}
+
+fn add_to_leaf(leaves: &mut BTreeMap<(u8, u32), (f32, [f32; 3])>, key: (u8, u32), weight: f32, sum: [f32; 3]) {
+    let leaf = leaves.entry(key).or_insert((0.0, [0.0; 3]));
+    leaf.0 += weight;
+    for (total, part) in leaf.1.iter_mut().zip(sum) {
+        *total += part;
+    }
+}
+
+/// Deterministic random numbers for the k-means++ seeding (xorshift64).
+struct Rng(u64);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// An index picked with probability proportional to its weight.
+    fn pick(&mut self, weights: &[f32]) -> Option<usize> {
+        let total: f32 = weights.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut target = self.next_f32() * total;
+        for (i, w) in weights.iter().enumerate() {
+            if target < *w {
+                return Some(i);
+            }
+            target -= w;
+        }
+        weights.iter().rposition(|w| *w > 0.0)
+    }
+}
+
+fn kmeans_spread(pixels: &[Lab], weights: &[f32]) -> Vec<Lab> {
+    let mut rng = Rng(SEED);
+
+    // k-means++: each next seed is picked with probability weight × distance².
+    let mut centroids: Vec<Lab> = rng.pick(weights).map(|i| pixels[i]).into_iter().collect();
+    while centroids.len() < PALETTE_SIZE {
+        let odds: Vec<f32> = pixels.iter().zip(weights).map(|(p, w)| w * delta_e(*p, centroids[nearest(*p, &centroids)]).powi(2)).collect();
+        let Some(i) = rng.pick(&odds) else { break };
+        centroids.push(pixels[i]);
+    }
+
+    // Weighted Lloyd iterations.
+    for _ in 0..20 {
+        let mut members = vec![Vec::new(); centroids.len()];
+        for (i, pixel) in pixels.iter().enumerate() {
+            members[nearest(*pixel, &centroids)].push(i);
+        }
+        let moved: Vec<Lab> = members.into_iter().zip(&centroids).map(|(m, c)| if m.is_empty() { *c } else { weighted_mean(pixels, weights, m) }).collect();
+        let shift = moved.iter().zip(&centroids).map(|(a, b)| delta_e(*a, *b)).fold(0.0, f32::max);
+        centroids = moved;
+        if shift < 0.01 {
+            break;
+        }
+    }
+
+    // Keep the heaviest centroids that are far enough from each other...
+    let mut by_weight = populations(pixels, weights, &centroids);
+    by_weight.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mut kept: Vec<Lab> = Vec::new();
+    for (centroid, _) in by_weight {
+        if kept.iter().all(|k| delta_e(*k, centroid) >= MIN_SWATCH_DISTANCE) {
+            kept.push(centroid);
+        }
+    }
+    // ...and fill the gaps from the pixels far enough from all of them: their
+    // average if that is far enough too, else the one nearest to it.
+    while kept.len() < PALETTE_SIZE {
+        let distance = |p: &Lab| kept.iter().map(|k| delta_e(*k, *p)).fold(f32::INFINITY, f32::min);
+        let eligible: Vec<usize> = (0..pixels.len()).filter(|&i| distance(&pixels[i]) >= MIN_SWATCH_DISTANCE).collect();
+        let fill = if eligible.is_empty() {
+            // Not that many distinct colours in the image: the farthest will have to do.
+            pixels.iter().copied().max_by(|a, b| distance(a).total_cmp(&distance(b)))
+        } else {
+            let mean = weighted_mean(pixels, weights, eligible.iter().copied());
+            if distance(&mean) >= MIN_SWATCH_DISTANCE {
+                Some(mean)
+            } else {
+                eligible.iter().map(|&i| pixels[i]).min_by(|a, b| delta_e(*a, mean).total_cmp(&delta_e(*b, mean)))
+            }
+        };
+        let Some(fill) = fill else { break };
+        kept.push(fill);
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCKS: [[u8; 3]; 6] = [[220, 40, 40], [40, 180, 60], [40, 70, 220], [240, 220, 40], [200, 50, 200], [30, 200, 210]];
+
+    /// A 200×120 image of `colors` in vertical stripes, so resizing leaves it alone.
+    fn stripes(colors: &[[u8; 3]]) -> DynamicImage {
+        DynamicImage::ImageRgb8(image::RgbImage::from_fn(200, 120, |x, _| image::Rgb(colors[x as usize * colors.len() / 200])))
+    }
+
+    fn lab([r, g, b]: [u8; 3]) -> Lab {
+        Lab::from_color(Srgb::new(r, g, b).into_format::<f32>())
+    }
+
+    #[test]
+    fn every_algorithm_finds_six_distinct_colours() {
+        let image = stripes(&BLOCKS);
+        for algorithm in PaletteAlgorithm::ALL {
+            let palette = extract_palette(&image, &PaletteOptions { algorithm, ..Default::default() }).unwrap();
+            assert_eq!(palette.len(), PALETTE_SIZE);
+            for block in BLOCKS {
+                let closest = palette.iter().map(|(c, _)| delta_e(*c, lab(block))).fold(f32::INFINITY, f32::min);
+                assert!(closest < 3.0, "{algorithm:?} missed {block:?} by {closest}");
+            }
+            let total: f32 = palette.iter().map(|(_, w)| w).sum();
+            assert_eq!(total as usize, 200 * 120, "{algorithm:?}");
+        }
+        // Nothing opaque to go on.
+        let clear = DynamicImage::ImageRgba8(image::RgbaImage::new(40, 40));
+        assert!(extract_palette(&clear, &PaletteOptions::default()).is_none());
+    }
+
+    #[test]
+    fn spread_swatches_keep_their_distance() {
+        // Two near-identical reds would be two swatches for plain k-means.
+        let colors = [[220, 40, 40], [215, 45, 40], [40, 180, 60], [40, 70, 220], [240, 220, 40], [200, 50, 200], [30, 200, 210], [120, 120, 120]];
+        let palette = extract_palette(&stripes(&colors), &PaletteOptions { algorithm: PaletteAlgorithm::KMeansSpread, ..Default::default() }).unwrap();
+        for (i, (a, _)) in palette.iter().enumerate() {
+            for (b, _) in &palette[i + 1..] {
+                assert!(delta_e(*a, *b) >= MIN_SWATCH_DISTANCE, "{a:?} and {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn greys_can_be_left_out_and_odd_pixels_weighted_up() {
+        let mut colors = vec![[128, 128, 128]; 4];
+        colors.extend(BLOCKS);
+        let image = stripes(&colors);
+        let greyish = |palette: &[(Lab, f32)]| palette.iter().filter(|(c, _)| chroma(*c) < GREY_CHROMA).count();
+
+        let plain = extract_palette(&image, &PaletteOptions { algorithm: PaletteAlgorithm::MedianCut, ..Default::default() }).unwrap();
+        assert!(greyish(&plain) > 0);
+        let colourful = extract_palette(&image, &PaletteOptions { algorithm: PaletteAlgorithm::MedianCut, exclude_greys: true, ..Default::default() }).unwrap();
+        assert_eq!(greyish(&colourful), 0);
+        // A grey-only image keeps its greys rather than failing.
+        assert!(extract_palette(&stripes(&[[90, 90, 90], [200, 200, 200]]), &PaletteOptions { exclude_greys: true, ..Default::default() }).is_some());
+
+        let mut pixels = vec![lab([128, 128, 128]); 99];
+        pixels.push(lab([220, 40, 40]));
+        let weights = saliency(&pixels);
+        assert!((weights.iter().sum::<f32>() / 100.0 - 1.0).abs() < 1e-4);
+        assert!(weights[99] > 5.0 * weights[0]);
+    }
+}
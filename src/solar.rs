@@ -0,0 +1,190 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+
+/// Sun elevation at sunrise and sunset: the upper limb on the horizon, with
+/// standard refraction (0.833° below the geometric horizon).
+pub const SUNRISE_ELEVATION: f64 = -0.833;
+
+/// Sun elevation that ends the morning golden hour and starts the evening one.
+pub const GOLDEN_HOUR_ELEVATION: f64 = 6.0;
+
+/// Sun elevation that ends civil twilight; below it, it is properly dark.
+pub const CIVIL_DUSK_ELEVATION: f64 = -6.0;
+
+/// Sunrise, sunset and golden hours for one day at one place, computed
+/// locally with the NOAA solar position equations (good to a minute or so
+/// outside the polar circles). Times are `None` where the sun doesn't cross
+/// that elevation that day: no sunrise in polar day or night, no golden-hour
+/// end when the sun stays below 6°.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SunDay {
+    pub sunrise: Option<DateTime<Utc>>,
+    pub sunset: Option<DateTime<Utc>>,
+    /// The sun reaches 6° after sunrise.
+    pub golden_morning_end: Option<DateTime<Utc>>,
+    /// The sun sinks to 6° before sunset.
+    pub golden_evening_start: Option<DateTime<Utc>>,
+    /// Time between sunrise and sunset; a whole day in polar day, none in
+    /// polar night.
+    pub daylight: chrono::Duration,
+}
+
+/// Declination (degrees) and equation of time (minutes) at Julian day `jd`.
+fn solar_coordinates(jd: f64) -> (f64, f64) {
+    let t = (jd - 2_451_545.0) / 36_525.0;
+    let mean_longitude = (280.466_46 + t * (36_000.769_83 + t * 0.000_303_2)).rem_euclid(360.0);
+    let mean_anomaly = 357.529_11 + t * (35_999.050_29 - 0.000_153_7 * t);
+    let eccentricity = 0.016_708_634 - t * (0.000_042_037 + 0.000_000_126_7 * t);
+
+    let m = mean_anomaly.to_radians();
+    let centre = m.sin() * (1.914_602 - t * (0.004_817 + 0.000_014 * t))
+        + (2.0 * m).sin() * (0.019_993 - 0.000_101 * t)
+        + (3.0 * m).sin() * 0.000_289;
+    let omega = (125.04 - 1_934.136 * t).to_radians();
+    let apparent_longitude = (mean_longitude + centre - 0.005_69 - 0.004_78 * omega.sin()).to_radians();
+
+    let mean_obliquity = 23.0 + (26.0 + (21.448 - t * (46.815 + t * (0.000_59 - t * 0.001_813))) / 60.0) / 60.0;
+    let obliquity = (mean_obliquity + 0.002_56 * omega.cos()).to_radians();
+    let declination = (obliquity.sin() * apparent_longitude.sin()).asin();
+
+    let y = (obliquity / 2.0).tan().powi(2);
+    let l0 = mean_longitude.to_radians();
+    let equation_of_time = y * (2.0 * l0).sin() - 2.0 * eccentricity * m.sin()
+        + 4.0 * eccentricity * y * m.sin() * (2.0 * l0).cos()
+        - 0.5 * y * y * (4.0 * l0).sin()
+        - 1.25 * eccentricity * eccentricity * (2.0 * m).sin();
+
+    (declination.to_degrees(), 4.0 * equation_of_time.to_degrees())
+}
+
+fn julian_day(at: DateTime<Utc>) -> f64 {
+    at.timestamp() as f64 / 86_400.0 + 2_440_587.5
+}
+
+/// Geometric elevation of the sun's centre, in degrees, at `at` seen from
+/// `[latitude, longitude]`.
+pub fn sun_elevation(at: DateTime<Utc>, coordinates: [f32; 2]) -> f64 {
+    let (declination, equation_of_time) = solar_coordinates(julian_day(at));
+    let (latitude, longitude) = (coordinates[0] as f64, coordinates[1] as f64);
+
+    let minutes = (at.timestamp().rem_euclid(86_400)) as f64 / 60.0;
+    let hour_angle = ((minutes + equation_of_time + 4.0 * longitude) / 4.0 - 180.0).to_radians();
+    let (lat, dec) = (latitude.to_radians(), declination.to_radians());
+    let cos_zenith = lat.sin() * dec.sin() + lat.cos() * dec.cos() * hour_angle.cos();
+    90.0 - cos_zenith.clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+/// When the sun passes `elevation` on `date` (the location's solar day), on
+/// the way up or down. `Err(true)` if it stays above all day, `Err(false)` if
+/// it stays below.
+fn crossing(date: NaiveDate, coordinates: [f32; 2], elevation: f64, rising: bool) -> Result<DateTime<Utc>, bool> {
+    let (latitude, longitude) = (coordinates[0] as f64, coordinates[1] as f64);
+    let midnight = date.and_time(NaiveTime::MIN).and_utc();
+
+    // Start at local noon; the second pass uses the sun's position at the
+    // first estimate, which is enough for minute precision.
+    let mut minutes = 720.0 - 4.0 * longitude;
+    for _ in 0..2 {
+        let at = midnight + chrono::Duration::seconds((minutes * 60.0) as i64);
+        let (declination, equation_of_time) = solar_coordinates(julian_day(at));
+        let (lat, dec) = (latitude.to_radians(), declination.to_radians());
+        let cos_hour_angle = (elevation.to_radians().sin() - lat.sin() * dec.sin()) / (lat.cos() * dec.cos());
+        if cos_hour_angle > 1.0 {
+            return Err(false);
+        }
+        if cos_hour_angle < -1.0 {
+            return Err(true);
+        }
+        let hour_angle = cos_hour_angle.acos().to_degrees();
+        let noon = 720.0 - 4.0 * longitude - equation_of_time;
+        minutes = if rising { noon - 4.0 * hour_angle } else { noon + 4.0 * hour_angle };
+    }
+    Ok(midnight + chrono::Duration::seconds((minutes * 60.0).round() as i64))
+}
+
+/// Sunrise, sunset, daylight and golden hours on `date` at `[latitude,
+/// longitude]`. `date` is the location's: its solar noon falls on that date.
+pub fn sun_day(date: NaiveDate, coordinates: [f32; 2]) -> SunDay {
+    let sunrise = crossing(date, coordinates, SUNRISE_ELEVATION, true);
+    let sunset = crossing(date, coordinates, SUNRISE_ELEVATION, false);
+    let daylight = match (sunrise, sunset) {
+        (Ok(rise), Ok(set)) => set - rise,
+        (Err(true), _) | (_, Err(true)) => chrono::Duration::days(1),
+        _ => chrono::Duration::zero(),
+    };
+    SunDay {
+        sunrise: sunrise.ok(),
+        sunset: sunset.ok(),
+        golden_morning_end: crossing(date, coordinates, GOLDEN_HOUR_ELEVATION, true).ok(),
+        golden_evening_start: crossing(date, coordinates, GOLDEN_HOUR_ELEVATION, false).ok(),
+        daylight,
+    }
+}
+
+/// How far the UI is dimmed at `at`: 0 while the sun is up, rising to 1 as
+/// it sinks through civil twilight.
+pub fn night_dimming(at: DateTime<Utc>, coordinates: [f32; 2]) -> f32 {
+    let elevation = sun_elevation(at, coordinates);
+    ((SUNRISE_ELEVATION - elevation) / (SUNRISE_ELEVATION - CIVIL_DUSK_ELEVATION)).clamp(0.0, 1.0) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OSLO: [f32; 2] = [59.91, 10.75];
+    const TROMSO: [f32; 2] = [69.65, 18.96];
+
+    fn utc(date: (i32, u32, u32), hour: u32, minute: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap().and_hms_opt(hour, minute, 0).unwrap().and_utc()
+    }
+
+    fn assert_near(actual: Option<DateTime<Utc>>, expected: DateTime<Utc>) {
+        let actual = actual.expect("the sun should cross");
+        assert!((actual - expected).num_seconds().abs() <= 120, "{actual} is not within two minutes of {expected}");
+    }
+
+    #[test]
+    fn oslo_midsummer_matches_published_times() {
+        // Published: sunrise 03:53, sunset 22:44 CEST.
+        let day = sun_day(NaiveDate::from_ymd_opt(2025, 6, 21).unwrap(), OSLO);
+        assert_near(day.sunrise, utc((2025, 6, 21), 1, 53));
+        assert_near(day.sunset, utc((2025, 6, 21), 20, 44));
+        assert!((day.daylight.num_minutes() - (18 * 60 + 51)).abs() <= 3, "{}", day.daylight);
+
+        // Golden hours sit inside the day, next to sunrise and sunset.
+        let (morning, evening) = (day.golden_morning_end.unwrap(), day.golden_evening_start.unwrap());
+        assert!(day.sunrise.unwrap() < morning && morning < evening && evening < day.sunset.unwrap());
+    }
+
+    #[test]
+    fn equinox_at_the_equator_is_twelve_hours() {
+        let day = sun_day(NaiveDate::from_ymd_opt(2025, 3, 20).unwrap(), [0.0, 0.0]);
+        assert_near(day.sunrise, utc((2025, 3, 20), 6, 4));
+        assert_near(day.sunset, utc((2025, 3, 20), 18, 11));
+        assert!((day.daylight.num_minutes() - 12 * 60).abs() <= 10);
+    }
+
+    #[test]
+    fn polar_day_and_night_have_no_sunrise() {
+        let summer = sun_day(NaiveDate::from_ymd_opt(2025, 6, 21).unwrap(), TROMSO);
+        assert_eq!((summer.sunrise, summer.sunset), (None, None));
+        assert_eq!(summer.daylight, chrono::Duration::days(1));
+
+        let winter = sun_day(NaiveDate::from_ymd_opt(2025, 12, 21).unwrap(), TROMSO);
+        assert_eq!((winter.sunrise, winter.sunset, winter.golden_morning_end), (None, None, None));
+        assert_eq!(winter.daylight, chrono::Duration::zero());
+    }
+
+    #[test]
+    fn dimming_follows_the_sun_through_twilight() {
+        // Midday and midnight in Oslo in winter.
+        assert_eq!(night_dimming(utc((2025, 1, 15), 11, 30), OSLO), 0.0);
+        assert_eq!(night_dimming(utc((2025, 1, 15), 23, 0), OSLO), 1.0);
+        assert!(sun_elevation(utc((2025, 6, 21), 11, 20), OSLO) > 53.0);
+
+        // A minute after sunset it is only just dimming.
+        let sunset = sun_day(NaiveDate::from_ymd_opt(2025, 3, 20).unwrap(), OSLO).sunset.unwrap();
+        let dimming = night_dimming(sunset + chrono::Duration::minutes(1), OSLO);
+        assert!(dimming > 0.0 && dimming < 0.2, "{dimming}");
+    }
+}
@@ -1,6 +1,5 @@
-use std::{collections::HashMap, error::Error, fs::{self, File, OpenOptions}, io::{BufReader, BufWriter, Write}, path::PathBuf};
-use chrono::{DateTime, Local, NaiveDate};
-use rev_lines::RevLines;
+use std::{collections::HashMap, error::Error, fs::{self, File, OpenOptions}, io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write}, path::{Path, PathBuf}};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
 use tempfile::NamedTempFile;
 
@@ -18,6 +17,14 @@ pub struct Active {
     pub created: DateTime<Local>,
     pub deadline: Option<DateTime<Local>>,
     pub is_event: bool,
+    /// An event that happens outside, flagged when a weather alert covers its
+    /// day. Not carried into the archive.
+    #[serde(default)]
+    pub outdoor: bool,
+    /// Where an event takes place, if it is one of the weather locations;
+    /// its forecast for the day is shown in the day popup.
+    #[serde(default)]
+    pub location: Option<crate::weather::Location>,
 }
 
 /// Upper bound on the exponent fed to the importance-score exponentials, chosen
@@ -66,7 +73,7 @@ impl Active {
 
         return score * random_variation;
     }
-    pub fn to_inactive(self) -> InActive {
+    pub fn to_inactive(self, reason: ArchiveReason) -> InActive {
         InActive {
             id: self.id,
             importance: self.importance,
@@ -75,6 +82,7 @@ impl Active {
             deadline: self.deadline,
             is_event: self.is_event,
             inactivated: chrono::Local::now(),
+            reason,
         }
     }
     pub fn calendar_item_color(&self) -> usize {
@@ -90,6 +98,17 @@ impl Active {
     }
 }
 
+/// Ids of events whose deadline lies more than `after` in the past, for
+/// archiving as `ArchiveReason::ExpiredEvent`. Tasks are never swept: an overdue
+/// task still needs doing, while a past event is simply over.
+pub fn expired_event_ids(items: &[Active], now: DateTime<Local>, after: Duration) -> Vec<u64> {
+    items
+        .iter()
+        .filter(|item| item.is_event && item.deadline.is_some_and(|deadline| deadline + after < now))
+        .map(|item| item.id)
+        .collect()
+}
+
 /// Group dated items by their deadline day, preserving input order within each
 /// day's bucket. The returned vectors borrow from `items`, so the caller can
 /// build the calendar with O(1) per-cell lookups instead of re-scanning every
@@ -105,7 +124,7 @@ pub fn bucket_by_deadline_day(items: &[Active]) -> HashMap<NaiveDate, Vec<&Activ
     buckets
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct InActive {
     /// Carried over from the `Active` item so archived rows keep a stable
     /// identity. See `Active::id`.
@@ -117,41 +136,201 @@ pub struct InActive {
     pub deadline: Option<DateTime<Local>>,
     pub is_event: bool,
     pub inactivated: DateTime<Local>,
+    /// Why the item left the active list. Rows archived before reasons were
+    /// recorded only ever came from completions, hence the default.
+    #[serde(default)]
+    pub reason: ArchiveReason,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum ArchiveReason {
+    #[default]
+    Completed,
+    Deleted,
+    /// An event swept out by `expired_event_ids` once its deadline was far enough
+    /// in the past (`archive_past_events_after_days`).
+    ExpiredEvent,
+    /// Folded into another item. Nothing in the app merges items yet; the
+    /// variant exists so such rows round-trip and can be filtered.
+    Merged,
+}
 
-pub fn get_data_dir(exe_path: &PathBuf) -> Result<PathBuf, Box<dyn Error>> {
-    let exe_dir = exe_path.parent().ok_or("Could not find exe directory")?;
-    let data_in_exe_dir = exe_dir.join("taskdeck_data");
+impl ArchiveReason {
+    pub const ALL: [ArchiveReason; 4] = [
+        ArchiveReason::Completed,
+        ArchiveReason::Deleted,
+        ArchiveReason::ExpiredEvent,
+        ArchiveReason::Merged,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ArchiveReason::Completed => "Completed",
+            ArchiveReason::Deleted => "Deleted",
+            ArchiveReason::ExpiredEvent => "Expired event",
+            ArchiveReason::Merged => "Merged",
+        }
+    }
+}
+
+/// Archive browser columns, for `ArchiveQuery::sort`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArchiveSort {
+    Created,
+    Name,
+    Importance,
+    #[default]
+    Inactivated,
+    Reason,
+}
+
+/// Filter and ordering for the archive browser. Every filter is optional; the
+/// default (no filters, newest first) is exactly what `read_archive_page`
+/// returns, so the UI only falls back to `query_archive` when it differs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveQuery {
+    pub reason: Option<ArchiveReason>,
+    pub is_event: Option<bool>,
+    pub importance: Option<u8>,
+    /// Inclusive lower bound on `inactivated`.
+    pub from: Option<DateTime<Local>>,
+    /// Exclusive upper bound on `inactivated`.
+    pub to: Option<DateTime<Local>>,
+    pub sort: ArchiveSort,
+    pub descending: bool,
+}
+
+impl Default for ArchiveQuery {
+    fn default() -> Self {
+        ArchiveQuery {
+            reason: None,
+            is_event: None,
+            importance: None,
+            from: None,
+            to: None,
+            sort: ArchiveSort::Inactivated,
+            descending: true,
+        }
+    }
+}
+
+impl ArchiveQuery {
+    pub fn is_default(&self) -> bool {
+        *self == ArchiveQuery::default()
+    }
+
+    pub fn matches(&self, record: &InActive) -> bool {
+        self.reason.is_none_or(|reason| record.reason == reason)
+            && self.is_event.is_none_or(|is_event| record.is_event == is_event)
+            && self.importance.is_none_or(|importance| record.importance == Some(importance))
+            && self.from.is_none_or(|from| record.inactivated >= from)
+            && self.to.is_none_or(|to| record.inactivated < to)
+    }
+
+    /// Drop non-matching records and sort the rest. Ties keep archive order, so
+    /// equal keys still read oldest-to-newest (or the reverse when descending).
+    pub fn apply(&self, records: &mut Vec<InActive>) {
+        records.retain(|record| self.matches(record));
+        match self.sort {
+            ArchiveSort::Created => records.sort_by_key(|r| r.created),
+            ArchiveSort::Name => records.sort_by_cached_key(|r| r.name.to_lowercase()),
+            ArchiveSort::Importance => records.sort_by_key(|r| r.importance),
+            ArchiveSort::Inactivated => records.sort_by_key(|r| r.inactivated),
+            ArchiveSort::Reason => records.sort_by_key(|r| r.reason),
+        }
+        if self.descending {
+            records.reverse();
+        }
+    }
+}
+
+/// Summary figures for a set of archived records (see `archive_stats`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveStats {
+    pub completed: usize,
+    pub deleted: usize,
+    pub expired: usize,
+    pub merged: usize,
+    /// Completions per week (weeks start on Monday), oldest first. Weeks between
+    /// the first and last completion with nothing done are present as zeros.
+    pub weekly_completions: Vec<(NaiveDate, u32)>,
+    /// Mean time from `created` to `inactivated` across completed records.
+    pub average_lead_time: Option<Duration>,
+}
 
-    if data_in_exe_dir.exists() {
-        return Ok(data_in_exe_dir);
+impl ArchiveStats {
+    pub fn completions_per_week(&self) -> f64 {
+        if self.weekly_completions.is_empty() {
+            0.0
+        } else {
+            self.completed as f64 / self.weekly_completions.len() as f64
+        }
     }
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
 
-    // Fallback for development (e.g., target/debug/app)
-    let maybe_project_root = exe_dir
-        .parent() // target/
-        .and_then(|p| p.parent()); // project root
+pub fn archive_stats(records: &[InActive]) -> ArchiveStats {
+    let completions: Vec<&InActive> = records.iter().filter(|r| r.reason == ArchiveReason::Completed).collect();
 
-    let dev_data_path = maybe_project_root
-        .ok_or("Could not determine project root for dev mode")?
-        .join("taskdeck_data");
+    let mut per_week: HashMap<NaiveDate, u32> = HashMap::new();
+    for record in &completions {
+        *per_week.entry(week_start(record.inactivated.date_naive())).or_default() += 1;
+    }
+    let mut weekly_completions = Vec::new();
+    if let (Some(first), Some(last)) = (per_week.keys().min(), per_week.keys().max()) {
+        let mut week = *first;
+        while week <= *last {
+            weekly_completions.push((week, per_week.get(&week).copied().unwrap_or(0)));
+            week += Duration::weeks(1);
+        }
+    }
 
-    if dev_data_path.exists() {
-        Ok(dev_data_path)
+    let average_lead_time = if completions.is_empty() {
+        None
     } else {
-        Err("Could not locate 'data' directory".into())
+        let total_seconds: i64 = completions.iter().map(|r| (r.inactivated - r.created).num_seconds()).sum();
+        Some(Duration::seconds(total_seconds / completions.len() as i64))
+    };
+
+    let count = |reason: ArchiveReason| records.iter().filter(|r| r.reason == reason).count();
+
+    ArchiveStats {
+        completed: completions.len(),
+        deleted: count(ArchiveReason::Deleted),
+        expired: count(ArchiveReason::ExpiredEvent),
+        merged: count(ArchiveReason::Merged),
+        weekly_completions,
+        average_lead_time,
     }
 }
 
+
+/// The data directory startup resolved (and created on first run); see
+/// `paths::data_dir`.
+pub fn get_data_dir(exe_path: &PathBuf) -> Result<PathBuf, Box<dyn Error>> {
+    crate::paths::data_dir(exe_path)
+}
+
+/// A fake exe whose sibling `taskdeck_data/` exists, so `get_data_dir`
+/// resolves inside the temp dir. Shared by every test that touches data files.
+#[cfg(test)]
+pub(crate) fn fake_exe_with_data_dir(tmp: &tempfile::TempDir) -> PathBuf {
+    fs::create_dir_all(tmp.path().join("taskdeck_data")).unwrap();
+    tmp.path().join("app.exe")
+}
+
 pub fn read_at_startup(exe_path: &PathBuf) -> Result<Vec<Active>, Box<dyn Error>> {
     let dir_path: PathBuf = get_data_dir(exe_path)?;
     
     let file_path = dir_path.join("read_at_startup.json");
     
+    // Nothing saved yet: start empty. The first save creates the file, so a
+    // read-only launch leaves the data directory as it found it.
     if !file_path.exists() {
-        let mut file = File::create(&file_path).expect("failed to create active save JSON file");
-        file.write_all(b"[]").expect("failed to write to JSON file");
+        return Ok(Vec::new());
     }
 
     let file = File::open(&file_path)?;
@@ -237,41 +416,469 @@ pub fn oversafe_activesave(payload: &Vec<Active>, exe_path: &PathBuf) -> Result<
     Ok(())
 }
 
-pub fn save_inactive(payload: &InActive, exe_path: &PathBuf) -> Result<(), Box<dyn Error>> {
-    let data_dir = get_data_dir(exe_path)?;
-    let final_path = data_dir.join("archived.jsonl");
+/// Directory (inside the data dir) holding the archive segments. Each calendar
+/// month of `inactivated` timestamps gets one append-only `YYYY-MM.jsonl` log
+/// plus a `YYYY-MM.idx` sidecar of fixed-width byte offsets, one per record. The
+/// index is what makes counting and paging O(segments + page) instead of
+/// re-scanning the whole archive for every "Show more" (CODE_REVIEW B4).
+const ARCHIVE_DIR: &str = "archive";
+
+/// The pre-segment archive: a single newline-delimited `InActive` log. Still the
+/// format `export_archive_jsonl` writes, and migrated into segments at startup.
+const LEGACY_ARCHIVE_FILE: &str = "archived.jsonl";
+
+/// Size of one index entry: the record's starting byte offset as a little-endian `u64`.
+const INDEX_ENTRY_SIZE: u64 = 8;
+
+/// Outcome of `rebuild_archive`, for display after the rebuild command runs.
+pub struct ArchiveRebuildReport {
+    pub segments: usize,
+    pub records: u64,
+    /// Unparseable lines moved out of the segments (kept in `*.corrupt-*` files).
+    pub dropped: u64,
+}
 
-    // Ensure the directory exists
-    fs::create_dir_all(&data_dir)?;
+fn archive_dir(exe_path: &PathBuf) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = get_data_dir(exe_path)?.join(ARCHIVE_DIR);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn is_segment_key(key: &str) -> bool {
+    NaiveDate::parse_from_str(&format!("{key}-01"), "%Y-%m-%d").is_ok()
+}
+
+fn segment_key(at: DateTime<Local>) -> String {
+    at.format("%Y-%m").to_string()
+}
+
+fn segment_paths(dir: &Path, key: &str) -> (PathBuf, PathBuf) {
+    (dir.join(format!("{key}.jsonl")), dir.join(format!("{key}.idx")))
+}
+
+/// Segment keys present in `dir`, oldest first. Keys are `YYYY-MM`, so lexical
+/// order is chronological; anything else in the directory is ignored.
+fn list_segments(dir: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut keys: Vec<String> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter_map(|name| name.strip_suffix(".jsonl").map(str::to_string))
+        .filter(|key| is_segment_key(key))
+        .collect();
+    keys.sort();
+    Ok(keys)
+}
+
+/// Number of records in a segment, read from the index length. A missing or
+/// torn index (e.g. a crash between the log and index writes) is regenerated
+/// from the log first.
+fn segment_len(dir: &Path, key: &str) -> Result<u64, Box<dyn Error>> {
+    let (_, idx_path) = segment_paths(dir, key);
+    match fs::metadata(&idx_path) {
+        Ok(meta) if meta.len() % INDEX_ENTRY_SIZE == 0 => Ok(meta.len() / INDEX_ENTRY_SIZE),
+        _ => Ok(rebuild_segment(dir, key, false)?.0),
+    }
+}
+
+fn read_index(dir: &Path, key: &str, start: u64, end: u64) -> Result<Vec<u64>, Box<dyn Error>> {
+    let (_, idx_path) = segment_paths(dir, key);
+    let mut idx = File::open(idx_path)?;
+    idx.seek(SeekFrom::Start(start * INDEX_ENTRY_SIZE))?;
+
+    let mut buf = vec![0u8; ((end - start) * INDEX_ENTRY_SIZE) as usize];
+    idx.read_exact(&mut buf)?;
+
+    Ok(buf
+        .chunks_exact(INDEX_ENTRY_SIZE as usize)
+        .map(|entry| u64::from_le_bytes(entry.try_into().unwrap_or_default()))
+        .collect())
+}
+
+fn read_record(log: &mut BufReader<File>, offset: u64) -> Option<InActive> {
+    log.seek(SeekFrom::Start(offset)).ok()?;
+    let mut line = String::new();
+    log.read_line(&mut line).ok()?;
+    serde_json::from_str(line.trim_end()).ok()
+}
+
+/// Records `start..end` of a segment, in append (oldest-first) order.
+fn read_segment_slice(dir: &Path, key: &str, start: u64, end: u64) -> Result<Vec<InActive>, Box<dyn Error>> {
+    if start >= end {
+        return Ok(Vec::new());
+    }
+    let offsets = read_index(dir, key, start, end)?;
+    let (log_path, _) = segment_paths(dir, key);
+    let mut log = BufReader::new(File::open(log_path)?);
+
+    Ok(offsets.into_iter().filter_map(|offset| read_record(&mut log, offset)).collect())
+}
+
+fn append_to_segment(dir: &Path, payload: &InActive) -> Result<(), Box<dyn Error>> {
+    let key = segment_key(payload.inactivated);
+    let (log_path, idx_path) = segment_paths(dir, &key);
+
+    // Never append behind a missing index, or every later offset would be
+    // attributed to the wrong record.
+    if log_path.exists() && !idx_path.exists() {
+        rebuild_segment(dir, &key, false)?;
+    }
 
     let mut json = serde_json::to_string(payload)?;
-    json.push_str("\n");
+    json.push('\n');
 
-    let mut file = OpenOptions::new().create(true).append(true).open(final_path)?;
+    let mut log = OpenOptions::new().create(true).append(true).open(&log_path)?;
+    let offset = log.metadata()?.len();
+    log.write_all(json.as_bytes())?;
+    log.sync_all()?;
 
-    {
-        let mut writer = BufWriter::new(&mut file);
-        writer.write_all(json.as_bytes())?;
-        writer.flush()?;
+    let mut idx = OpenOptions::new().create(true).append(true).open(&idx_path)?;
+    idx.write_all(&offset.to_le_bytes())?;
+    Ok(idx.sync_all()?)
+}
+
+/// Write `bytes` to `path` atomically (temp file in the same dir → fsync → persist).
+pub fn write_atomically(dir: &Path, path: &Path, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut temp_file = NamedTempFile::new_in(dir)?;
+    temp_file.write_all(bytes)?;
+    temp_file.as_file_mut().sync_all()?;
+    temp_file.persist(path)?;
+    Ok(())
+}
+
+/// Regenerate a segment's index from its log. With `compact`, unparseable lines
+/// are also moved out of the log into `<key>.jsonl.corrupt-<timestamp>` so the
+/// index and the rows the UI can display agree one-to-one. Returns
+/// `(records kept, lines dropped)`.
+fn rebuild_segment(dir: &Path, key: &str, compact: bool) -> Result<(u64, u64), Box<dyn Error>> {
+    let (log_path, idx_path) = segment_paths(dir, key);
+    let contents = fs::read(&log_path)?;
+
+    let mut kept: Vec<u8> = Vec::with_capacity(contents.len());
+    let mut discarded: Vec<u8> = Vec::new();
+    let mut index: Vec<u8> = Vec::new();
+    let (mut records, mut dropped) = (0, 0);
+
+    let mut offset = 0u64;
+    for line in contents.split_inclusive(|b| *b == b'\n') {
+        let parses = std::str::from_utf8(line)
+            .ok()
+            .is_some_and(|text| serde_json::from_str::<InActive>(text.trim_end()).is_ok());
+
+        if parses {
+            let position = if compact { kept.len() as u64 } else { offset };
+            index.extend_from_slice(&position.to_le_bytes());
+            kept.extend_from_slice(line);
+            if !line.ends_with(b"\n") {
+                kept.push(b'\n');
+            }
+            records += 1;
+        } else if !line.iter().all(|b| b.is_ascii_whitespace()) {
+            discarded.extend_from_slice(line);
+            dropped += 1;
+        }
+        offset += line.len() as u64;
+    }
+
+    if compact && kept != contents {
+        if dropped > 0 {
+            let timestamp = Local::now().format("%Y%m%d-%H%M%S");
+            fs::write(dir.join(format!("{key}.jsonl.corrupt-{timestamp}")), &discarded)?;
+        }
+        write_atomically(dir, &log_path, &kept)?;
+    }
+    write_atomically(dir, &idx_path, &index)?;
+
+    Ok((records, if compact { dropped } else { 0 }))
+}
+
+/// Rewrite a segment's log in `inactivated` order and regenerate its index, for
+/// when records were appended out of order (a legacy merge). The sort is
+/// stable; unparseable lines go first, where `segment_lower_bound` already
+/// treats them as "before".
+fn sort_segment(dir: &Path, key: &str) -> Result<(), Box<dyn Error>> {
+    let (log_path, _) = segment_paths(dir, key);
+    let contents = fs::read_to_string(&log_path)?;
+
+    let mut lines: Vec<(Option<DateTime<Local>>, &str)> = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| (serde_json::from_str::<InActive>(line).ok().map(|r| r.inactivated), line))
+        .collect();
+    if lines.is_sorted_by_key(|(at, _)| *at) {
+        return Ok(());
+    }
+    lines.sort_by_key(|(at, _)| *at);
+
+    let mut sorted = String::with_capacity(contents.len());
+    for (_, line) in lines {
+        sorted.push_str(line);
+        sorted.push('\n');
+    }
+    write_atomically(dir, &log_path, sorted.as_bytes())?;
+    rebuild_segment(dir, key, false)?;
+    Ok(())
+}
+
+pub fn save_inactive(payload: &InActive, exe_path: &PathBuf) -> Result<(), Box<dyn Error>> {
+    let dir = archive_dir(exe_path)?;
+    append_to_segment(&dir, payload)
+}
+
+/// Total number of archived records. Reads only the index sizes.
+pub fn archive_count(exe_path: &PathBuf) -> Result<u64, Box<dyn Error>> {
+    let dir = archive_dir(exe_path)?;
+    let mut total = 0;
+    for key in list_segments(&dir)? {
+        total += segment_len(&dir, &key)?;
+    }
+    Ok(total)
+}
+
+/// One page of the archive, newest first: skips the `offset` most recent
+/// records and returns up to `limit` after them. Only the index entries and
+/// records on the page are read, so paging cost doesn't grow with the archive.
+pub fn read_archive_page(offset: usize, limit: usize, exe_path: &PathBuf) -> Result<Vec<InActive>, Box<dyn Error>> {
+    let dir = archive_dir(exe_path)?;
+
+    let mut skip = offset as u64;
+    let mut remaining = limit as u64;
+    let mut page = Vec::new();
+
+    for key in list_segments(&dir)?.iter().rev() {
+        if remaining == 0 {
+            break;
+        }
+        let len = segment_len(&dir, key)?;
+        if skip >= len {
+            skip -= len;
+            continue;
+        }
+
+        let end = len - skip;
+        let start = end.saturating_sub(remaining);
+        let mut rows = read_segment_slice(&dir, key, start, end)?;
+        rows.reverse();
+        page.extend(rows);
+
+        remaining -= end - start;
+        skip = 0;
+    }
+
+    Ok(page)
+}
+
+/// Index of the first record in a segment whose `inactivated` is at or after
+/// `at`. Records are appended as they are archived (and `sort_segment` restores
+/// that order after a merge), so a segment is ordered by `inactivated`. The
+/// binary search seeks to each probed index entry, so it reads O(log n) index
+/// entries and records rather than the whole index.
+fn segment_lower_bound(dir: &Path, key: &str, len: u64, at: DateTime<Local>) -> Result<u64, Box<dyn Error>> {
+    let (log_path, idx_path) = segment_paths(dir, key);
+    let mut idx = File::open(idx_path)?;
+    let mut log = BufReader::new(File::open(log_path)?);
+
+    let (mut lo, mut hi) = (0u64, len);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let mut entry = [0u8; INDEX_ENTRY_SIZE as usize];
+        idx.seek(SeekFrom::Start(mid * INDEX_ENTRY_SIZE))?;
+        idx.read_exact(&mut entry)?;
+        // An unreadable record can't be ordered; treat it as "before" so the
+        // search still terminates and skips over it.
+        let before = read_record(&mut log, u64::from_le_bytes(entry)).is_none_or(|r| r.inactivated < at);
+        if before {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    Ok(lo)
+}
+
+/// All records with `from <= inactivated < to`, oldest first. Only the month
+/// segments overlapping the range are opened, and each is entered by binary
+/// search, so the cost is proportional to the result rather than the archive.
+pub fn read_archive_range(from: DateTime<Local>, to: DateTime<Local>, exe_path: &PathBuf) -> Result<Vec<InActive>, Box<dyn Error>> {
+    let dir = archive_dir(exe_path)?;
+    read_between(&dir, Some(from), Some(to))
+}
+
+/// `read_archive_range` with either bound optionally open.
+fn read_between(dir: &Path, from: Option<DateTime<Local>>, to: Option<DateTime<Local>>) -> Result<Vec<InActive>, Box<dyn Error>> {
+    let first_key = from.map(segment_key);
+    let last_key = to.map(segment_key);
+
+    let mut rows = Vec::new();
+    for key in list_segments(dir)? {
+        if first_key.as_ref().is_some_and(|first| key < *first) || last_key.as_ref().is_some_and(|last| key > *last) {
+            continue;
+        }
+        let len = segment_len(dir, &key)?;
+        let start = match from {
+            Some(from) => segment_lower_bound(dir, &key, len, from)?,
+            None => 0,
+        };
+        let end = match to {
+            Some(to) => segment_lower_bound(dir, &key, len, to)?,
+            None => len,
+        };
+        rows.extend(read_segment_slice(dir, &key, start, end)?);
+    }
+    Ok(rows)
+}
+
+/// Every record matching `query`, in its sort order. Only the segments inside
+/// the query's date range are read; the other filters run in memory.
+pub fn query_archive(query: &ArchiveQuery, exe_path: &PathBuf) -> Result<Vec<InActive>, Box<dyn Error>> {
+    let dir = archive_dir(exe_path)?;
+    let mut rows = read_between(&dir, query.from, query.to)?;
+    query.apply(&mut rows);
+    Ok(rows)
+}
+
+/// The archive compaction/rebuild command: regenerates every segment's index
+/// from its log and moves unparseable lines aside, so "Show more" pages and
+/// counts line up with what can actually be displayed.
+pub fn rebuild_archive(exe_path: &PathBuf) -> Result<ArchiveRebuildReport, Box<dyn Error>> {
+    let dir = archive_dir(exe_path)?;
+    let mut report = ArchiveRebuildReport { segments: 0, records: 0, dropped: 0 };
+
+    for key in list_segments(&dir)? {
+        let (records, dropped) = rebuild_segment(&dir, &key, true)?;
+        report.segments += 1;
+        report.records += records;
+        report.dropped += dropped;
+    }
+    Ok(report)
+}
+
+/// Write the whole archive, oldest first, as one plain JSONL file — the same
+/// format the pre-segment `archived.jsonl` used. Returns the record count.
+pub fn export_archive_jsonl(dest: &Path, exe_path: &PathBuf) -> Result<u64, Box<dyn Error>> {
+    let dir = archive_dir(exe_path)?;
+    let mut out = Vec::new();
+    let mut count = 0;
+
+    for key in list_segments(&dir)? {
+        let len = segment_len(&dir, &key)?;
+        for record in read_segment_slice(&dir, &key, 0, len)? {
+            out.extend_from_slice(serde_json::to_string(&record)?.as_bytes());
+            out.push(b'\n');
+            count += 1;
+        }
     }
 
-    Ok(file.sync_all()?)
+    let dest_dir = dest.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    write_atomically(dest_dir, dest, &out)?;
+    Ok(count)
 }
 
-pub fn read_lines_range(offset: usize, limit: usize, exe_path: &PathBuf) -> Result<Vec<InActive>, Box<dyn Error>> {
+/// Whether a pre-segment `archived.jsonl` is waiting for `migrate_legacy_archive`.
+pub fn legacy_archive_pending(exe_path: &PathBuf) -> bool {
+    get_data_dir(exe_path).is_ok_and(|dir| dir.join(LEGACY_ARCHIVE_FILE).exists())
+}
+
+/// Every segment log as `(YYYY-MM key, contents)`, oldest first. The indexes are
+/// left out: they are derived data that `replace_archive` regenerates.
+pub fn read_archive_segments(exe_path: &PathBuf) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let dir = archive_dir(exe_path)?;
+    let mut segments = Vec::new();
+    for key in list_segments(&dir)? {
+        let (log_path, _) = segment_paths(&dir, &key);
+        segments.push((key, fs::read_to_string(log_path)?));
+    }
+    Ok(segments)
+}
+
+/// Swap the whole archive for the given segment logs (as produced by
+/// `read_archive_segments`) and rebuild their indexes. Keys that aren't
+/// `YYYY-MM` are rejected before anything is touched.
+pub fn replace_archive(segments: &[(String, String)], exe_path: &PathBuf) -> Result<ArchiveRebuildReport, Box<dyn Error>> {
+    if let Some((key, _)) = segments.iter().find(|(key, _)| !is_segment_key(key)) {
+        return Err(format!("\"{key}\" is not an archive segment name").into());
+    }
+
+    let dir = archive_dir(exe_path)?;
+    for key in list_segments(&dir)? {
+        let (log_path, idx_path) = segment_paths(&dir, &key);
+        fs::remove_file(log_path)?;
+        if idx_path.exists() {
+            fs::remove_file(idx_path)?;
+        }
+    }
+    for (key, contents) in segments {
+        let (log_path, _) = segment_paths(&dir, key);
+        write_atomically(&dir, &log_path, contents.as_bytes())?;
+    }
+    rebuild_archive(exe_path)
+}
+
+/// One-time import of a pre-segment `archived.jsonl` into the segmented
+/// archive. Records are built in a staging directory first and swapped in with
+/// a rename, so an interrupted migration simply re-runs on the next start. The
+/// legacy file is kept as `archived.jsonl.migrated-<timestamp>`. Returns a
+/// message only when something needs the user's attention (skipped lines).
+pub fn migrate_legacy_archive(exe_path: &PathBuf) -> Result<Option<String>, Box<dyn Error>> {
     let data_dir = get_data_dir(exe_path)?;
-    let path = data_dir.join("archived.jsonl");
+    let legacy_path = data_dir.join(LEGACY_ARCHIVE_FILE);
+    if !legacy_path.exists() {
+        return Ok(None);
+    }
+
+    let staging = data_dir.join(format!("{ARCHIVE_DIR}.migrating"));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+
+    let reader = BufReader::new(File::open(&legacy_path)?);
+    let mut skipped = 0;
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<InActive>(&line) {
+            Ok(item) => append_to_segment(&staging, &item)?,
+            Err(_) => skipped += 1,
+        }
+    }
+    for key in list_segments(&staging)? {
+        sort_segment(&staging, &key)?;
+    }
 
-    let file = File::open(path)?;
-    let rev_lines = RevLines::new(file);
+    let archive = data_dir.join(ARCHIVE_DIR);
+    let archive_is_empty = !archive.exists() || list_segments(&archive)?.is_empty();
+    if archive_is_empty {
+        if archive.exists() {
+            fs::remove_dir_all(&archive)?;
+        }
+        fs::rename(&staging, &archive)?;
+    } else {
+        // Segments already exist (a legacy file reappeared after migrating);
+        // merge record by record rather than overwrite them.
+        for key in list_segments(&staging)? {
+            let len = segment_len(&staging, &key)?;
+            for item in read_segment_slice(&staging, &key, 0, len)? {
+                append_to_segment(&archive, &item)?;
+            }
+            // Older legacy records land after newer ones; range reads
+            // binary-search each segment, so put it back in order.
+            sort_segment(&archive, &key)?;
+        }
+        fs::remove_dir_all(&staging)?;
+    }
 
-    let archives: Vec<InActive> = rev_lines
-        .skip(offset)
-        .take(limit)
-        .filter_map(|line| serde_json::from_str::<InActive>(&line.ok()?).ok())
-        .collect();
+    let timestamp = Local::now().format("%Y%m%d-%H%M%S");
+    fs::rename(&legacy_path, data_dir.join(format!("{LEGACY_ARCHIVE_FILE}.migrated-{timestamp}")))?;
 
-    Ok(archives)
+    if skipped > 0 {
+        Ok(Some(format!(
+            "{LEGACY_ARCHIVE_FILE} was converted to the indexed archive format.\n{skipped} unreadable line(s) were skipped; the original file was kept next to it."
+        )))
+    } else {
+        Ok(None)
+    }
 }
 
 #[cfg(test)]
@@ -293,6 +900,8 @@ mod tests {
             created: Local.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(),
             deadline,
             is_event,
+            outdoor: false,
+            location: None,
         }
     }
 
@@ -377,14 +986,17 @@ mod tests {
 
     #[test]
     fn quarantine_reports_when_no_file_present() {
-        // No taskdeck_data dir at all: nothing to move, but we still get a
-        // human-readable message rather than panicking.
+        // A portable install whose data dir has no such file: nothing to move,
+        // but we still get a human-readable message rather than panicking. The
+        // marker keeps the data dir inside the temp dir.
         let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join(crate::paths::PORTABLE_MARKER), "").unwrap();
         let fake_exe = tmp.path().join("app.exe");
         let cause = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
 
         let msg = quarantine_corrupt_file(&fake_exe, "colorschemes.json", &cause);
         assert!(msg.contains("colorschemes.json"), "message was {msg}");
+        assert!(!tmp.path().join("taskdeck_data").exists(), "looking up the data dir must not create it");
     }
 
     #[test]
@@ -445,5 +1057,269 @@ mod tests {
         let d2: Vec<u64> = buckets[&day2.date_naive()].iter().map(|x| x.id).collect();
         assert_eq!(d2, vec![3]);
     }
+
+    fn archived(id: u64, inactivated: DateTime<Local>) -> InActive {
+        InActive {
+            id,
+            importance: Some(2),
+            name: format!("item {id}"),
+            created: inactivated - chrono::Duration::days(3),
+            deadline: None,
+            is_event: false,
+            inactivated,
+            reason: ArchiveReason::Completed,
+        }
+    }
+
+    #[test]
+    fn archive_pages_newest_first_across_segments() {
+        let tmp = tempfile::tempdir().unwrap();
+        let exe = fake_exe_with_data_dir(&tmp);
+
+        // 40 items a day apart, spanning two month segments.
+        let start = Local.with_ymd_and_hms(2025, 5, 10, 12, 0, 0).unwrap();
+        for id in 1..=40 {
+            save_inactive(&archived(id, start + chrono::Duration::days(id as i64)), &exe).unwrap();
+        }
+
+        assert_eq!(archive_count(&exe).unwrap(), 40);
+
+        let first: Vec<u64> = read_archive_page(0, 15, &exe).unwrap().iter().map(|r| r.id).collect();
+        assert_eq!(first, (26..=40).rev().collect::<Vec<_>>());
+
+        // A page straddling the month boundary continues without gaps or repeats.
+        let second: Vec<u64> = read_archive_page(15, 15, &exe).unwrap().iter().map(|r| r.id).collect();
+        assert_eq!(second, (11..=25).rev().collect::<Vec<_>>());
+
+        let last = read_archive_page(30, 15, &exe).unwrap();
+        assert_eq!(last.len(), 10, "the final page is short");
+        assert!(read_archive_page(40, 15, &exe).unwrap().is_empty());
+    }
+
+    #[test]
+    fn archive_range_returns_only_matching_records() {
+        let tmp = tempfile::tempdir().unwrap();
+        let exe = fake_exe_with_data_dir(&tmp);
+
+        let start = Local.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        for id in 1..=90 {
+            save_inactive(&archived(id, start + chrono::Duration::days(id as i64)), &exe).unwrap();
+        }
+
+        let from = Local.with_ymd_and_hms(2025, 2, 25, 0, 0, 0).unwrap();
+        let to = Local.with_ymd_and_hms(2025, 3, 5, 0, 0, 0).unwrap();
+        let rows = read_archive_range(from, to, &exe).unwrap();
+
+        assert!(!rows.is_empty());
+        assert!(rows.iter().all(|r| r.inactivated >= from && r.inactivated < to));
+        // Oldest first, and nothing in range was missed.
+        let expected = (1..=90u64)
+            .filter(|id| {
+                let at = start + chrono::Duration::days(*id as i64);
+                at >= from && at < to
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(rows.iter().map(|r| r.id).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn rebuild_archive_restores_index_and_sets_aside_bad_lines() {
+        let tmp = tempfile::tempdir().unwrap();
+        let exe = fake_exe_with_data_dir(&tmp);
+
+        let at = Local.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        for id in 1..=3 {
+            save_inactive(&archived(id, at + chrono::Duration::hours(id as i64)), &exe).unwrap();
+        }
+
+        let archive = tmp.path().join("taskdeck_data").join("archive");
+        // Lose the index and corrupt the log with a hand-edited line.
+        fs::remove_file(archive.join("2025-06.idx")).unwrap();
+        let mut log = OpenOptions::new().append(true).open(archive.join("2025-06.jsonl")).unwrap();
+        log.write_all(b"{ not json\n").unwrap();
+
+        let report = rebuild_archive(&exe).unwrap();
+        assert_eq!(report.segments, 1);
+        assert_eq!(report.records, 3);
+        assert_eq!(report.dropped, 1);
+
+        // Every index entry is displayable again, so counts and pages agree.
+        assert_eq!(archive_count(&exe).unwrap(), 3);
+        assert_eq!(read_archive_page(0, 15, &exe).unwrap().len(), 3);
+        let set_aside = fs::read_dir(&archive)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .any(|e| e.file_name().to_string_lossy().starts_with("2025-06.jsonl.corrupt-"));
+        assert!(set_aside, "the unreadable line should be kept for manual recovery");
+    }
+
+    #[test]
+    fn legacy_archive_is_migrated_and_exports_back_to_jsonl() {
+        let tmp = tempfile::tempdir().unwrap();
+        let exe = fake_exe_with_data_dir(&tmp);
+        let data_dir = tmp.path().join("taskdeck_data");
+
+        let at = Local.with_ymd_and_hms(2025, 3, 30, 12, 0, 0).unwrap();
+        let mut legacy = String::new();
+        for id in 1..=5 {
+            legacy.push_str(&serde_json::to_string(&archived(id, at + chrono::Duration::days(id as i64))).unwrap());
+            legacy.push('\n');
+        }
+        legacy.push_str("garbage\n");
+        fs::write(data_dir.join("archived.jsonl"), &legacy).unwrap();
+
+        let message = migrate_legacy_archive(&exe).unwrap();
+        assert!(message.is_some_and(|m| m.contains("1 unreadable")), "skipped lines are reported");
+        assert!(!data_dir.join("archived.jsonl").exists(), "legacy file is moved aside");
+        assert_eq!(archive_count(&exe).unwrap(), 5);
+        // A second run is a no-op.
+        assert!(migrate_legacy_archive(&exe).unwrap().is_none());
+
+        let dest = data_dir.join("export.jsonl");
+        assert_eq!(export_archive_jsonl(&dest, &exe).unwrap(), 5);
+        let exported: Vec<u64> = fs::read_to_string(&dest)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<InActive>(line).unwrap().id)
+            .collect();
+        assert_eq!(exported, vec![1, 2, 3, 4, 5], "export is oldest first, like the legacy log");
+    }
+
+    #[test]
+    fn legacy_records_merged_into_a_segment_keep_it_ordered() {
+        let tmp = tempfile::tempdir().unwrap();
+        let exe = fake_exe_with_data_dir(&tmp);
+        let data_dir = tmp.path().join("taskdeck_data");
+
+        let day = |d: u32| Local.with_ymd_and_hms(2025, 7, d, 12, 0, 0).unwrap();
+        save_inactive(&archived(10, day(10)), &exe).unwrap();
+        save_inactive(&archived(20, day(20)), &exe).unwrap();
+
+        // A legacy file that reappeared, itself out of order.
+        let legacy: String = [15, 5].iter().map(|d| serde_json::to_string(&archived(*d as u64, day(*d))).unwrap() + "\n").collect();
+        fs::write(data_dir.join("archived.jsonl"), legacy).unwrap();
+        assert!(migrate_legacy_archive(&exe).unwrap().is_none());
+
+        let ids = |rows: Vec<InActive>| rows.iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(ids(read_archive_range(day(1), day(31), &exe).unwrap()), vec![5, 10, 15, 20]);
+        assert_eq!(ids(read_archive_range(day(12), day(31), &exe).unwrap()), vec![15, 20]);
+        assert_eq!(ids(read_archive_page(0, 2, &exe).unwrap()), vec![20, 15]);
+    }
+
+    #[test]
+    fn archive_query_filters_and_sorts() {
+        let at = Local.with_ymd_and_hms(2025, 4, 1, 12, 0, 0).unwrap();
+        let mut rows = Vec::new();
+        for id in 1..=6u64 {
+            let mut row = archived(id, at + chrono::Duration::days(id as i64));
+            row.name = ["delta", "Alpha", "charlie", "bravo", "echo", "foxtrot"][id as usize - 1].to_string();
+            row.is_event = id % 3 == 0;
+            row.importance = if row.is_event { None } else { Some((id % 2) as u8 + 1) };
+            row.reason = if id % 2 == 0 { ArchiveReason::Deleted } else { ArchiveReason::Completed };
+            rows.push(row);
+        }
+
+        let query = ArchiveQuery { is_event: Some(false), sort: ArchiveSort::Name, descending: false, ..ArchiveQuery::default() };
+        let mut tasks_by_name = rows.clone();
+        query.apply(&mut tasks_by_name);
+        let names: Vec<&str> = tasks_by_name.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["Alpha", "bravo", "delta", "echo"], "name sort ignores case");
+
+        let query = ArchiveQuery {
+            reason: Some(ArchiveReason::Completed),
+            from: Some(at + chrono::Duration::days(2)),
+            ..ArchiveQuery::default()
+        };
+        let mut completed = rows.clone();
+        query.apply(&mut completed);
+        assert_eq!(completed.iter().map(|r| r.id).collect::<Vec<_>>(), vec![5, 3], "newest first by default");
+
+        let query = ArchiveQuery { importance: Some(2), ..ArchiveQuery::default() };
+        assert!(rows.iter().filter(|r| query.matches(r)).all(|r| r.importance == Some(2) && !r.is_event));
+        assert!(ArchiveQuery::default().is_default());
+    }
+
+    #[test]
+    fn query_archive_reads_only_the_requested_range() {
+        let tmp = tempfile::tempdir().unwrap();
+        let exe = fake_exe_with_data_dir(&tmp);
+
+        let start = Local.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        for id in 1..=60 {
+            let mut row = archived(id, start + chrono::Duration::days(id as i64));
+            if id % 4 == 0 {
+                row.reason = ArchiveReason::Deleted;
+            }
+            save_inactive(&row, &exe).unwrap();
+        }
+
+        let query = ArchiveQuery {
+            reason: Some(ArchiveReason::Deleted),
+            to: Some(Local.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap()),
+            ..ArchiveQuery::default()
+        };
+        let ids: Vec<u64> = query_archive(&query, &exe).unwrap().iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![28, 24, 20, 16, 12, 8, 4]);
+    }
+
+    #[test]
+    fn archive_stats_counts_weeks_and_lead_time() {
+        // 2025-06-02 is a Monday.
+        let monday = Local.with_ymd_and_hms(2025, 6, 2, 9, 0, 0).unwrap();
+        let mut rows = vec![
+            archived(1, monday),
+            archived(2, monday + chrono::Duration::days(3)),
+            archived(3, monday + chrono::Duration::weeks(2)),
+            archived(4, monday + chrono::Duration::weeks(2)),
+        ];
+        rows[0].created = monday - chrono::Duration::hours(10);
+        rows[1].created = monday + chrono::Duration::days(3) - chrono::Duration::hours(20);
+        rows[2].created = monday + chrono::Duration::weeks(2) - chrono::Duration::hours(30);
+        rows[3].reason = ArchiveReason::Deleted;
+        rows.push(archived(5, monday));
+        rows[4].reason = ArchiveReason::ExpiredEvent;
+
+        let stats = archive_stats(&rows);
+        assert_eq!(stats.completed, 3);
+        assert_eq!(stats.deleted, 1);
+        assert_eq!(stats.expired, 1);
+        assert_eq!(stats.merged, 0);
+        let weeks: Vec<u32> = stats.weekly_completions.iter().map(|(_, n)| *n).collect();
+        assert_eq!(weeks, vec![2, 0, 1], "the empty middle week is kept");
+        assert_eq!(stats.weekly_completions[0].0, monday.date_naive());
+        assert_eq!(stats.completions_per_week(), 1.0);
+        assert_eq!(stats.average_lead_time, Some(chrono::Duration::hours(20)), "only completions count towards lead time");
+
+        assert_eq!(archive_stats(&[]).average_lead_time, None);
+    }
+
+    #[test]
+    fn expired_event_ids_only_sweeps_old_events() {
+        let now = Local.with_ymd_and_hms(2025, 6, 15, 12, 0, 0).unwrap();
+        let mut items = vec![
+            active(None, None, true, Some(now - chrono::Duration::days(10))),
+            active(None, None, true, Some(now - chrono::Duration::days(2))),
+            active(Some(3), None, false, Some(now - chrono::Duration::days(30))),
+            active(None, None, true, None),
+        ];
+        for (i, item) in items.iter_mut().enumerate() {
+            item.id = i as u64 + 1;
+        }
+
+        assert_eq!(expired_event_ids(&items, now, chrono::Duration::days(7)), vec![1], "overdue tasks stay");
+        assert_eq!(expired_event_ids(&items, now, chrono::Duration::days(1)), vec![1, 2]);
+    }
+
+    #[test]
+    fn archive_reason_defaults_to_completed_for_old_rows() {
+        let row = r#"{"id":4,"importance":null,"name":"old","created":"2024-01-01T10:00:00+02:00","deadline":null,"is_event":false,"inactivated":"2024-01-02T10:00:00+02:00"}"#;
+        let parsed: InActive = serde_json::from_str(row).unwrap();
+        assert_eq!(parsed.reason, ArchiveReason::Completed);
+
+        let mut expired = parsed.clone();
+        expired.reason = ArchiveReason::ExpiredEvent;
+        let round_trip: InActive = serde_json::from_str(&serde_json::to_string(&expired).unwrap()).unwrap();
+        assert_eq!(round_trip.reason, ArchiveReason::ExpiredEvent);
+    }
 }
 
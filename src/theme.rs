@@ -0,0 +1,326 @@
+use std::{collections::HashMap, error::Error, fs, path::PathBuf, sync::Arc};
+
+use egui::{Color32, Context, FontData, FontDefinitions, FontFamily, FontId, Id, TextStyle, Visuals};
+use serde::{Deserialize, Serialize};
+
+/// Font family for day numbers and the weekday heading.
+pub const NUMBERS: &str = "numbers";
+/// Font family for times and the item names on the calendar.
+pub const DETAIL: &str = "detail";
+/// Font family for the task list.
+pub const TASKS: &str = "tasks";
+/// Font family for the items in the day popup.
+pub const LIST: &str = "list";
+
+/// Ids of the built-in themes, which are always there and can't be edited.
+pub const DARK_THEME_ID: u32 = 0;
+pub const LIGHT_THEME_ID: u32 = 1;
+
+const THEMES_FILE: &str = "themes.json";
+
+/// The fonts compiled into the binary.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum EmbeddedFont {
+    Fixedsys,
+    DejaVuSans,
+    Anton,
+    SpaceMono,
+    LexendGiga,
+    FacultyGlyphic,
+}
+
+impl EmbeddedFont {
+    pub const ALL: [EmbeddedFont; 6] = [EmbeddedFont::Fixedsys, EmbeddedFont::DejaVuSans, EmbeddedFont::Anton, EmbeddedFont::SpaceMono, EmbeddedFont::LexendGiga, EmbeddedFont::FacultyGlyphic];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            EmbeddedFont::Fixedsys => "Fixedsys",
+            EmbeddedFont::DejaVuSans => "DejaVu Sans",
+            EmbeddedFont::Anton => "Anton",
+            EmbeddedFont::SpaceMono => "Space Mono",
+            EmbeddedFont::LexendGiga => "Lexend Giga",
+            EmbeddedFont::FacultyGlyphic => "Faculty Glyphic",
+        }
+    }
+
+    /// Name of the font's data in `FontDefinitions`.
+    fn key(self) -> &'static str {
+        match self {
+            EmbeddedFont::Fixedsys => "fixedsys",
+            EmbeddedFont::DejaVuSans => "dejavu",
+            EmbeddedFont::Anton => "anton",
+            EmbeddedFont::SpaceMono => "space",
+            EmbeddedFont::LexendGiga => "spaceb",
+            EmbeddedFont::FacultyGlyphic => "bungee",
+        }
+    }
+
+    fn data(self) -> &'static [u8] {
+        match self {
+            EmbeddedFont::Fixedsys => include_bytes!(r#"../fonts/FSEX300.ttf"#),
+            EmbeddedFont::DejaVuSans => include_bytes!(r#"../fonts/DejaVuSans.ttf"#),
+            EmbeddedFont::Anton => include_bytes!(r#"../fonts/Anton-Regular.ttf"#),
+            EmbeddedFont::SpaceMono => include_bytes!(r#"../fonts/SpaceMono-Regular.ttf"#),
+            EmbeddedFont::LexendGiga => include_bytes!(r#"../fonts/LexendGiga-Light.ttf"#),
+            EmbeddedFont::FacultyGlyphic => include_bytes!(r#"../fonts/FacultyGlyphic-Regular.ttf"#),
+        }
+    }
+}
+
+/// The font for each role. `interface` is egui's text styles (buttons,
+/// labels, headings); the rest are the families named by the constants above.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ThemeFonts {
+    pub interface: EmbeddedFont,
+    pub numbers: EmbeddedFont,
+    pub detail: EmbeddedFont,
+    pub tasks: EmbeddedFont,
+    pub list: EmbeddedFont,
+}
+
+impl Default for ThemeFonts {
+    fn default() -> Self {
+        Self {
+            interface: EmbeddedFont::Fixedsys,
+            numbers: EmbeddedFont::Anton,
+            detail: EmbeddedFont::SpaceMono,
+            tasks: EmbeddedFont::FacultyGlyphic,
+            list: EmbeddedFont::LexendGiga,
+        }
+    }
+}
+
+/// Everything about the look that isn't the calendar tints of a `ColorScheme`.
+/// Colours are unmultiplied RGBA, as in schemes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    /// Start from egui's light visuals rather than its dark ones.
+    pub light: bool,
+    /// egui's text colour: widgets, and day numbers on other days than today.
+    pub text: [u8; 4],
+    /// Headings and labels drawn straight on the background.
+    pub heading_text: [u8; 4],
+    /// Notes, ages and other secondary text.
+    pub weak_text: [u8; 4],
+    /// Selections, links and the date marks.
+    pub accent: [u8; 4],
+    /// Frames and marks; the alpha is that of a plain frame.
+    pub stroke: [u8; 4],
+    /// Panels and windows.
+    pub panel: [u8; 4],
+    /// Today's day number.
+    pub today: [u8; 4],
+    /// The item names on the calendar's fills.
+    pub item_text: [u8; 4],
+    pub fonts: ThemeFonts,
+    pub is_user_configurable: bool,
+}
+
+fn color([r, g, b, a]: [u8; 4]) -> Color32 {
+    Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+impl Theme {
+    /// The look TaskDeck always had.
+    pub fn dark() -> Self {
+        Self {
+            name: "Dark".to_string(),
+            light: false,
+            text: [140, 140, 140, 255],
+            heading_text: [255, 255, 255, 165],
+            weak_text: [255, 255, 255, 120],
+            accent: [98, 114, 164, 255],
+            stroke: [255, 255, 255, 55],
+            panel: [27, 27, 27, 255],
+            today: [255, 255, 255, 255],
+            item_text: [150, 150, 150, 255],
+            fonts: ThemeFonts::default(),
+            is_user_configurable: false,
+        }
+    }
+
+    /// Dark text on pale panels, for bright rooms.
+    pub fn light() -> Self {
+        Self {
+            name: "Light".to_string(),
+            light: true,
+            text: [60, 60, 60, 255],
+            heading_text: [0, 0, 0, 200],
+            weak_text: [0, 0, 0, 150],
+            accent: [50, 80, 160, 255],
+            stroke: [0, 0, 0, 70],
+            panel: [238, 236, 230, 255],
+            today: [170, 60, 20, 255],
+            item_text: [35, 35, 35, 255],
+            fonts: ThemeFonts::default(),
+            is_user_configurable: false,
+        }
+    }
+
+    pub fn duplicate(&self) -> Self {
+        Self { name: format!("DUPLICATE - '{}'", self.name), is_user_configurable: true, ..self.clone() }
+    }
+
+    pub fn heading_text_color(&self) -> Color32 {
+        color(self.heading_text)
+    }
+    pub fn weak_text_color(&self) -> Color32 {
+        color(self.weak_text)
+    }
+    pub fn accent_color(&self) -> Color32 {
+        color(self.accent)
+    }
+    pub fn item_text_color(&self) -> Color32 {
+        color(self.item_text)
+    }
+    pub fn panel_color(&self) -> Color32 {
+        color(self.panel)
+    }
+    /// The frame colour.
+    pub fn stroke_color(&self) -> Color32 {
+        color(self.stroke)
+    }
+    /// The frame colour at another alpha, for marks that are stronger or
+    /// fainter than a frame.
+    pub fn stroke_with_alpha(&self, alpha: u8) -> Color32 {
+        let [r, g, b, _] = self.stroke;
+        color([r, g, b, alpha])
+    }
+
+    /// egui's visuals for this theme.
+    pub fn visuals(&self) -> Visuals {
+        let mut visuals = if self.light { Visuals::light() } else { Visuals::dark() };
+        visuals.panel_fill = color(self.panel);
+        visuals.window_fill = color(self.panel);
+        visuals.widgets.noninteractive.fg_stroke.color = color(self.text);
+        visuals.widgets.active.fg_stroke.color = color(self.today);
+        visuals.selection.bg_fill = color(self.accent);
+        visuals.hyperlink_color = color(self.accent);
+        visuals
+    }
+}
+
+/// The built-in themes under their fixed ids.
+pub fn builtin_themes() -> [(u32, Theme); 2] {
+    [(DARK_THEME_ID, Theme::dark()), (LIGHT_THEME_ID, Theme::light())]
+}
+
+fn font_definitions(choice: &ThemeFonts) -> FontDefinitions {
+    let mut fonts = FontDefinitions::default();
+    for font in EmbeddedFont::ALL {
+        fonts.font_data.insert(font.key().to_owned(), Arc::new(FontData::from_static(font.data())));
+    }
+
+    // egui's text styles all use Monospace; DejaVu and Space Mono fill in
+    // the glyphs a pixel font lacks.
+    let monospace = fonts.families.entry(FontFamily::Monospace).or_default();
+    monospace.clear();
+    for font in [choice.interface, EmbeddedFont::DejaVuSans, EmbeddedFont::SpaceMono] {
+        if !monospace.iter().any(|key| key == font.key()) {
+            monospace.push(font.key().to_owned());
+        }
+    }
+    fonts.families.entry(FontFamily::Proportional).or_default().push(EmbeddedFont::LexendGiga.key().to_owned());
+
+    for (family, font) in [(NUMBERS, choice.numbers), (DETAIL, choice.detail), (TASKS, choice.tasks), (LIST, choice.list)] {
+        fonts.families.insert(FontFamily::Name(family.into()), vec![font.key().to_owned(), EmbeddedFont::DejaVuSans.key().to_owned()]);
+    }
+    fonts
+}
+
+fn theme_id() -> Id {
+    Id::new("taskdeck_theme")
+}
+
+/// Makes `theme` the look of the whole UI: egui's visuals and text styles,
+/// the fonts, and the colours the custom widgets read with [`current`].
+pub fn install(ctx: &Context, theme: &Theme) {
+    let mut style = (*ctx.global_style()).clone();
+    style.visuals = theme.visuals();
+    style.text_styles = [
+        (TextStyle::Heading, FontId::new(30.0, FontFamily::Monospace)),
+        (TextStyle::Body, FontId::new(18.0, FontFamily::Monospace)),
+        (TextStyle::Button, FontId::new(22.0, FontFamily::Monospace)),
+        (TextStyle::Small, FontId::new(11.0, FontFamily::Monospace)),
+        (TextStyle::Monospace, FontId::new(11.0, FontFamily::Monospace)),
+    ]
+    .into();
+    ctx.set_global_style(style);
+    ctx.set_fonts(font_definitions(&theme.fonts));
+    ctx.data_mut(|data| data.insert_temp(theme_id(), Arc::new(theme.clone())));
+}
+
+/// The theme last installed, or the dark one before that.
+pub fn current(ctx: &Context) -> Arc<Theme> {
+    ctx.data(|data| data.get_temp(theme_id())).unwrap_or_else(|| Arc::new(Theme::dark()))
+}
+
+/// Reads the user's themes from `themes.json` (none if there is no file) and
+/// adds the built-in ones.
+pub fn read_themes(exe_path: &PathBuf) -> Result<HashMap<u32, Theme>, Box<dyn Error>> {
+    let path = crate::tasks::get_data_dir(exe_path)?.join(THEMES_FILE);
+    let mut themes: HashMap<u32, Theme> = if path.exists() { serde_json::from_str(&fs::read_to_string(&path)?)? } else { HashMap::new() };
+    themes.extend(builtin_themes());
+    Ok(themes)
+}
+
+/// Writes the user's themes; the built-in ones are left out.
+pub fn save_themes(themes: &HashMap<u32, Theme>, exe_path: &PathBuf) -> Result<(), Box<dyn Error>> {
+    let data_dir = crate::tasks::get_data_dir(exe_path)?;
+    fs::create_dir_all(&data_dir)?;
+    let own: HashMap<&u32, &Theme> = themes.iter().filter(|(_, theme)| theme.is_user_configurable).collect();
+    crate::tasks::write_atomically(&data_dir, &data_dir.join(THEMES_FILE), serde_json::to_string_pretty(&own)?.as_bytes())
+}
+
+/// One past the highest id, so built-in and deleted ids aren't reused.
+pub fn fresh_theme_id(themes: &HashMap<u32, Theme>) -> u32 {
+    themes.keys().max().map_or(LIGHT_THEME_ID + 1, |id| id.saturating_add(1).max(LIGHT_THEME_ID + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tasks::fake_exe_with_data_dir;
+
+    #[test]
+    fn user_themes_round_trip_and_built_ins_are_always_there() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = fake_exe_with_data_dir(&dir);
+
+        let themes = read_themes(&exe).unwrap();
+        assert_eq!(themes.len(), 2);
+        assert_eq!(themes[&DARK_THEME_ID], Theme::dark());
+        assert_eq!(fresh_theme_id(&themes), 2);
+
+        let mut themes = themes;
+        let mut mine = Theme::light().duplicate();
+        mine.fonts.numbers = EmbeddedFont::DejaVuSans;
+        mine.accent = [200, 30, 90, 255];
+        themes.insert(fresh_theme_id(&themes), mine.clone());
+        // Edits to a built-in are not saved.
+        themes.get_mut(&LIGHT_THEME_ID).unwrap().panel = [0, 0, 0, 255];
+        save_themes(&themes, &exe).unwrap();
+
+        let saved = fs::read_to_string(dir.path().join("taskdeck_data").join(THEMES_FILE)).unwrap();
+        assert!(!saved.contains("\"Dark\"") && saved.contains("DejaVuSans"), "{saved}");
+        let read = read_themes(&exe).unwrap();
+        assert_eq!(read[&2], mine);
+        assert_eq!(read[&LIGHT_THEME_ID], Theme::light());
+    }
+
+    #[test]
+    fn visuals_and_fonts_follow_the_theme() {
+        let light = Theme::light();
+        let visuals = light.visuals();
+        assert!(!visuals.dark_mode);
+        assert_eq!(visuals.panel_fill, light.panel_color());
+        assert_eq!(visuals.strong_text_color(), Color32::from_rgb(170, 60, 20));
+        assert_eq!(light.stroke_with_alpha(255), Color32::BLACK);
+
+        let fonts = font_definitions(&ThemeFonts { interface: EmbeddedFont::DejaVuSans, numbers: EmbeddedFont::SpaceMono, ..Default::default() });
+        assert_eq!(fonts.families[&FontFamily::Monospace], ["dejavu", "space"]);
+        assert_eq!(fonts.families[&FontFamily::Name(NUMBERS.into())], ["space", "dejavu"]);
+        assert_eq!(fonts.font_data.len(), EmbeddedFont::ALL.len() + FontDefinitions::default().font_data.len());
+    }
+}
@@ -1,11 +1,14 @@
-use std::{collections::HashMap, error::Error, fs, path::PathBuf, process::{Command, exit}, sync::{Arc, atomic::Ordering}, time::Instant};
+use std::{collections::HashMap, error::Error, fs, path::{Path, PathBuf}, process::{Command, exit}, sync::atomic::Ordering, time::Instant};
 
-use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Timelike, Weekday};
-use egui::{self, Align, Button, Color32, ColorImage, ComboBox, Context, CornerRadius, Event, FontData, FontDefinitions, FontFamily, FontId, Grid, Key, Label, Layout, Margin, PointerButton, Pos2, Rect, RichText, Stroke, StrokeKind, TextureHandle, Ui, Vec2, ViewportCommand, pos2, vec2};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Weekday};
+use egui::{self, Align, Button, Color32, ColorImage, ComboBox, Context, CornerRadius, Event, FontFamily, FontId, Grid, Key, Label, Layout, Margin, PointerButton, Pos2, Rect, RichText, Stroke, StrokeKind, TextureHandle, Ui, Vec2, ViewportCommand, pos2, vec2};
 use image::{ImageBuffer, Rgba};
 use toml_edit::{DocumentMut};
 
-use crate::{calendarwidgets, color::{self, ColorScheme}, utilities::{self, next_three_weekdays, resolve_colorscheme}, tasks::{self, Active, InActive}, weather::{self, WeatherService}};
+use crate::{alerts::{Alert, AlertRules, MAX_ALERT_HORIZON_HOURS}, backup::{self, SnapshotKind}, cli::LaunchOptions, initialization::{self, Config, ConfigFiles, ConfigWatcher}, calendarwidgets, color::{self, Backdrop, ColorScheme, ColorVision, ContrastGoal, ImportedScheme, SchemeFormat}, gazetteer::{self, Place}, quantize::{PaletteAlgorithm, PaletteOptions}, theme::{self, EmbeddedFont, Theme}, utilities::{self, resolve_colorscheme}, solar::{self, SunDay}, tasks::{self, Active, ArchiveReason, ArchiveSort, InActive}, units::{PrecipitationUnit, Rounding, TemperatureUnit, Units, WindUnit}, weather::{self, DayPhase, DaySummary, Forecast, HourDetails, Location, RefreshPolicy, WeatherProviderKind, WeatherService}};
+
+/// Weather alerts: the banner over the forecast and flagged outdoor events.
+const ALERT_COLOR: Color32 = Color32::from_rgb(255, 176, 60);
 
 const WEEK_DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
 
@@ -43,6 +46,24 @@ struct DayItem {
     name: String,
     time: String,
     is_event: bool,
+    /// Tagged as happening outside; flagged on days with a weather alert.
+    outdoor: bool,
+    /// An event's own weather location (`Active::location`).
+    location: Option<Location>,
+}
+
+/// What the day popup shows of the weather for one `DayItem`.
+struct ItemWeather {
+    /// The alert flagging an outdoor item.
+    alert: Option<String>,
+    /// An event's own location and its forecast for the day, once fetched.
+    place: Option<(String, Option<DaySummary>)>,
+}
+
+/// What only events have, for `add_active_thing`.
+struct NewEvent {
+    outdoor: bool,
+    location: Option<Location>,
 }
 
 /// One day cell of the calendar model, cached in `TaskApp::calendar_elements`
@@ -80,21 +101,202 @@ impl FpsCounter {
     }
 }
 
+/// One forecast slot as shown in `display_stuff`, `weather_window_hours` long.
+struct WeatherSlot {
+    /// "HH:MM" of the first hour.
+    time: String,
+    /// The slot runs until the next one starts (or midnight), which on a DST
+    /// day is not always `weather_window_hours` later. Used for the highlight.
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    /// Mean over the slot's hours in the display units, rounded only when shown.
+    temp: f64,
+    symbol: u8,
+    phase: DayPhase,
+    /// Whether the forecast still reaches this slot; past its end the slot
+    /// stays empty.
+    covered: bool,
+    details: HourDetails,
+    /// The sun is down at the slot's midpoint; shaded with `night_bands`.
+    night: bool,
+}
+
+/// One day of the forecast panel.
+struct WeatherDay {
+    /// Weekday name.
+    label: String,
+    /// Sunrise, sunset and golden hours at the forecast's coordinates.
+    sun: SunDay,
+    slots: Vec<WeatherSlot>,
+}
+
+/// A weather alert threshold in Settings: a checkbox that turns the rule on
+/// (at `default`) or off, and the value in display units. `shown` and
+/// `stored` convert from and to the metric the rules are kept in.
+fn alert_threshold_input(
+    ui: &mut Ui,
+    label: &str,
+    threshold: &mut Option<f64>,
+    default: Option<f64>,
+    unit: &str,
+    shown: impl Fn(f64) -> f64,
+    stored: impl Fn(f64) -> f64,
+) {
+    let mut enabled = threshold.is_some();
+    ui.checkbox(&mut enabled, label);
+    match (enabled, *threshold) {
+        (false, _) => *threshold = None,
+        (true, None) => *threshold = default.or(Some(0.0)),
+        (true, Some(metric)) => {
+            let mut value = shown(metric);
+            if ui.add(egui::DragValue::new(&mut value).speed(0.5).max_decimals(1).suffix(format!(" {unit}"))).changed() {
+                *threshold = Some((stored(value) * 10.0).round() / 10.0);
+            }
+        }
+    }
+}
+
+/// "↑ 04:54  ↓ 22:43  18h 49m" in local time, for a forecast day's header.
+fn sun_text(sun: &SunDay) -> String {
+    match (sun.sunrise, sun.sunset) {
+        (None, None) if sun.daylight > Duration::zero() => "Sun up all day".to_string(),
+        (None, None) => "Sun down all day".to_string(),
+        (sunrise, sunset) => {
+            let time = |at: Option<DateTime<chrono::Utc>>| at.map_or("–".to_string(), |at| at.with_timezone(&Local).format("%H:%M").to_string());
+            format!("↑ {}  ↓ {}  {}", time(sunrise), time(sunset), utilities::format_duration_short(sun.daylight))
+        }
+    }
+}
+
+/// Hover text for the sun line: the golden hours, where the sun reaches 6°.
+fn golden_hour_text(sun: &SunDay) -> Option<String> {
+    let time = |at: DateTime<chrono::Utc>| at.with_timezone(&Local).format("%H:%M").to_string();
+    match (sun.sunrise, sun.golden_morning_end, sun.golden_evening_start, sun.sunset) {
+        (Some(sunrise), Some(morning_end), Some(evening_start), Some(sunset)) => Some(format!(
+            "Golden hour {}–{} and {}–{}",
+            time(sunrise), time(morning_end), time(evening_start), time(sunset)
+        )),
+        // The sun rises but stays low all day.
+        (Some(sunrise), None, None, Some(sunset)) => Some(format!("Golden hour all day, {}–{}", time(sunrise), time(sunset))),
+        _ => None,
+    }
+}
+
+/// The optional row under a forecast slot: a bar filled to the chance of
+/// precipitation (blue once any is expected) and an arrow pointing where the
+/// wind blows to, with its speed. Left empty where the data has nothing.
+fn paint_weather_details(ui: &mut Ui, slot: &WeatherSlot, units: Units) {
+    let (rect, _) = ui.allocate_exact_size(vec2(60.0, 12.0), egui::Sense::hover());
+    if !slot.covered {
+        return;
+    }
+    let theme = theme::current(ui.ctx());
+    let painter = ui.painter();
+    let details = slot.details;
+
+    if let Some(probability) = details.precipitation_probability.or(details.precipitation.map(|amount| if amount > 0.0 { 100.0 } else { 0.0 })) {
+        let track = Rect::from_min_size(pos2(rect.left(), rect.center().y - 2.5), vec2(24.0, 5.0));
+        painter.rect_filled(track, CornerRadius::same(2), theme.stroke_with_alpha(30));
+        let filled = track.width() * (probability.clamp(0.0, 100.0) / 100.0) as f32;
+        if filled > 0.0 {
+            let wet = details.precipitation.is_some_and(|amount| units.precipitation.to_mm(amount) >= 0.1);
+            let color = if wet { Color32::from_rgb(110, 170, 255) } else { theme.weak_text_color() };
+            painter.rect_filled(Rect::from_min_size(track.min, vec2(filled, track.height())), CornerRadius::same(2), color);
+        }
+    }
+
+    if let Some(speed) = details.wind_speed {
+        let center = pos2(rect.left() + 35.0, rect.center().y);
+        let stroke = Stroke::new(1.2, theme.stroke_with_alpha(150));
+        if let Some(from) = details.wind_direction {
+            // Screen y grows downwards; 0° (from the north) points south.
+            let towards = (from as f32 + 180.0).to_radians();
+            let dir = vec2(towards.sin(), -towards.cos());
+            let tip = center + dir * 5.0;
+            painter.line_segment([center - dir * 5.0, tip], stroke);
+            let side = vec2(-dir.y, dir.x);
+            painter.line_segment([tip, tip - dir * 3.0 + side * 2.5], stroke);
+            painter.line_segment([tip, tip - dir * 3.0 - side * 2.5], stroke);
+        }
+        painter.text(
+            pos2(rect.right(), rect.center().y),
+            egui::Align2::RIGHT_CENTER,
+            format!("{speed:.0}"),
+            FontId::proportional(10.0),
+            theme.weak_text_color(),
+        );
+    }
+}
+
+/// Hover text for a forecast slot, or `None` if the provider sent no details.
+/// `details` is already in `units`.
+fn weather_details_text(details: &HourDetails, units: Units, rounding: Rounding) -> Option<String> {
+    let mut lines = Vec::new();
+    if let Some(feels) = details.apparent_temp {
+        lines.push(format!("Feels like {}{}", rounding.format(feels), units.temperature.label()));
+    }
+    match (details.precipitation, details.precipitation_probability) {
+        (Some(amount), Some(chance)) => lines.push(format!("Precipitation {} ({chance:.0}%)", units.precipitation.format(amount))),
+        (Some(amount), None) => lines.push(format!("Precipitation {}", units.precipitation.format(amount))),
+        (None, Some(chance)) => lines.push(format!("Precipitation {chance:.0}%")),
+        (None, None) => {}
+    }
+    if let Some(speed) = details.wind_speed {
+        let mut wind = format!("Wind {speed:.0} {}", units.wind.label());
+        if let Some(gusts) = details.wind_gusts {
+            wind += &format!(", gusts {gusts:.0}");
+        }
+        if let Some(from) = details.wind_direction {
+            wind += &format!(" from {}", utilities::compass_point(from));
+        }
+        lines.push(wind);
+    }
+    if let Some(uv) = details.uv_index {
+        lines.push(format!("UV index {uv:.0}"));
+    }
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
 pub struct TaskAppConfig {
     pub colorschemes: HashMap<u32, ColorScheme>,
     pub selected_colorscheme_id: u32,
+    /// The built-in themes and the user's, from `themes.json`.
+    pub themes: HashMap<u32, Theme>,
+    pub selected_theme_id: u32,
     pub active_items: Vec<Active>,
     pub exe_file_path: PathBuf,
+    /// `userconfig.toml` (with any profile) and the backgrounds folder, as
+    /// resolved by `paths::data_paths` and the command line.
+    pub config_files: ConfigFiles,
+    pub images_dir: PathBuf,
+    /// The command line, whose overrides outlast a config reload.
+    pub launch: LaunchOptions,
     pub background: String,
     pub background_options: Vec<String>,
-    pub coordinates: [f32; 2],
+    pub locations: Vec<Location>,
+    pub weather_location: String,
     pub start_in_fullscreen: bool,
+    /// Only kept to tell whether a reloaded config changed it.
+    pub window_size_startup: [f32; 2],
     pub enable_fps_counter: bool,
     pub calendar_weeks_to_show: usize,
     pub selected_monitor_name: String,
     pub textbox_text: String,
     pub three_day_weather: bool,
+    pub weather_details_row: bool,
+    pub calendar_weather: bool,
+    pub night_bands: bool,
+    pub night_dimming: bool,
+    pub item_cues: bool,
+    pub alert_rules: AlertRules,
+    pub weather_refresh: RefreshPolicy,
+    pub units: Units,
+    pub temperature_rounding: Rounding,
+    pub weather_window_hours: u32,
+    pub forecast_days: usize,
+    pub weather_provider: WeatherProviderKind,
     pub background_image_tint_percent: u32,
+    pub archive_past_events_after_days: u32,
     pub weather_service: WeatherService,
     /// Message describing any non-fatal startup recovery (e.g. a corrupt data
     /// file that was quarantined), to surface in the error window once the UI is
@@ -123,11 +325,15 @@ pub struct TaskApp {
     offset: usize,
     press_origin: Option<PressState>,
 
-    userconfig_path: PathBuf,
+    config_files: ConfigFiles,
+    /// Pick up edits to `userconfig.toml` and the profile made while running.
+    config_watcher: ConfigWatcher,
+    profile_watcher: Option<ConfigWatcher>,
+    images_dir: PathBuf,
+    launch: LaunchOptions,
 
     /* ───────────────────────── Time & Date ───────────────────────── */
     date: DateTime<Local>,
-    next_three_weekdays: (String, String, String),
     /// Timestamp of the most recent notepad edit, used to debounce autosave.
     /// `None` once there is nothing pending to save.
     last_textbox_edit_time: Option<Instant>,
@@ -136,6 +342,18 @@ pub struct TaskApp {
     active_things: Vec<Active>,
     list_tasks: Vec<Active>,
     archive: Option<Vec<InActive>>,
+    /// Record count of the whole archive, read from the segment indexes when
+    /// the Archive window opens (so "Show more" knows when it has reached the end).
+    /// While a filter is active it is the number of matching records instead.
+    archive_total: u64,
+    archive_query: tasks::ArchiveQuery,
+    /// Date-range text fields (`dd.mm.yyyy`, empty = open-ended), parsed into
+    /// `archive_query` when applied.
+    archive_from_input: String,
+    archive_to_input: String,
+    /// Computed lazily the first time the Statistics section is opened, and
+    /// dropped whenever the query changes.
+    archive_stats: Option<tasks::ArchiveStats>,
     /// Next stable id to hand out to a newly created item. Seeded past the
     /// highest id present at startup (see `tasks::assign_missing_ids`).
     next_id: u64,
@@ -144,10 +362,49 @@ pub struct TaskApp {
 
     /* ───────────────────────── Weather ───────────────────────── */
     pub weather_service: WeatherService,
-    weather_data_cache: Vec<Vec<(String, f64, i32, bool)>>,
+    /// Per day, twelve 2-hour slots.
+    weather_data_cache: Vec<WeatherDay>,
     last_weather_version: u64,
+    /// The forecast takes the whole column and the notepad is hidden.
     three_day_weather: bool,
+    /// Precipitation bar and wind arrow under each forecast slot.
+    weather_details_row: bool,
+    /// Icon, high and low in each forecast calendar day (`calendar_weather_days`).
+    calendar_weather: bool,
+    /// Daily summaries by location (index into `locations`) and date, in
+    /// `units`, from `fix_and_cache_weather_data`.
+    calendar_weather_days: HashMap<(usize, NaiveDate), DaySummary>,
+    /// Shade forecast slots whose middle falls between sunset and sunrise.
+    night_bands: bool,
+    /// Darken everything once the sun is down at the shown location.
+    night_dimming: bool,
+    /// Mark calendar items by slot as well as by colour.
+    item_cues: bool,
+    /// Edited in Settings and sent to the weather thread, which evaluates them.
+    alert_rules: AlertRules,
+    /// The thread's latest evaluation, read with each `version`.
+    weather_alerts: Vec<Alert>,
+    /// The alerts' messages by location and the days they touch, for
+    /// flagging outdoor events.
+    weather_alert_days: HashMap<(usize, NaiveDate), String>,
+    /// The saved places, all fetched by the weather thread; never empty.
+    locations: Vec<Location>,
+    /// Index into `locations` of the one the panel and calendar show.
+    selected_location: usize,
+    /// Forecast display units; the cached slots are already converted.
+    units: Units,
+    temperature_rounding: Rounding,
+    /// Slot length and number of days in the forecast panel.
+    weather_window_hours: u32,
+    forecast_days: usize,
+    weather_provider: WeatherProviderKind,
+    /// Edited in Settings and sent to the weather thread.
+    weather_refresh: RefreshPolicy,
     weather_is_broken_flag: bool,
+    /// When the shown forecast was fetched and how far it reaches, for the
+    /// stale indicator and for noticing when cached data runs out.
+    weather_fetched_at: Option<DateTime<Local>>,
+    weather_covered_until: Option<NaiveDateTime>,
 
     /* ───────────────────────── Inputs ───────────────────────── */
     week_number_input: String,
@@ -155,6 +412,9 @@ pub struct TaskApp {
     task_importance_input: u8,
     time_importance_input: u8,
     event_name_input: String,
+    event_outdoor_input: bool,
+    /// Index into `locations` for the new event, if it has one.
+    event_location_input: Option<usize>,
 
     year_input: i32,
     month_input: i32,
@@ -178,6 +438,7 @@ pub struct TaskApp {
 
     /* ───────────────────────── Settings ───────────────────────── */
     start_in_fullscreen: bool,
+    window_size_startup: [f32; 2],
     enable_fps_counter: bool,
     calendar_weeks_to_show: usize,
 
@@ -185,6 +446,9 @@ pub struct TaskApp {
     background_options: Vec<String>,
     background_image_tint_percent: u32,
     background_tint_input: String,
+    /// See `Config::archive_past_events_after_days`; `0` disables the sweep.
+    archive_past_events_after_days: u32,
+    archive_events_after_input: String,
 
     /* ───────────────────────── Errors & Confirmations ───────────────────────── */
     /// Id of the item awaiting a complete/delete confirmation. The dialog looks
@@ -192,6 +456,19 @@ pub struct TaskApp {
     confirm_complete_task: Option<u64>,
     confirm_delete_task: Option<u64>,
     error_text: String,
+    /// Informational counterpart to the error window (e.g. the result of an
+    /// archive rebuild or export).
+    notice_flag: bool,
+    notice_text: String,
+
+    /* ───────────────────────── Backups ───────────────────────── */
+    backup_restore_flag: bool,
+    /// Listed (with item counts) when the Restore dialog opens; newest first.
+    backup_snapshots: Vec<backup::SnapshotInfo>,
+    backup_selected: Option<usize>,
+    /// Comparison of the selected snapshot with the current data, once asked for.
+    backup_diff: Option<backup::BackupDiff>,
+    confirm_restore_flag: bool,
 
     /* ───────────────────────── FPS / Monitor ───────────────────────── */
     fps_counter: FpsCounter,
@@ -204,9 +481,13 @@ pub struct TaskApp {
     map_zoom: f32,
     map_offset: Vec2,
     /// Live, editable coordinates `[lat, lon]` — the single source of truth for
-    /// the map picker. Pushed to the weather service (its own thread-local copy)
-    /// and persisted only on confirm, via `set_weather_coordinates`.
+    /// the map picker's marker. Saved as a new location (named from
+    /// `location_name_input`) only on confirm, via `add_weather_location`.
     coordinates: [f32; 2],
+    location_name_input: String,
+    /// The picker's place search box and the places matching it.
+    place_search_input: String,
+    place_search_results: Vec<&'static Place>,
     map_texture: Option<TextureHandle>,
 
     /* ───────────────────────── Color Schemes ───────────────────────── */
@@ -217,10 +498,31 @@ pub struct TaskApp {
 
     rename_colorscheme_flag: bool,
     colorscheme_rename_input: String,
+    /// Path typed into the manager's import field.
+    colorscheme_import_path: String,
     user_wants_to_delete_colorscheme_flag: bool,
     edit_colorscheme_flag: bool,
     colorscheme_being_edited: Option<ColorScheme>,
     dragged_color_index: Option<usize>,
+    /// The calendar area as fractions of the window, from the last frame.
+    calendar_region: [f32; 4],
+    /// The calendar's backdrop and the background it was sampled from.
+    backdrop_samples: Option<(Backdrop, String, Vec<[u8; 3]>)>,
+    palette_options: PaletteOptions,
+    /// Colour vision the calendar is shown with while a scheme is edited.
+    simulated_vision: Option<ColorVision>,
+    /// One scheme per algorithm for the current background while the preview is open.
+    palette_previews: Option<Vec<(PaletteAlgorithm, ColorScheme)>>,
+
+    /* ───────────────────────── Themes ───────────────────────── */
+    theme_manager_flag: bool,
+    themes: HashMap<u32, Theme>,
+    selected_theme_id: u32,
+    /// The theme installed in egui, kept to tell when it has to be installed again.
+    theme: Theme,
+    /// Changes to the selected theme, shown live until saved or cancelled.
+    theme_being_edited: Option<Theme>,
+    user_wants_to_delete_theme_flag: bool,
 
     /* ───────────────────────── Calendar ───────────────────────── */
     row_contains_month_switch: Vec<Option<(String, String)>>,
@@ -235,6 +537,7 @@ impl TaskApp {
 
         let active_colorscheme =
             resolve_colorscheme(&config.colorschemes, config.selected_colorscheme_id);
+        let theme = config.themes.get(&config.selected_theme_id).cloned().unwrap_or_else(Theme::dark);
 
         let selected_background_index = config
             .background_options
@@ -247,6 +550,9 @@ impl TaskApp {
         let mut active_items = config.active_items;
         let next_id = tasks::assign_missing_ids(&mut active_items);
 
+        let selected_location = config.locations.iter().position(|location| location.name == config.weather_location).unwrap_or(0);
+        let coordinates = config.locations[selected_location].coordinates;
+
         Self {
             /* Animation */
             row_anim: Vec::new(),
@@ -265,11 +571,14 @@ impl TaskApp {
             expanded_day: None,
             offset: 0,
             press_origin: None,
-            userconfig_path: PathBuf::from("taskdeck_data").join(PathBuf::from("userconfig.toml")),
+            config_watcher: ConfigWatcher::new(config.config_files.base.clone()),
+            profile_watcher: config.config_files.profile.clone().map(ConfigWatcher::new),
+            config_files: config.config_files,
+            images_dir: config.images_dir,
+            launch: config.launch,
 
             /* Time */
             date: now,
-            next_three_weekdays: next_three_weekdays(now),
             last_textbox_edit_time: None,
 
             /* Tasks */
@@ -280,6 +589,16 @@ impl TaskApp {
                 .collect(),
             active_things: active_items,
             archive: None,
+            archive_total: 0,
+            archive_query: tasks::ArchiveQuery::default(),
+            archive_from_input: String::new(),
+            archive_to_input: String::new(),
+            archive_stats: None,
+            backup_restore_flag: false,
+            backup_snapshots: Vec::new(),
+            backup_selected: None,
+            backup_diff: None,
+            confirm_restore_flag: false,
             next_id,
             calendar_elements: Vec::new(),
 
@@ -288,7 +607,26 @@ impl TaskApp {
             weather_data_cache: Vec::new(),
             last_weather_version: 0,
             three_day_weather: config.three_day_weather,
+            weather_details_row: config.weather_details_row,
+            calendar_weather: config.calendar_weather,
+            calendar_weather_days: HashMap::new(),
+            night_bands: config.night_bands,
+            night_dimming: config.night_dimming,
+            item_cues: config.item_cues,
+            alert_rules: config.alert_rules,
+            weather_alerts: Vec::new(),
+            weather_alert_days: HashMap::new(),
+            locations: config.locations,
+            selected_location,
+            units: config.units,
+            temperature_rounding: config.temperature_rounding,
+            weather_window_hours: config.weather_window_hours,
+            forecast_days: config.forecast_days,
+            weather_provider: config.weather_provider,
+            weather_refresh: config.weather_refresh,
             weather_is_broken_flag: false,
+            weather_fetched_at: None,
+            weather_covered_until: None,
 
             /* Inputs */
             week_number_input: config.calendar_weeks_to_show.to_string(),
@@ -296,6 +634,8 @@ impl TaskApp {
             task_importance_input: 2,
             time_importance_input: 1,
             event_name_input: String::new(),
+            event_outdoor_input: false,
+            event_location_input: None,
 
             year_input: now.year(),
             month_input: now.month() as i32,
@@ -318,6 +658,7 @@ impl TaskApp {
 
             /* Settings */
             start_in_fullscreen: config.start_in_fullscreen,
+            window_size_startup: config.window_size_startup,
             enable_fps_counter: config.enable_fps_counter,
             calendar_weeks_to_show: config.calendar_weeks_to_show,
 
@@ -325,11 +666,15 @@ impl TaskApp {
             background_options: config.background_options,
             background_image_tint_percent: config.background_image_tint_percent,
             background_tint_input: config.background_image_tint_percent.to_string(),
+            archive_past_events_after_days: config.archive_past_events_after_days,
+            archive_events_after_input: config.archive_past_events_after_days.to_string(),
 
             /* Errors */
             confirm_complete_task: None,
             confirm_delete_task: None,
             error_text: config.startup_error.unwrap_or_default(),
+            notice_flag: false,
+            notice_text: String::new(),
 
             /* FPS / Monitor */
             fps_counter: FpsCounter::new(),
@@ -341,7 +686,10 @@ impl TaskApp {
             coordinates_map_flag: false,
             map_zoom: 1.0,
             map_offset: Vec2::ZERO,
-            coordinates: config.coordinates,
+            coordinates,
+            location_name_input: String::new(),
+            place_search_input: String::new(),
+            place_search_results: Vec::new(),
             map_texture: None,
 
             /* Colors */
@@ -352,10 +700,24 @@ impl TaskApp {
 
             rename_colorscheme_flag: false,
             colorscheme_rename_input: String::new(),
+            colorscheme_import_path: String::new(),
             user_wants_to_delete_colorscheme_flag: false,
             edit_colorscheme_flag: false,
             colorscheme_being_edited: None,
             dragged_color_index: None,
+            calendar_region: [0.25, 0.0, 0.75, 1.0],
+            backdrop_samples: None,
+            palette_options: PaletteOptions::default(),
+            simulated_vision: None,
+            palette_previews: None,
+
+            /* Themes */
+            theme_manager_flag: false,
+            themes: config.themes,
+            selected_theme_id: config.selected_theme_id,
+            theme,
+            theme_being_edited: None,
+            user_wants_to_delete_theme_flag: false,
 
             /* Calendar */
             row_contains_month_switch: Vec::new(),
@@ -372,8 +734,7 @@ impl TaskApp {
     }
 
     pub fn init_with_context(&mut self, ctx: &Context) {
-        load_fonts(ctx);
-        set_styles(ctx);
+        theme::install(ctx, &self.theme);
 
         if self.start_in_fullscreen {
             ctx.send_viewport_cmd(ViewportCommand::Fullscreen(true));
@@ -406,7 +767,7 @@ impl TaskApp {
                 for task in self.list_tasks.iter() {
                     egui::Frame::new()
                         .fill(Color32::from_black_alpha(60))
-                        .stroke(egui::Stroke::new(1.5, Color32::from_white_alpha(55)))
+                        .stroke(egui::Stroke::new(1.5, self.theme.stroke_color()))
                         .corner_radius(egui::CornerRadius::same(14))
                         .inner_margin(Margin::symmetric(12, 12))
                         .show(ui, |ui| {
@@ -414,11 +775,11 @@ impl TaskApp {
                             ui.set_min_size(egui::Vec2 { x: 258.0, y: 40.0 });
                             ui.set_max_size(egui::Vec2 { x: 245.0, y: 40.0 });
                             ui.horizontal(|ui| {
-                                let task_font = FontId::new(17.0, FontFamily::Name("bungee".into()));
+                                let task_font = FontId::new(17.0, FontFamily::Name(theme::TASKS.into()));
                                 ui.set_width(245.0);
                                 ui.set_min_size(egui::Vec2 { x: 245.0, y: 40.0 });
                                 ui.set_max_size(egui::Vec2 { x: 245.0, y: 40.0 });
-                                ui.add(Label::new(RichText::new(&task.name).color(Color32::from_white_alpha(120)).font(task_font)).wrap().selectable(false));
+                                ui.add(Label::new(RichText::new(&task.name).color(self.theme.weak_text_color()).font(task_font)).wrap().selectable(false));
                                 
                                 if ui.ui_contains_pointer() {
                                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -444,35 +805,24 @@ impl TaskApp {
         });
     }
 
-    fn display_stuff(&self, thing: &Vec<(String, f64, i32, bool)>, ui: &mut Ui, grid_id: String, upper_day: bool) {
+    fn display_stuff(&self, slots: &[WeatherSlot], ui: &mut Ui, grid_id: String) {
+        let now = self.date.naive_local();
         egui::Grid::new(grid_id)
             .spacing(Vec2::new(10.0, 10.0))
             .min_col_width(80.0)
             .max_col_width(80.0)
             .show(ui, |ui| {
-                let nth_cell_to_highlight = match self.date.hour() {
-                    0..2 => 0,
-                    2..4 => 1,
-                    4..6 => 2,
-                    6..8 => 3,
-                    8..10 => 4,
-                    10..12 => 5,
-                    12..14 => 6,
-                    14..16 => 7,
-                    16..18 => 8,
-                    18..20 => 9,
-                    20..22 => 10,
-                    _ => 11,
-                };
-                for (i, (time, temp, wmo_code, is_day)) in thing.iter().enumerate() {
-                    egui::Frame::default()
+                for (i, slot) in slots.iter().enumerate() {
+                    let is_now = slot.start <= now && now < slot.end;
+                    let frame = egui::Frame::default()
                         .stroke(
-                            if i == nth_cell_to_highlight && upper_day {
-                                Stroke::new(0.6, Color32::WHITE)
+                            if is_now {
+                                Stroke::new(0.6, self.theme.stroke_with_alpha(255))
                             } else {
-                                Stroke::new(0.5, Color32::from_white_alpha(150))
+                                Stroke::new(0.5, self.theme.stroke_with_alpha(150))
                             }                            
                         )
+                        .fill(if self.night_bands && slot.night { Color32::from_black_alpha(70) } else { Color32::TRANSPARENT })
                         .corner_radius(CornerRadius::same(15))
                         .inner_margin(egui::Margin {
                             left: 10,
@@ -482,39 +832,53 @@ impl TaskApp {
                         })
                         .show(ui, |ui| {
                             ui.with_layout(egui::Layout::bottom_up(Align::Center), |ui| {
-                                let weather_icon_ref = weather::icon_for_wmo(*wmo_code, *is_day);
-                                ui.add(egui::Image::new(weather_icon_ref.clone())
-                                    .fit_to_exact_size(Vec2::new(48.0, 48.0)));
+                                if self.weather_details_row {
+                                    paint_weather_details(ui, slot, self.units);
+                                }
+
+                                // Slots past the end of an old forecast stay empty.
+                                if slot.covered {
+                                    let weather_icon_ref = weather::icon_for_symbol(slot.symbol, slot.phase);
+                                    ui.add(egui::Image::new(weather_icon_ref.clone())
+                                        .fit_to_exact_size(Vec2::new(48.0, 48.0)));
+                                } else {
+                                    ui.add_space(48.0);
+                                }
 
                                 ui.add_space(-15.0);
 
                                 ui.horizontal(|ui| {
-                                    ui.add_space(37.0);
+                                    // One decimal is wider; keep it roughly centred.
+                                    ui.add_space(if self.temperature_rounding == Rounding::Tenths { 30.0 } else { 37.0 });
 
-                                    ui.label(RichText::new(format!("{temp:.0}")).color(
-                                        if i == nth_cell_to_highlight && upper_day {
-                                            Color32::WHITE
+                                    let temp_text = if slot.covered { self.temperature_rounding.format(slot.temp) } else { "–".to_string() };
+                                    ui.label(RichText::new(temp_text).color(
+                                        if is_now {
+                                            ui.visuals().strong_text_color()
                                         } else {
-                                            Color32::from_white_alpha(120)
+                                            self.theme.weak_text_color()
                                         }   ));
                                 });
 
                                 ui.add_space(-5.0);
 
-                                let time_text = RichText::new(time)
+                                let time_text = RichText::new(&slot.time)
                                     .color(
-                                        if i == nth_cell_to_highlight && upper_day {
-                                            Color32::WHITE
+                                        if is_now {
+                                            ui.visuals().strong_text_color()
                                         } else {
-                                            Color32::from_white_alpha(120)
+                                            self.theme.weak_text_color()
                                         }   
                                 )
                                     .size(14.0)
-                                    .font(FontId { size:13.5, family: FontFamily::Name("space".into()) });
+                                    .font(FontId { size:13.5, family: FontFamily::Name(theme::DETAIL.into()) });
 
                                 ui.label(time_text);
                             });
                         });
+                    if slot.covered && let Some(text) = weather_details_text(&slot.details, self.units, self.temperature_rounding) {
+                        frame.response.on_hover_text(text);
+                    }
                     if (i + 1) % 4 == 0 {
                         ui.end_row();
                     }
@@ -523,52 +887,118 @@ impl TaskApp {
     }
 
     fn show_weather_forecast(&mut self, ui: &mut Ui) {
+        // The notepad's scroll area plus the space above it.
+        const NOTEPAD_BLOCK_HEIGHT: f32 = 390.0 + 15.0 + 10.0;
+
         ui.vertical(|ui| {
-            if self.weather_is_broken_flag {
-                // No usable forecast yet — either the first fetch hasn't completed
-                // or the data arrived in an unexpected shape. Show a small notice in
-                // place of the grids; the notepad below stays reachable regardless.
-                ui.add_space(75.0);
+            // With several locations, the one the panel shows.
+            if self.locations.len() > 1 {
+                let mut selected = self.selected_location;
                 ui.horizontal(|ui| {
-                    ui.add_space(120.0);
-                    ui.label(RichText::new("WEATHER IS BROKEN").size(14.0).color(Color32::from_white_alpha(165)));
+                    ui.add_space(7.0);
+                    for (index, location) in self.locations.iter().enumerate() {
+                        ui.selectable_value(&mut selected, index, &location.name);
+                    }
                 });
-            } else {
+                if selected != self.selected_location {
+                    self.select_weather_location(selected);
+                }
+                ui.add_space(5.0);
+            }
+
+            // Alerts from the weather thread, until each one is over.
+            let now = self.date.naive_local();
+            let shown = self.shown_coordinates();
+            let alert_lines: Vec<String> = self.weather_alerts
+                .iter()
+                .filter(|alert| alert.end > now && weather::same_place(alert.coordinates, shown))
+                .map(|alert| alert.message(self.units, self.temperature_rounding))
+                .collect();
+            if !alert_lines.is_empty() {
                 ui.horizontal(|ui| {
-                    ui.add_space(147.0);
-                    ui.label(RichText::new(&self.next_three_weekdays.0).size(14.0).color(Color32::from_white_alpha(165)));
+                    ui.add_space(7.0);
+                    egui::Frame::new()
+                        .fill(ALERT_COLOR.gamma_multiply(0.25))
+                        .stroke(Stroke::new(1.0, ALERT_COLOR))
+                        .corner_radius(CornerRadius::same(10))
+                        .inner_margin(Margin::symmetric(10, 6))
+                        .show(ui, |ui| {
+                            ui.set_width(320.0);
+                            for line in &alert_lines {
+                                ui.label(RichText::new(line).size(12.0).color(self.theme.heading_text_color()));
+                            }
+                        });
                 });
-                ui.add_space(75.0);
-
-                let day_1 = &self.weather_data_cache[0];
-                self.display_stuff(day_1, ui, "firstweathergrid".to_string(), true);
-
                 ui.add_space(5.0);
+            }
 
+            if self.weather_is_broken_flag {
+                // No usable forecast yet — either the first fetch hasn't completed
+                // or the data arrived in an unexpected shape. Show a small notice in
+                // place of the grids; the notepad below stays reachable regardless.
+                ui.add_space(75.0);
                 ui.horizontal(|ui| {
-                    ui.add_space(150.0);
-                    ui.label(RichText::new(&self.next_three_weekdays.1).size(14.0).color(Color32::from_white_alpha(165)));
+                    ui.add_space(120.0);
+                    ui.label(RichText::new("WEATHER IS BROKEN").size(14.0).color(self.theme.heading_text_color()));
+                    ui.add_space(10.0);
+                    self.show_weather_status(ui);
                 });
-                ui.add_space(75.0);
-
-                let day_2 = &self.weather_data_cache[1];
-                self.display_stuff(day_2, ui, "secondweathergrid".to_string(), false);
-
-                if self.three_day_weather {
-                    ui.add_space(5.0);
-
+                if let Some(age) = self.weather_age_text() {
                     ui.horizontal(|ui| {
-                        ui.add_space(150.0);
-                        ui.label(RichText::new(&self.next_three_weekdays.2).size(14.0).color(Color32::from_white_alpha(165)));
+                        ui.add_space(120.0);
+                        ui.label(RichText::new(age).size(11.0).color(self.theme.weak_text_color()));
                     });
-                    ui.add_space(75.0);
-
-                    let day_3 = &self.weather_data_cache[2];
-                    self.display_stuff(day_3, ui, "thirdweathergrid".to_string(), false);
                 }
+            } else {
+                // More days or shorter slots than fit scroll; with the notepad
+                // shown the forecast keeps to the space above it.
+                let max_height = if self.three_day_weather {
+                    ui.available_height()
+                } else {
+                    (ui.available_height() - NOTEPAD_BLOCK_HEIGHT).max(0.0)
+                };
+                egui::ScrollArea::vertical()
+                    .id_salt("weather_days")
+                    .max_height(max_height)
+                    .show(ui, |ui| {
+                        for (index, day) in self.weather_data_cache.iter().enumerate() {
+                            if index > 0 {
+                                ui.add_space(5.0);
+                            }
+                            ui.horizontal(|ui| {
+                                ui.add_space(if index == 0 { 147.0 } else { 150.0 });
+                                ui.label(RichText::new(&day.label).size(14.0).color(self.theme.heading_text_color()));
+                                if index == 0 {
+                                    ui.add_space(10.0);
+                                    self.show_weather_status(ui);
+                                }
+                                // Only once fetches have been failing for a while.
+                                if index == 0
+                                    && let Some(fetched_at) = self.weather_fetched_at
+                                    && self.date - fetched_at > Duration::minutes(weather::STALE_AFTER_MINUTES)
+                                    && let Some(age) = self.weather_age_text()
+                                {
+                                    ui.add_space(20.0);
+                                    ui.label(RichText::new(age).size(11.0).color(self.theme.weak_text_color()));
+                                }
+                            });
+                            let sun_line = ui.horizontal(|ui| {
+                                ui.add_space(150.0);
+                                ui.label(RichText::new(sun_text(&day.sun)).size(11.0).color(self.theme.weak_text_color()))
+                            });
+                            if let Some(text) = golden_hour_text(&day.sun) {
+                                sun_line.inner.on_hover_text(text);
+                            }
+                            // The sun line takes part of the gap above the grid.
+                            ui.add_space(75.0 - sun_line.response.rect.height() - ui.spacing().item_spacing.y);
+
+                            self.display_stuff(&day.slots, ui, format!("weathergrid{index}"));
+                        }
+                    });
             }
 
-            // The notepad occupies the right panel whenever 3-day weather is off.
+            // The notepad occupies the right panel unless the forecast has the whole column
+            // (`three_day_weather`, named before the horizon was configurable).
             // It is deliberately decoupled from `weather_is_broken_flag` so a failed
             // or still-pending weather fetch can never hide the user's notes
             // (CODE_REVIEW A6).
@@ -825,10 +1255,10 @@ impl TaskApp {
                                 let fill = (minimum_fill - (-normal_fill + minimum_fill) * color_factor) as u8;
 
                                 let fill_color = Color32::from_black_alpha(fill);
-                                let stroke_color = Color32::from_white_alpha(fill - 5);
+                                let stroke_color = self.theme.stroke_with_alpha(fill - 5);
 
-                                let hovered_fill_color = Color32::from_white_alpha(fill - 5);
-                                let hovered_stroke_color = Color32::from_white_alpha(fill + 40);
+                                let hovered_fill_color = self.theme.stroke_with_alpha(fill - 5);
+                                let hovered_stroke_color = self.theme.stroke_with_alpha(fill + 40);
 
                                 if let Some(i) = self.hovered_calendar_cell && i == idx {
                                     row_ui.painter().rect_filled(rect, frame_corner, hovered_fill_color);
@@ -862,8 +1292,17 @@ impl TaskApp {
                                     let preview = &cell.preview;
                                     let is_strong = cell.is_today;
                                     let day_label = &cell.label;
+                                    let weather = self.calendar_weather.then(|| self.calendar_weather_days.get(&(self.selected_location, cell.date))).flatten();
+                                    let alert = cell.items
+                                        .iter()
+                                        .filter(|item| item.outdoor)
+                                        .find_map(|item| self.item_location(item).and_then(|index| self.weather_alert_days.get(&(index, cell.date))));
+                                    let weather_width = if weather.is_some() { calendarwidgets::DayWeather::WIDTH + 4.0 } else { 0.0 };
+                                    let alert_width = if alert.is_some() { calendarwidgets::AlertMark::SIZE + 4.0 } else { 0.0 };
+                                    let trailing_space = weather_width + alert_width;
                                     ui.vertical(|ui| {
                                         let num = cell.items.len();
+                                        let cues = self.item_cues;
                                         if num == 0 {
                                             ui.add(calendarwidgets::DayNumber::new(day_label, is_strong));
                                             ui.with_layout(Layout::bottom_up(Align::RIGHT), |ui| {
@@ -871,34 +1310,64 @@ impl TaskApp {
                                             });
                                         } else if num == 1 {
                                             let first = &preview[0];
-                                            ui.add(calendarwidgets::DayHeader::new(day_label, &first.name, is_strong, &first.time, self.active_colorscheme[first.color_id]));
+                                            ui.add(calendarwidgets::DayHeader::new(day_label, &first.name, is_strong, &first.time, self.active_colorscheme[first.color_id]).cue(cues.then_some(first.color_id)).trailing_space(trailing_space));
                                             ui.with_layout(Layout::bottom_up(Align::RIGHT), |ui| {
                                                 ui.add(calendarwidgets::RotatedNumberOnly::new(day_label, is_strong));
                                             });
                                         } else if num == 2 {
                                             let first = &preview[0];
-                                            ui.add(calendarwidgets::DayHeader::new(day_label, &first.name, is_strong, &first.time, self.active_colorscheme[first.color_id]));
+                                            ui.add(calendarwidgets::DayHeader::new(day_label, &first.name, is_strong, &first.time, self.active_colorscheme[first.color_id]).cue(cues.then_some(first.color_id)).trailing_space(trailing_space));
                                             let second = &preview[1];
-                                            ui.add(calendarwidgets::MiddleHeader::new(&second.name, Some(&second.time), self.active_colorscheme[second.color_id]));
+                                            ui.add(calendarwidgets::MiddleHeader::new(&second.name, Some(&second.time), self.active_colorscheme[second.color_id]).cue(cues.then_some(second.color_id)));
                                             ui.with_layout(Layout::bottom_up(Align::RIGHT), |ui| {
                                                 ui.add(calendarwidgets::RotatedNumberOnly::new(day_label, is_strong));
                                             });
                                         } else if num == 3 {
                                             let first = &preview[0];
-                                            ui.add(calendarwidgets::DayHeader::new(day_label, &first.name, is_strong, &first.time, self.active_colorscheme[first.color_id]));
+                                            ui.add(calendarwidgets::DayHeader::new(day_label, &first.name, is_strong, &first.time, self.active_colorscheme[first.color_id]).cue(cues.then_some(first.color_id)).trailing_space(trailing_space));
                                             let second = &preview[1];
-                                            ui.add(calendarwidgets::MiddleHeader::new(&second.name, None, self.active_colorscheme[second.color_id]));
+                                            ui.add(calendarwidgets::MiddleHeader::new(&second.name, None, self.active_colorscheme[second.color_id]).cue(cues.then_some(second.color_id)));
                                             let third = &preview[2];
-                                            ui.add(calendarwidgets::BottomHeaderRotated::new(day_label, &third.name, is_strong, &third.time, Some(&second.time), self.active_colorscheme[third.color_id]));
+                                            ui.add(calendarwidgets::BottomHeaderRotated::new(day_label, &third.name, is_strong, &third.time, Some(&second.time), self.active_colorscheme[third.color_id]).cue(cues.then_some(third.color_id)));
                                         } else {
                                             let first = &preview[0];
-                                            ui.add(calendarwidgets::DayHeader::new(day_label, &first.name, is_strong, &first.time, self.active_colorscheme[first.color_id]));
+                                            ui.add(calendarwidgets::DayHeader::new(day_label, &first.name, is_strong, &first.time, self.active_colorscheme[first.color_id]).cue(cues.then_some(first.color_id)).trailing_space(trailing_space));
                                             let second = &preview[1];
-                                            ui.add(calendarwidgets::MiddleHeader::new(&second.name, None, self.active_colorscheme[second.color_id]));
+                                            ui.add(calendarwidgets::MiddleHeader::new(&second.name, None, self.active_colorscheme[second.color_id]).cue(cues.then_some(second.color_id)));
                                             let third = &preview[2];
-                                            ui.add(calendarwidgets::ButtonHeaderRotated::new(day_label, &third.name, is_strong, &third.time, Some(&second.time), self.active_colorscheme[third.color_id]));
+                                            ui.add(calendarwidgets::ButtonHeaderRotated::new(day_label, &third.name, is_strong, &third.time, Some(&second.time), self.active_colorscheme[third.color_id]).cue(cues.then_some(third.color_id)));
                                         }
                                     });
+
+                                    // Right of the day number, over the header's first line
+                                    if let Some(day) = weather {
+                                        // Whole degrees only; tenths don't fit the badge.
+                                        let rounding = if self.temperature_rounding == Rounding::Tenths { Rounding::Nearest } else { self.temperature_rounding };
+                                        let high = rounding.format(day.high);
+                                        let low = rounding.format(day.low);
+                                        // A day with at least a millimetre of rain is worth planning around.
+                                        let wet = day.precipitation.is_some_and(|amount| self.units.precipitation.to_mm(amount) >= 1.0);
+                                        let badge_rect = Rect::from_min_size(
+                                            pos2(inner_rect.right() - calendarwidgets::DayWeather::WIDTH, inner_rect.top() + 11.0),
+                                            vec2(calendarwidgets::DayWeather::WIDTH, calendarwidgets::DayWeather::HEIGHT),
+                                        );
+                                        let response = ui.scope_builder(
+                                            egui::UiBuilder::new().max_rect(badge_rect).layout(Layout::right_to_left(Align::Min)),
+                                            |ui| ui.add(calendarwidgets::DayWeather::new(weather::icon_for_symbol(day.symbol, DayPhase::Day), &high, &low, wet)),
+                                        ).inner;
+                                        if let Some(amount) = day.precipitation {
+                                            response.on_hover_text(format!("Precipitation: {}", self.units.precipitation.format(amount)));
+                                        }
+                                    }
+
+                                    // An outdoor event on a day with a weather alert
+                                    if let Some(text) = alert {
+                                        let mark_rect = Rect::from_min_size(
+                                            pos2(inner_rect.right() - trailing_space, inner_rect.top() + 12.0),
+                                            Vec2::splat(calendarwidgets::AlertMark::SIZE),
+                                        );
+                                        ui.put(mark_rect, calendarwidgets::AlertMark::new(ALERT_COLOR)).on_hover_text(text);
+                                    }
                                 });
 
                                 if !self.expand_calendar_day_flag {
@@ -916,10 +1385,10 @@ impl TaskApp {
                                 row_ui.vertical(|ui| {
                                     let font_id = FontId {
                                         size: 12.0,
-                                        family: FontFamily::Name("space".into()),
+                                        family: FontFamily::Name(theme::DETAIL.into()),
                                     };
 
-                                    let font_color = Color32::from_rgba_unmultiplied(211, 215, 211, 210);
+                                    let font_color = self.theme.weak_text_color();
 
                                     ui.label(RichText::new(this).font(font_id.clone()).color(font_color));
                                     ui.label(RichText::new("↓").font(font_id.clone()).color(font_color));
@@ -1000,7 +1469,9 @@ impl TaskApp {
         });
     }
 
-    fn add_active_thing(&mut self, name: String, deadline: Option<DateTime<Local>>, importance: Option<u8>, is_event: bool, time_importance: Option<u8>) {
+    fn add_active_thing(&mut self, name: String, deadline: Option<DateTime<Local>>, importance: Option<u8>, time_importance: Option<u8>, event: Option<NewEvent>) {
+        let is_event = event.is_some();
+        let (outdoor, location) = event.map_or((false, None), |event| (event.outdoor, event.location));
         let id = self.next_id;
         self.next_id += 1;
         self.active_things.push(Active {
@@ -1011,22 +1482,77 @@ impl TaskApp {
             time_importance,
             is_event,
             created: chrono::Local::now(),
+            outdoor,
+            location,
         });
         self.summarize_calendar();
-        if let Err(text) = tasks::oversafe_activesave(&self.active_things, &self.exe_file_path) {
-            self.show_error(format!("Saving error:\n{}", text.to_string()));
-        }
+        self.save_active_things();
     }
 
     fn delete_active_thing(&mut self, id: u64) {
         self.user_wants_to_delete_task_flag = false;
-        self.active_things.retain(|task| task.id != id);
         self.confirm_delete_task = None;
+        self.archive_active_things(&[id], ArchiveReason::Deleted);
+    }
+
+    /// Move events that ended more than `archive_past_events_after_days` ago to
+    /// the archive. Runs at startup, when the day changes, and when the setting
+    /// is edited.
+    pub fn archive_expired_events(&mut self) {
+        if self.archive_past_events_after_days == 0 {
+            return;
+        }
+        let after = Duration::days(self.archive_past_events_after_days as i64);
+        let expired = tasks::expired_event_ids(&self.active_things, Local::now(), after);
+        if !expired.is_empty() {
+            self.archive_active_things(&expired, ArchiveReason::ExpiredEvent);
+        }
+    }
+
+    /// Move items to the archive (recording why) and drop them from the active
+    /// list. An item whose archive write fails is reported and stays active, so
+    /// nothing is lost; the next sweep or click tries it again.
+    fn archive_active_things(&mut self, ids: &[u64], reason: ArchiveReason) {
+        let mut failure = None;
+        let mut archived = Vec::new();
+        for thing in self.active_things.iter().filter(|x| ids.contains(&x.id)) {
+            let found_inactive: InActive = thing.clone().to_inactive(reason);
+
+            if !self.launch.read_only && let Err(text) = tasks::save_inactive(&found_inactive, &self.exe_file_path) {
+                failure = Some(text);
+            } else {
+                archived.push(thing.id);
+            }
+        }
+        if let Some(text) = failure {
+            self.show_error(format!("Error archiving:\n{text}"));
+        }
+        if archived.is_empty() {
+            return;
+        }
+
+        self.active_things.retain(|task| !archived.contains(&task.id));
         self.summarize_calendar();
+        self.save_active_things();
+    }
 
+    /// Writes the active list; in read-only mode changes stay in memory.
+    fn save_active_things(&mut self) {
+        if self.launch.read_only {
+            return;
+        }
         if let Err(text) = tasks::oversafe_activesave(&self.active_things, &self.exe_file_path) {
             self.show_error(format!("Saving error:\n{}", text.to_string()));
-        };
+        }
+    }
+
+    /// Says that `what` would write to the data directory, which
+    /// `--read-only` rules out. True if so.
+    fn refused_in_read_only(&mut self, what: &str) -> bool {
+        if self.launch.read_only {
+            self.show_error(format!("{what} is not available: TaskDeck was started with --read-only."));
+        }
+        self.launch.read_only
     }
 
     pub fn summarize_calendar(&mut self) {
@@ -1147,6 +1673,8 @@ impl TaskApp {
                             .map(|d| d.format("%H:%M").to_string())
                             .unwrap_or_default(),
                         is_event: a.is_event,
+                        outdoor: a.is_event && a.outdoor,
+                        location: a.location.clone().filter(|_| a.is_event),
                     })
                     .collect();
 
@@ -1173,6 +1701,11 @@ impl TaskApp {
         self.error_text = errortext;
     }
 
+    fn show_notice(&mut self, text: String) {
+        self.notice_flag = true;
+        self.notice_text = text;
+    }
+
     /// True when any modal/overlay window is open. This is the single source of
     /// truth for "is the calendar covered" — used both to suppress the calendar
     /// tap/drag machine and to clear the hovered cell. It replaces two
@@ -1186,6 +1719,8 @@ impl TaskApp {
             || self.display_archive_flag
             || self.expand_calendar_day_flag
             || self.error_flag
+            || self.notice_flag
+            || self.backup_restore_flag
             || self.user_wants_to_complete_task_flag
             || self.user_wants_to_delete_task_flag
             || self.coordinates_map_flag
@@ -1193,17 +1728,12 @@ impl TaskApp {
             || self.edit_colorscheme_flag
             || self.rename_colorscheme_flag
             || self.user_wants_to_delete_colorscheme_flag
+            || self.theme_manager_flag
     }
 
     fn complete_active_thing(&mut self, id: u64) {
-        if let Some(thing) = self.active_things.iter().find(|x| x.id == id) {
-            let found_inactive: InActive = thing.clone().to_inactive();
-
-            if let Err(text) = tasks::save_inactive(&found_inactive, &self.exe_file_path) {
-                self.show_error(format!("Error archiving:\n{}", text.to_string()));
-            };
-
-            self.delete_active_thing(id);
+        if self.active_things.iter().any(|x| x.id == id) {
+            self.archive_active_things(&[id], ArchiveReason::Completed);
 
             self.confirm_complete_task = None;
             self.user_wants_to_complete_task_flag = false;
@@ -1215,14 +1745,24 @@ impl TaskApp {
 
         if !self.display_archive_flag {
             self.archive = None;
+            self.archive_stats = None;
             self.offset = 0;
         } else {
-            self.load_more_archives();
+            self.reload_archive();
         }
     }
 
     fn load_more_archives(&mut self) {
-        let new_items = tasks::read_lines_range(self.offset, 15, &self.exe_file_path).unwrap_or_else(|_| Vec::new());
+        // A filtered/sorted view already holds every matching record; "Show
+        // more" only reveals the next rows of it.
+        if !self.archive_query.is_default() {
+            self.offset += 15;
+            return;
+        }
+
+        // The offset counts index entries, not displayed rows, so a record that
+        // fails to parse can't shift later pages (see `tasks::rebuild_archive`).
+        let new_items = tasks::read_archive_page(self.offset, 15, &self.exe_file_path).unwrap_or_else(|_| Vec::new());
         self.offset += 15;
 
         if let Some(archive) = self.archive.as_mut() {
@@ -1232,11 +1772,118 @@ impl TaskApp {
         }
     }
 
+    /// Re-read the archive for the current query. The default view pages through
+    /// the index; any filter or non-default sort reads the matching date range.
+    fn reload_archive(&mut self) {
+        self.archive = None;
+        self.archive_stats = None;
+        self.offset = 0;
+
+        if self.archive_query.is_default() {
+            self.archive_total = tasks::archive_count(&self.exe_file_path).unwrap_or(0);
+            self.load_more_archives();
+            return;
+        }
+
+        match tasks::query_archive(&self.archive_query, &self.exe_file_path) {
+            Ok(rows) => {
+                self.archive_total = rows.len() as u64;
+                self.archive = Some(rows);
+                self.offset = 15;
+            }
+            Err(e) => {
+                self.archive_total = 0;
+                self.show_error(format!("Could not read the archive:\n{}", e));
+            }
+        }
+    }
+
+    /// Parse the date-range fields into the query and reload. The "to" date is
+    /// inclusive for the user, so the bound is the following midnight.
+    fn apply_archive_dates(&mut self) {
+        let parse = |text: &str| -> Result<Option<NaiveDate>, String> {
+            if text.trim().is_empty() {
+                return Ok(None);
+            }
+            utilities::parse_date_input(text).map(Some).ok_or(format!("\"{}\" is not a dd.mm.yyyy date", text.trim()))
+        };
+        let midnight = |date: NaiveDate| date.and_hms_opt(0, 0, 0).and_then(|naive| Local.from_local_datetime(&naive).earliest());
+
+        match (parse(&self.archive_from_input), parse(&self.archive_to_input)) {
+            (Ok(from), Ok(to)) => {
+                self.archive_query.from = from.and_then(midnight);
+                self.archive_query.to = to.and_then(|date| date.succ_opt()).and_then(midnight);
+                self.reload_archive();
+            }
+            (Err(e), _) | (_, Err(e)) => self.show_error(format!("Archive date range:\n{}", e)),
+        }
+    }
+
+    /// Clicking a column header sorts by it; clicking the active one flips the order.
+    fn sort_archive_by(&mut self, column: ArchiveSort) {
+        if self.archive_query.sort == column {
+            self.archive_query.descending = !self.archive_query.descending;
+        } else {
+            self.archive_query.sort = column;
+            self.archive_query.descending = matches!(column, ArchiveSort::Created | ArchiveSort::Inactivated | ArchiveSort::Importance);
+        }
+        self.reload_archive();
+    }
+
+    /// Statistics for the rows matching the current query, computed on demand.
+    fn compute_archive_stats(&mut self) {
+        let stats = if self.archive_query.is_default() {
+            tasks::query_archive(&self.archive_query, &self.exe_file_path).map(|rows| tasks::archive_stats(&rows))
+        } else {
+            Ok(tasks::archive_stats(self.archive.as_deref().unwrap_or_default()))
+        };
+        match stats {
+            Ok(stats) => self.archive_stats = Some(stats),
+            Err(e) => {
+                // Cache an empty result so a broken archive isn't re-read every frame.
+                self.archive_stats = Some(tasks::archive_stats(&[]));
+                self.show_error(format!("Could not read the archive:\n{}", e));
+            }
+        }
+    }
+
+    fn rebuild_archive(&mut self) {
+        if self.refused_in_read_only("Rebuilding the archive") {
+            return;
+        }
+        match tasks::rebuild_archive(&self.exe_file_path) {
+            Ok(report) => {
+                let mut text = format!("Rebuilt {} archive segment(s) holding {} item(s).", report.segments, report.records);
+                if report.dropped > 0 {
+                    text.push_str(&format!("\n{} unreadable line(s) were moved to *.corrupt-* files in the archive folder.", report.dropped));
+                }
+                self.show_notice(text);
+            }
+            Err(e) => self.show_error(format!("Could not rebuild the archive:\n{}", e)),
+        }
+        self.reload_archive();
+    }
+
+    fn export_archive(&mut self) {
+        if self.refused_in_read_only("Exporting the archive") {
+            return;
+        }
+        let exported = tasks::get_data_dir(&self.exe_file_path).and_then(|dir| {
+            let dest = dir.join(format!("archived-export-{}.jsonl", Local::now().format("%Y%m%d-%H%M%S")));
+            tasks::export_archive_jsonl(&dest, &self.exe_file_path).map(|count| (dest, count))
+        });
+
+        match exported {
+            Ok((dest, count)) => self.show_notice(format!("Exported {} archived item(s) to\n{}", count, dest.display())),
+            Err(e) => self.show_error(format!("Could not export the archive:\n{}", e)),
+        }
+    }
+
     fn display_date_entering(&mut self, ui: &mut Ui) {
-        let space_font = FontId::new(14.0, FontFamily::Name("space".into()));
+        let space_font = FontId::new(14.0, FontFamily::Name(theme::DETAIL.into()));
 
         egui::Frame::default()
-            .stroke(Stroke::new(0.9, Color32::from_white_alpha(80)))
+            .stroke(Stroke::new(0.9, self.theme.stroke_with_alpha(80)))
             .corner_radius(CornerRadius::same(5))
             .inner_margin(Margin { left: 3, right: 3, top: 0, bottom: 2 })
             .show(ui, |ui| {
@@ -1328,15 +1975,27 @@ impl TaskApp {
     /// runtime setter used to duplicate. Values go in with their real TOML type
     /// (bool / integer / float-array / string) — never stringified numbers — so
     /// this agrees with the startup writer (`write_normalized_config`).
+    /// A key the profile sets is written to the profile. Nothing is written
+    /// in read-only mode; the change lasts until the app closes.
     fn write_config_value(
-        &self,
+        &mut self,
         key: &str,
         value: impl Into<toml_edit::Value>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let toml_content = fs::read_to_string(&self.userconfig_path)?;
+        if self.launch.read_only {
+            return Ok(());
+        }
+        let path = self.config_files.file_for(key).clone();
+        let toml_content = fs::read_to_string(&path)?;
         let mut doc = toml_content.parse::<DocumentMut>()?;
         doc[key] = toml_edit::value(value);
-        fs::write(&self.userconfig_path, doc.to_string())?;
+        let contents = doc.to_string();
+        fs::write(&path, &contents)?;
+        // Our own write, not an edit to reload.
+        match &mut self.profile_watcher {
+            Some(watcher) if self.config_files.profile.as_ref() == Some(&path) => watcher.wrote(contents),
+            _ => self.config_watcher.wrote(contents),
+        }
         Ok(())
     }
 
@@ -1348,6 +2007,122 @@ impl TaskApp {
         }
     }
 
+    /// Picks up edits to `userconfig.toml` made while running: applies what
+    /// can change live (`apply_config`) and says in a notice what only
+    /// applies after a restart. Problems with the file go to the error
+    /// window; a file that isn't TOML changes nothing until it is fixed.
+    fn reload_config_if_changed(&mut self, ctx: &Context) {
+        let base_changed = self.config_watcher.poll().is_some();
+        let profile_changed = self.profile_watcher.as_mut().is_some_and(|watcher| watcher.poll().is_some());
+        if !base_changed && !profile_changed {
+            return;
+        }
+        let changed = match &self.config_files.profile {
+            Some(profile) if profile_changed => profile.clone(),
+            _ => self.config_files.base.clone(),
+        };
+
+        let overlay = self.profile_watcher.as_ref().map(ConfigWatcher::contents);
+        let (mut config, issues) = match initialization::check_profile_config(self.config_watcher.contents(), overlay) {
+            Ok(checked) => checked,
+            Err(issue) => {
+                self.show_error(format!("Could not reload {}:\n{}", changed.display(), issue));
+                return;
+            }
+        };
+        if !issues.is_empty() {
+            self.show_error(format!("Problems in {}:\n{}", changed.display(), initialization::issues_text(&issues)));
+        }
+        // The command line still wins.
+        self.launch.apply(&mut config);
+
+        let restart_keys = self.apply_config(ctx, config);
+        if !restart_keys.is_empty() {
+            let name = changed.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+            self.show_notice(format!("Reloaded {name}. These changes apply after a restart:\n{}", restart_keys.join("\n")));
+        }
+    }
+    /// Applies a reloaded config to the running app, the way the Settings
+    /// controls do but without writing it back. Returns the changed keys that
+    /// only take effect after a restart (the window is placed at launch).
+    fn apply_config(&mut self, ctx: &Context, config: Config) -> Vec<&'static str> {
+        let mut restart_keys = Vec::new();
+        if config.start_in_fullscreen != self.start_in_fullscreen {
+            self.start_in_fullscreen = config.start_in_fullscreen;
+            restart_keys.push("start_in_fullscreen");
+        }
+        if config.window_size_startup != self.window_size_startup {
+            self.window_size_startup = config.window_size_startup;
+            restart_keys.push("window_size_startup");
+        }
+        if config.selected_monitor_name != self.selected_monitor_name {
+            self.selected_monitor_name = config.selected_monitor_name;
+            restart_keys.push("selected_monitor_name");
+        }
+
+        self.enable_fps_counter = config.enable_fps_counter;
+        self.three_day_weather = config.three_day_weather;
+        self.weather_details_row = config.weather_details_row;
+        self.calendar_weather = config.calendar_weather;
+        self.night_bands = config.night_bands;
+        self.night_dimming = config.night_dimming;
+        self.item_cues = config.item_cues;
+        self.selected_theme_id = config.selected_theme_id;
+        self.background_image_tint_percent = config.background_image_tint_percent;
+        self.background_tint_input = config.background_image_tint_percent.to_string();
+
+        if config.calendar_weeks_to_show != self.calendar_weeks_to_show {
+            self.calendar_weeks_to_show = config.calendar_weeks_to_show;
+            self.week_number_input = config.calendar_weeks_to_show.to_string();
+            self.summarize_calendar();
+            self.sync_calendar_caches();
+        }
+        if config.selected_colorscheme_id != self.selected_colorscheme_id {
+            self.selected_colorscheme_id = config.selected_colorscheme_id;
+            self.active_colorscheme = resolve_colorscheme(&self.colorschemes, config.selected_colorscheme_id);
+        }
+        if let Some(index) = self.background_options.iter().position(|name| *name == config.background)
+            && index != self.selected_background_index
+        {
+            self.selected_background_index = index;
+            self.background_image_texture = Some(set_background(ctx, &self.images_dir, config.background));
+        }
+        if config.archive_past_events_after_days != self.archive_past_events_after_days {
+            self.archive_past_events_after_days = config.archive_past_events_after_days;
+            self.archive_events_after_input = config.archive_past_events_after_days.to_string();
+            self.archive_expired_events();
+        }
+
+        if config.alert_rules != self.alert_rules {
+            self.alert_rules = config.alert_rules;
+            self.weather_service.set_alert_rules(config.alert_rules);
+        }
+        if config.weather_refresh != self.weather_refresh {
+            self.weather_refresh = config.weather_refresh;
+            self.weather_service.set_refresh_policy(config.weather_refresh);
+        }
+        if config.weather_provider != self.weather_provider {
+            self.weather_provider = config.weather_provider;
+            self.weather_service.set_provider(config.weather_provider);
+        }
+        if config.units != self.units {
+            self.units = config.units;
+            self.weather_service.set_units(config.units);
+        }
+        if config.locations != self.locations {
+            self.weather_service.set_locations(&config.locations);
+            self.locations = config.locations;
+            self.event_location_input = None;
+        }
+        self.selected_location = self.locations.iter().position(|location| location.name == config.weather_location).unwrap_or(0);
+        self.temperature_rounding = config.temperature_rounding;
+        self.weather_window_hours = config.weather_window_hours;
+        self.forecast_days = config.forecast_days;
+        // Cheap, and covers every weather setting above.
+        self.fix_and_cache_weather_data();
+
+        restart_keys
+    }
     fn set_calendar_weeks(&mut self) {
         let truncated: String = self.week_number_input.chars().take(5).collect();
         match truncated.parse::<usize>() {
@@ -1377,61 +2152,339 @@ impl TaskApp {
             self.persist_config_value("background_image_tint_percent", clamped as i64);
         }
     }
-    fn set_weather_coordinates(&mut self) {
-        let coords = self.coordinates;
-        self.weather_service.set_coordinates(coords);
-        self.persist_config_value("coordinates", utilities::float_pair_array(coords));
+    fn set_archive_events_after(&mut self) {
+        match self.archive_events_after_input.trim().parse::<u32>() {
+            Ok(days) => {
+                let clamped = days.min(crate::initialization::ARCHIVE_EVENTS_AFTER_DAYS_MAX);
+                self.archive_past_events_after_days = clamped;
+                self.archive_events_after_input = clamped.to_string();
+                self.persist_config_value("archive_past_events_after_days", clamped as i64);
+                // A shorter window may already cover some events; sweep them now.
+                self.archive_expired_events();
+            }
+            Err(_) => self.archive_events_after_input = self.archive_past_events_after_days.to_string(),
+        }
+    }
+    fn set_alert_rules(&mut self) {
+        let rules = self.alert_rules;
+        self.persist_config_value("alert_frost_below", crate::alerts::threshold_to_toml(rules.frost_below));
+        self.persist_config_value("alert_rain_above", crate::alerts::threshold_to_toml(rules.rain_above));
+        self.persist_config_value("alert_gusts_above", crate::alerts::threshold_to_toml(rules.gusts_above));
+        self.persist_config_value("alert_thunderstorms", rules.thunderstorms);
+        self.persist_config_value("alert_horizon_hours", rules.horizon_hours as i64);
+        self.weather_service.set_alert_rules(rules);
+    }
+    /// Where the shown location is, for the sun and the alert banner.
+    fn shown_coordinates(&self) -> [f32; 2] {
+        self.locations[self.selected_location].coordinates
+    }
+    /// The weather location a calendar item is at: its own while that is
+    /// still saved (else none), or the shown one for items without.
+    fn item_location(&self, item: &DayItem) -> Option<usize> {
+        match &item.location {
+            Some(location) => self.locations.iter().position(|saved| weather::same_place(saved.coordinates, location.coordinates)),
+            None => Some(self.selected_location),
+        }
+    }
+    /// Show `index` of `locations` in the forecast panel and the calendar.
+    /// Every location is already fetched, so nothing is refetched.
+    fn select_weather_location(&mut self, index: usize) {
+        self.selected_location = index;
+        let name = self.locations[index].name.clone();
+        self.persist_config_value("weather_location", name);
+        self.fix_and_cache_weather_data();
+    }
+    /// Save the picked `coordinates` as a location named from
+    /// `location_name_input`, or after the coordinates when that is empty.
+    fn add_weather_location(&mut self) {
+        let coordinates = self.coordinates;
+        let name = match self.location_name_input.trim() {
+            "" => format!("{:.2}, {:.2}", coordinates[0], coordinates[1]),
+            name => name.to_string(),
+        };
+        self.locations.push(Location { name, coordinates });
+        self.location_name_input.clear();
+        self.set_weather_locations();
+    }
+    /// Move the picker's marker to a searched `place`, name it after the
+    /// place and zoom the map in around it.
+    fn go_to_place(&mut self, place: &Place) {
+        const PLACE_ZOOM: f32 = 8.0;
+
+        self.coordinates = place.coordinates();
+        self.location_name_input = place.name.to_string();
+        self.place_search_results.clear();
+
+        self.map_zoom = PLACE_ZOOM;
+        let uv_size = Vec2::splat(1.0 / self.map_zoom);
+        let world_uv = vec2((place.longitude + 180.0) / 360.0, 1.0 - ((place.latitude + 90.0) / 180.0));
+        self.map_offset = (world_uv - uv_size * 0.5).clamp(Vec2::ZERO, Vec2::splat(1.0) - uv_size);
+    }
+    /// The last location can't be removed. Removing the shown one shows the first.
+    fn remove_weather_location(&mut self, index: usize) {
+        if self.locations.len() <= 1 {
+            return;
+        }
+        self.locations.remove(index);
+        if self.selected_location == index {
+            self.selected_location = 0;
+        } else if self.selected_location > index {
+            self.selected_location -= 1;
+        }
+        self.event_location_input = None;
+        self.set_weather_locations();
+    }
+    /// The saved locations as chips, each (but a last one) with a button
+    /// removing it. Shared by Settings and the map picker.
+    fn show_location_chips(&mut self, ui: &mut Ui) {
+        let mut remove = None;
+        for (index, location) in self.locations.iter().enumerate() {
+            let alpha = if index == self.selected_location { 200 } else { 90 };
+            egui::Frame::new()
+                .stroke(Stroke::new(1.0, self.theme.stroke_with_alpha(alpha)))
+                .corner_radius(CornerRadius::same(8))
+                .inner_margin(Margin::symmetric(6, 2))
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(&location.name)
+                            .on_hover_text(format!("{:.2}, {:.2}", location.coordinates[0], location.coordinates[1]));
+                        if self.locations.len() > 1 && ui.small_button("x").on_hover_text("Remove this location").clicked() {
+                            remove = Some(index);
+                        }
+                    });
+                });
+        }
+        if let Some(index) = remove {
+            self.remove_weather_location(index);
+        }
+    }
+    fn set_weather_locations(&mut self) {
+        self.weather_service.set_locations(&self.locations);
+        self.persist_config_value("locations", weather::locations_to_toml(&self.locations));
+        let name = self.locations[self.selected_location].name.clone();
+        self.persist_config_value("weather_location", name);
+        // Indices into `locations` have moved; the new ones' forecasts follow
+        // with the refetch.
+        self.fix_and_cache_weather_data();
+    }
+    /// Re-shape the cached forecast in the new units right away, and have the
+    /// thread refetch in them where the provider can.
+    fn set_weather_units(&mut self) {
+        self.weather_service.set_units(self.units);
+        self.persist_config_value("temperature_unit", self.units.temperature.key());
+        self.persist_config_value("wind_speed_unit", self.units.wind.key());
+        self.persist_config_value("precipitation_unit", self.units.precipitation.key());
+        self.fix_and_cache_weather_data();
+    }
+    fn set_weather_provider(&mut self) {
+        self.weather_service.set_provider(self.weather_provider);
+        self.persist_config_value("weather_provider", self.weather_provider.key());
     }
     fn set_selected_monitor_name(&mut self) {
         self.selected_monitor_name = self.monitor_options.get(self.selected_monitor_index).unwrap_or(&"".to_string()).to_string();
         let name: String = self.selected_monitor_name.chars().take(1000).collect();
         self.persist_config_value("selected_monitor_name", name);
     }
+    /// A dot, amber while the latest fetch has failed, whose tooltip shows
+    /// what the weather thread is up to and offers "Refresh now".
+    fn show_weather_status(&self, ui: &mut Ui) {
+        let status = self.weather_service.status.read().map(|status| status.clone()).unwrap_or_default();
+        let color = if status.failing() { ALERT_COLOR } else { self.theme.weak_text_color() };
+        let now = self.date;
+        let at = |time: DateTime<Local>| time.format("%H:%M:%S").to_string();
+
+        let dot = ui.add(Label::new(RichText::new("●").size(11.0).color(color)).sense(egui::Sense::hover()));
+        dot.on_hover_ui(|ui| {
+            ui.set_max_width(320.0);
+            match (status.last_success, status.latency) {
+                (Some(succeeded_at), Some(latency)) => {
+                    ui.label(format!("Updated at {}, {} ago", at(succeeded_at), utilities::format_duration_short(now - succeeded_at)));
+                    ui.label(format!("{} answered in {} ms", self.weather_provider.label(), latency.as_millis()));
+                }
+                _ => {
+                    ui.label("No forecast fetched since starting");
+                }
+            }
+            if let Some((failed_at, error)) = &status.last_error {
+                ui.label(RichText::new(format!("Failed at {}: {}", at(*failed_at), error)).color(ALERT_COLOR));
+            }
+            match (status.backoff, status.next_fetch) {
+                (Some(backoff), _) => {
+                    ui.label(format!(
+                        "Retrying after {} s (attempt {} of {})",
+                        backoff.as_secs(),
+                        status.failures + 1,
+                        self.weather_refresh.attempts,
+                    ));
+                }
+                (None, Some(next_fetch)) => {
+                    ui.label(format!("Next update at {}", at(next_fetch)));
+                }
+                (None, None) => {
+                    ui.label("Fetching…");
+                }
+            }
+            ui.separator();
+            if ui.button("Refresh now").clicked() {
+                self.weather_service.refresh();
+            }
+        });
+    }
+    /// Store the refresh schedule and hand it to the weather thread.
+    fn set_weather_refresh(&mut self) {
+        let policy = self.weather_refresh;
+        self.persist_config_value("weather_refresh_minutes", policy.interval_minutes as i64);
+        self.persist_config_value("weather_retry_attempts", policy.attempts as i64);
+        self.persist_config_value("weather_retry_backoff_seconds", policy.backoff_seconds as i64);
+        self.weather_service.set_refresh_policy(policy);
+    }
+    /// "last updated 3h 5m ago", if a forecast was ever fetched.
+    fn weather_age_text(&self) -> Option<String> {
+        self.weather_fetched_at
+            .map(|fetched_at| format!("last updated {} ago", utilities::format_duration_short(self.date - fetched_at)))
+    }
     fn fix_and_cache_weather_data(&mut self) {
         self.weather_is_broken_flag = false;
-        let static_weather_data = self.weather_service.data.read().map(|w| w.clone())
-            .unwrap_or_else(|_| vec![]);
-
-        // The reshape below indexes `static_weather_data[hour][day]` for all 24
-        // hours and days 0..=2, so both the outer length (24 hours) and every
-        // inner length (>= 3 days) must hold. A partial/short Open-Meteo response
-        // (DST edge, API change, truncated payload) would otherwise panic here.
-        if static_weather_data.len() != 24
-            || static_weather_data.iter().any(|hour| hour.len() < 3)
-        {
-            self.weather_is_broken_flag = true;
-            return ();
+        let units = self.units;
+        let forecasts: Vec<Forecast> = self.weather_service.data.read().map(|data| data.clone()).unwrap_or_default();
+        // Each location's forecast in the display units, until one is fetched `None`.
+        let located: Vec<Option<Forecast>> = self.locations
+            .iter()
+            .map(|location| forecasts.iter().find(|forecast| forecast.is_for(location.coordinates)).map(|forecast| forecast.clone().in_units(units)))
+            .collect();
+        let forecast = located.get(self.selected_location).cloned().flatten();
+
+        // Without a forecast (no fetch has succeeded and nothing was cached), or
+        // with one that no longer reaches the current time, there is nothing to
+        // show. Slots are cut on the machine's local clock, the one `self.date`
+        // highlights against, so DST days come out with 23 or 25 hours.
+        let now = self.date.naive_local();
+        let days = forecast.as_ref()
+            .filter(|forecast| forecast.covers(now))
+            .and_then(|forecast| forecast.days(now.date(), self.forecast_days, self.weather_window_hours, &Local).ok());
+        self.weather_alerts = self.weather_service.alerts.read().map(|alerts| alerts.clone()).unwrap_or_default();
+        self.weather_alert_days = HashMap::new();
+        for alert in &self.weather_alerts {
+            let Some(index) = self.locations.iter().position(|location| weather::same_place(location.coordinates, alert.coordinates)) else {
+                continue;
+            };
+            let message = alert.message(self.units, self.temperature_rounding);
+            for day in alert.days() {
+                self.weather_alert_days
+                    .entry((index, day))
+                    .and_modify(|text| *text = format!("{text}\n{message}"))
+                    .or_insert_with(|| message.clone());
+            }
         }
 
-        let mut weather_datas = vec![vec![], vec![], vec![]];
-
-        for day in 0..=2 {
-            for i in 0..12 {
-                let index_first_hour = 2 * i;
-                let index_second_hour = index_first_hour + 1;
-
-                let data1 = static_weather_data[index_first_hour][day].clone();
-                let data2 = static_weather_data[index_second_hour][day].clone();
-
-                let mut temp_avg = (data1.temp + data2.temp) / 2_f64;
-
-                temp_avg = temp_avg.round();
+        self.calendar_weather_days = located
+            .iter()
+            .enumerate()
+            .filter_map(|(index, forecast)| {
+                forecast.as_ref().filter(|forecast| forecast.covers(now)).map(|forecast| (index, forecast.summaries(&Local)))
+            })
+            .flat_map(|(index, days)| days.into_iter().filter(|day| day.date >= now.date()).map(move |day| ((index, day.date), day)))
+            .collect();
+        self.weather_fetched_at = forecast.as_ref().map(|forecast| forecast.fetched_at);
+        self.weather_covered_until = forecast.as_ref().and_then(|forecast| forecast.covered_until());
+        let (Some(days), Some(covered_until)) = (days, self.weather_covered_until) else {
+            self.weather_is_broken_flag = true;
+            return ();
+        };
+        // The sun for the place the forecast is for, not for coordinates
+        // still being picked in Settings.
+        let coordinates = forecast.as_ref().map_or(self.shown_coordinates(), |forecast| forecast.coordinates);
+        let is_night = |start: NaiveDateTime, end: NaiveDateTime| {
+            Local.from_local_datetime(&(start + (end - start) / 2))
+                .earliest()
+                .is_some_and(|middle| solar::sun_elevation(middle.to_utc(), coordinates) < solar::SUNRISE_ELEVATION)
+        };
 
-                let eps = 0.1;
-                
-                if (temp_avg.abs()) < eps {
-                    temp_avg = 0.0;
+        self.weather_data_cache = days
+            .into_iter()
+            .map(|(date, slots)| {
+                let midnight = (date + Duration::days(1)).and_time(NaiveTime::MIN);
+                let ends: Vec<NaiveDateTime> = slots.iter().skip(1).map(|slot| slot.time).chain([midnight]).collect();
+                WeatherDay {
+                    label: date.format("%A").to_string(),
+                    sun: solar::sun_day(date, coordinates),
+                    slots: slots
+                        .into_iter()
+                        .zip(ends)
+                        .map(|(data, end)| WeatherSlot {
+                            time: data.time.format("%H:%M").to_string(),
+                            start: data.time,
+                            end,
+                            // Rounded (and kept from showing "-0") at display
+                            // time, per `temperature_rounding`.
+                            temp: data.temp,
+                            symbol: data.symbol,
+                            phase: data.phase,
+                            covered: data.time < covered_until,
+                            details: data.details,
+                            night: is_night(data.time, end),
+                        })
+                        .collect(),
                 }
-
-                //we set the weather code to be the greater one of the two
-                let weather_code = data1.weather_code.max(data2.weather_code);
-
-                //we maintain that the icon should contain the sun if the first or second hour is classified as being during the day
-                weather_datas[day].push((data1.time, temp_avg, weather_code, data1.is_day == 1 || data2.is_day == 1));
+            })
+            .collect();
+    }
+    fn open_backup_dialog(&mut self) {
+        self.backup_restore_flag = true;
+        self.backup_selected = None;
+        self.backup_diff = None;
+        self.confirm_restore_flag = false;
+        match backup::list_snapshots(&self.exe_file_path) {
+            Ok(snapshots) => self.backup_snapshots = snapshots,
+            Err(e) => {
+                self.backup_snapshots = Vec::new();
+                self.show_error(format!("Could not list backups:\n{}", e));
             }
         }
-
-        self.weather_data_cache = weather_datas;
+    }
+    fn back_up_now(&mut self) {
+        if self.refused_in_read_only("Backing up") {
+            return;
+        }
+        let result = backup::take_snapshot(SnapshotKind::Scheduled, &self.config_files.base, &self.exe_file_path)
+            .and_then(|path| backup::prune_backups(&self.exe_file_path).map(|_| path));
+        match result {
+            Ok(path) => {
+                self.open_backup_dialog();
+                self.show_notice(format!("Backed up to\n{}", path.display()));
+            }
+            Err(e) => self.show_error(format!("Backup failed:\n{}", e)),
+        }
+    }
+    /// Checked on day change so an always-on instance keeps its daily
+    /// generations without a restart (startup runs the same check in `main`).
+    fn run_scheduled_backup(&mut self) {
+        if self.launch.read_only {
+            return;
+        }
+        if let Err(e) = backup::run_scheduled_backup(&self.config_files.base, &self.exe_file_path) {
+            self.show_error(format!("Scheduled backup failed:\n{}", e));
+        }
+    }
+    fn compare_selected_backup(&mut self) {
+        let Some(snapshot) = self.backup_selected.and_then(|i| self.backup_snapshots.get(i)) else { return };
+        match backup::diff_with_current(&snapshot.path, &self.config_files.base, &self.exe_file_path) {
+            Ok(diff) => self.backup_diff = Some(diff),
+            Err(e) => self.show_error(format!("Could not compare the backup:\n{}", e)),
+        }
+    }
+    /// Restore writes the files underneath the running app, whose in-memory
+    /// state is now stale; restarting is the only consistent way to pick it up.
+    fn restore_selected_backup(&mut self) {
+        self.confirm_restore_flag = false;
+        if self.refused_in_read_only("Restoring a backup") {
+            return;
+        }
+        let Some(snapshot) = self.backup_selected.and_then(|i| self.backup_snapshots.get(i)) else { return };
+        match backup::restore_snapshot(&snapshot.path, &self.config_files.base, &self.exe_file_path) {
+            Ok(()) => self.restart_self(),
+            Err(e) => self.show_error(format!("Restore failed:\n{}", e)),
+        }
     }
     fn restart_self(&mut self) {
         // Restart by spawning a fresh copy and exiting. Any failure is reported
@@ -1454,7 +2507,7 @@ impl TaskApp {
     fn save_textbox_text(&mut self) {
         if self.should_save_textbox_text {
             // A silent failure here loses the user's notes; surface it instead.
-            if let Err(e) = utilities::save_notepad_text(self.textbox_text.clone(), &self.exe_file_path) {
+            if !self.launch.read_only && let Err(e) = utilities::save_notepad_text(self.textbox_text.clone(), &self.exe_file_path) {
                 self.show_error(format!("Could not save notepad text:\n{}", e));
             }
             self.should_save_textbox_text = false;
@@ -1486,7 +2539,7 @@ impl TaskApp {
     fn duplicate_current_colorscheme(&mut self) {
         let duplicate = self.colorschemes.get(&self.selected_colorscheme_id).unwrap_or(&ColorScheme::default_scheme()).duplicate();
 
-        let new_id = self.colorschemes.keys().max().unwrap_or(&0) + 1;
+        let new_id = color::fresh_scheme_id(&self.colorschemes);
 
         self.colorschemes.insert(new_id, duplicate);
 
@@ -1510,24 +2563,174 @@ impl TaskApp {
         }
     }
     fn add_schemes_2_doc(&self) {
+        if self.launch.read_only {
+            return;
+        }
         let _ = color::save_colorschemes(&self.colorschemes, &self.exe_file_path);
     }
+    fn export_current_colorscheme(&mut self, format: SchemeFormat) {
+        if self.refused_in_read_only("Exporting a colour scheme") {
+            return;
+        }
+        let scheme = self.colorschemes.get(&self.selected_colorscheme_id).cloned().unwrap_or(ColorScheme::default_scheme());
+        match color::export_scheme_to_data_dir(&scheme, format, &self.exe_file_path) {
+            Ok(path) => self.show_notice(format!("Exported \"{}\" to\n{}", scheme.name, path.display())),
+            Err(e) => self.show_error(format!("Could not export the colour scheme:\n{}", e)),
+        }
+    }
+    /// Imports a scheme file, typed into the manager or dropped on the window
+    /// (`bytes` when the platform hands over the contents), and selects it.
+    /// A scheme whose colours are already there selects that one instead.
+    fn import_colorscheme(&mut self, path: &Path, bytes: Option<&[u8]>) {
+        let Some(format) = SchemeFormat::of_path(path) else {
+            self.show_error(format!("Could not import {}:\ncolour schemes are .json, .toml, .gpl or .ase files.", path.display()));
+            return;
+        };
+        let read = match bytes {
+            Some(bytes) => Ok(bytes.to_vec()),
+            None => fs::read(path).map_err(Box::from),
+        };
+        let fallback_name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        let ImportedScheme { scheme, colors_in_file } = match read.and_then(|bytes| color::import_scheme(format, &bytes, &fallback_name)) {
+            Ok(imported) => imported,
+            Err(e) => {
+                self.show_error(format!("Could not import {}:\n{}", path.display(), e));
+                return;
+            }
+        };
+
+        let name = scheme.name.clone();
+        let (id, text) = match color::add_scheme(&mut self.colorschemes, scheme) {
+            Ok(id) => {
+                self.add_schemes_2_doc();
+                let mut text = format!("Imported \"{name}\".");
+                if colors_in_file > 6 {
+                    text.push_str(&format!(" The file has {colors_in_file} colours; the first 6 are used."));
+                }
+                (id, text)
+            }
+            Err(id) => (id, format!("\"{name}\" has the same colours as \"{}\", which is selected instead.", self.colorschemes[&id].name)),
+        };
+        self.selected_colorscheme_id = id;
+        self.set_colorscheme();
+        self.show_notice(text);
+    }
     fn save_colorscheme_edits(&mut self) {
         if let Some(scheme) = self.colorscheme_being_edited.take() {
             self.colorschemes.insert(self.selected_colorscheme_id, scheme);
         }
     }
-    fn try_to_generate_colorscheme(&mut self) {
+    /// What the calendar's fills are drawn over.
+    fn backdrop(&self, ctx: &Context) -> Backdrop {
+        let panel = ctx.global_style().visuals.panel_fill;
+        Backdrop {
+            region: self.calendar_region,
+            tint: (self.background_image_tint_percent as f32 / 100.0).clamp(0.0, 1.0),
+            panel: [panel.r(), panel.g(), panel.b()],
+        }
+    }
+    /// The text drawn on the fills, which schemes are checked against.
+    fn contrast_goal(&self, ctx: &Context) -> ContrastGoal {
+        let visuals = &ctx.global_style().visuals;
+        let rgb = |c: Color32| [c.r(), c.g(), c.b()];
+        ContrastGoal {
+            texts: vec![
+                ("Item names", rgb(self.theme.item_text_color())),
+                ("Day numbers", rgb(visuals.text_color())),
+                ("Today", rgb(visuals.strong_text_color())),
+            ],
+            ratio: color::WCAG_AA,
+        }
+    }
+    /// Samples the calendar's backdrop for the contrast badges. The image is
+    /// only read again when the background, tint or calendar area changed.
+    fn refresh_backdrop_samples(&mut self, ctx: &Context) {
+        let backdrop = self.backdrop(ctx);
+        let name = self.background_options.get(self.selected_background_index).cloned().unwrap_or_default();
+        if self.backdrop_samples.as_ref().is_some_and(|(b, n, _)| *b == backdrop && *n == name) {
+            return;
+        }
+        let image = color::load_background_image(&self.images_dir, &name);
+        let samples = backdrop.sample(image.as_ref());
+        self.backdrop_samples = Some((backdrop, name, samples));
+    }
+    fn try_to_generate_colorscheme(&mut self, ctx: &Context) {
         let name = self.background_options[self.selected_background_index].clone();
 
-        if let Some(scheme) = color::generate_colorscheme(name) {
-            let new_id = self.colorschemes.keys().max().unwrap_or(&0) + 1;
+        if let Some(scheme) = color::generate_colorscheme(&self.images_dir, name, &self.backdrop(ctx), &self.contrast_goal(ctx), &self.palette_options) {
+            let new_id = color::fresh_scheme_id(&self.colorschemes);
 
             self.colorschemes.insert(new_id, scheme);
 
             self.add_schemes_2_doc();
         }
     }
+    /// Runs every algorithm on the current background for the preview window.
+    fn preview_palettes(&mut self, ctx: &Context) {
+        let name = self.background_options[self.selected_background_index].clone();
+        let previews = color::preview_colorschemes(&self.images_dir, &name, &self.backdrop(ctx), &self.contrast_goal(ctx), &self.palette_options);
+        if previews.is_empty() {
+            self.show_error(format!("No colours could be read from \"{name}\"."));
+            return;
+        }
+        self.palette_previews = Some(previews);
+    }
+    /// Adds a previewed scheme and selects it.
+    fn keep_previewed_colorscheme(&mut self, scheme: ColorScheme) {
+        let new_id = color::fresh_scheme_id(&self.colorschemes);
+        self.colorschemes.insert(new_id, scheme);
+        self.add_schemes_2_doc();
+        self.selected_colorscheme_id = new_id;
+        self.set_colorscheme();
+        self.palette_previews = None;
+    }
+    /// Installs the theme being edited, or else the selected one, when egui
+    /// has another. Fonts are only rebuilt then, not every frame.
+    fn sync_theme(&mut self, ctx: &Context) {
+        let dark = Theme::dark();
+        let wanted = self.theme_being_edited.as_ref().or_else(|| self.themes.get(&self.selected_theme_id)).unwrap_or(&dark);
+        if *wanted != self.theme {
+            theme::install(ctx, wanted);
+            self.theme = wanted.clone();
+        }
+    }
+    fn select_theme(&mut self, id: u32) {
+        self.selected_theme_id = id;
+        self.persist_config_value("selected_theme_id", id as i64);
+    }
+    fn save_themes(&mut self) {
+        if self.launch.read_only {
+            return;
+        }
+        if let Err(e) = theme::save_themes(&self.themes, &self.exe_file_path) {
+            self.show_error(format!("Could not save the themes:\n{e}"));
+        }
+    }
+    fn currently_selected_theme_is_user_configurable(&self) -> bool {
+        self.themes.get(&self.selected_theme_id).is_some_and(|theme| theme.is_user_configurable)
+    }
+    /// Copies the selected theme into a new, editable one and selects it.
+    fn duplicate_current_theme(&mut self) {
+        let duplicate = self.themes.get(&self.selected_theme_id).cloned().unwrap_or_else(Theme::dark).duplicate();
+        let new_id = theme::fresh_theme_id(&self.themes);
+        self.themes.insert(new_id, duplicate);
+        self.save_themes();
+        self.select_theme(new_id);
+    }
+    fn delete_current_theme(&mut self) {
+        if self.currently_selected_theme_is_user_configurable() {
+            self.themes.remove(&self.selected_theme_id);
+            self.select_theme(theme::DARK_THEME_ID);
+            self.save_themes();
+        }
+        self.user_wants_to_delete_theme_flag = false;
+    }
+    fn save_theme_edits(&mut self) {
+        if let Some(theme) = self.theme_being_edited.take() {
+            self.themes.insert(self.selected_theme_id, theme);
+            self.save_themes();
+        }
+    }
 }
 
 impl TaskApp {
@@ -1538,9 +2741,10 @@ impl TaskApp {
         // top-level panels can still borrow the root `ui` mutably (the clone doesn't borrow `ui`).
         let ctx_owned = ui.ctx().clone();
         let ctx = &ctx_owned;
+        self.sync_theme(ctx);
         if self.background_image_texture.is_none() {
             if let Some(name) = self.pending_initial_background.take() {
-                self.background_image_texture = Some(set_background(ctx, name.clone()));
+                self.background_image_texture = Some(set_background(ctx, &self.images_dir, name.clone()));
             }
         }
 
@@ -1558,6 +2762,20 @@ impl TaskApp {
             ctx.send_viewport_cmd(ViewportCommand::Fullscreen(new_fullscreen));
         }
 
+        self.reload_config_if_changed(ctx);
+
+        // Colour scheme files dropped on the window are imported.
+        for file in ctx.input(|i| i.raw.dropped_files.clone()) {
+            let path = file.path.clone().unwrap_or_else(|| PathBuf::from(&file.name));
+            self.import_colorscheme(&path, file.bytes.as_deref());
+        }
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("drop_hint")));
+            let rect = ctx.content_rect();
+            painter.rect_filled(rect, 0.0, Color32::from_black_alpha(160));
+            painter.text(rect.center(), egui::Align2::CENTER_CENTER, "Drop a colour scheme (.json, .toml, .gpl, .ase) to import it", FontId::proportional(24.0), Color32::WHITE);
+        }
+
         let current_weather = self.weather_service.version.load(Ordering::Relaxed);
         if current_weather != self.last_weather_version {
             self.fix_and_cache_weather_data();
@@ -1568,7 +2786,26 @@ impl TaskApp {
         self.date = chrono::Local::now();
         if self.date.day() != old_date.day() {
             self.summarize_calendar();
-            self.next_three_weekdays = next_three_weekdays(self.date);
+            self.archive_expired_events();
+            self.run_scheduled_backup();
+            // Re-anchor the forecast on the new day.
+            self.fix_and_cache_weather_data();
+        }
+
+        if self.night_dimming {
+            let level = solar::night_dimming(self.date.to_utc(), self.shown_coordinates());
+            if level > 0.0 {
+                // Over everything but tooltips, without taking any input.
+                ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("night_dimming")))
+                    .rect_filled(ctx.content_rect(), 0.0, Color32::from_black_alpha((level * 90.0) as u8));
+            }
+        }
+
+        // Offline, a cached forecast eventually runs out.
+        if !self.weather_is_broken_flag
+            && self.weather_covered_until.is_some_and(|end| self.date.naive_local() >= end)
+        {
+            self.weather_is_broken_flag = true;
         }
 
         // Debounced notepad autosave: persist ~2s after the last edit. This uses
@@ -1628,6 +2865,14 @@ impl TaskApp {
                     if self.enable_fps_counter {
                         ui.label(self.fps_counter.fps_text.clone());
                     }
+
+                    if self.launch.read_only {
+                        ui.label(RichText::new("Read-only").color(ALERT_COLOR))
+                            .on_hover_text("Started with --read-only: changes last until TaskDeck closes and nothing is saved.");
+                    }
+                    if let Some(profile) = &self.launch.profile {
+                        ui.label(format!("Profile: {profile}"));
+                    }
                 });
             });
         });
@@ -1660,7 +2905,17 @@ impl TaskApp {
 
                 self.show_tasks(ui);
 
-                self.show_calendar(ui);              
+                let calendar_left = ui.cursor().left();
+                self.show_calendar(ui);
+                let calendar_right = ui.cursor().left();
+                // Rounded so the contrast badges don't resample on every pixel of a resize.
+                let fraction = |v: f32, from: f32, size: f32| (((v - from) / size.max(1.0)) * 100.0).round() / 100.0;
+                self.calendar_region = [
+                    fraction(calendar_left, screen_rect.left(), screen_rect.width()),
+                    0.0,
+                    fraction(calendar_right, screen_rect.left(), screen_rect.width()),
+                    1.0,
+                ];
 
                 ui.add_space(-20.0);
 
@@ -1739,6 +2994,23 @@ impl TaskApp {
                         ui.label("Date:");
                         self.display_date_entering(ui);
 
+                        ui.add_space(10.0);
+                        ui.checkbox(&mut self.event_outdoor_input, "Outdoors (flagged by weather alerts)");
+
+                        ui.add_space(5.0);
+                        ui.horizontal(|ui| {
+                            ui.label("Weather location:");
+                            let selected = self.event_location_input.and_then(|index| self.locations.get(index)).map_or("None", |location| location.name.as_str());
+                            ComboBox::from_id_salt("event location combo")
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.event_location_input, None, "None");
+                                    for (index, location) in self.locations.iter().enumerate() {
+                                        ui.selectable_value(&mut self.event_location_input, Some(index), &location.name);
+                                    }
+                                });
+                        });
+
                         ui.add_space(15.0);
                         
                         ui.horizontal(|ui| {
@@ -1747,7 +3019,8 @@ impl TaskApp {
                                 // so duplicates are allowed.
                                 match utilities::parse_time_input(self.day_input, self.month_input, self.year_input, self.hour_input, self.minute_input) {
                                     Ok(date) => {
-                                        self.add_active_thing(self.event_name_input.clone(), Some(date), None, true, None);
+                                        let location = self.event_location_input.and_then(|index| self.locations.get(index)).cloned();
+                                        self.add_active_thing(self.event_name_input.clone(), Some(date), None, None, Some(NewEvent { outdoor: self.event_outdoor_input, location }));
                                         self.new_event_flag = false;
                                     },
                                     _ => {
@@ -1820,12 +3093,12 @@ impl TaskApp {
                                 // Names are cosmetic now (items are keyed by id),
                                 // so duplicates are allowed.
                                 if !self.use_date_for_addable {
-                                    self.add_active_thing(self.task_name_input.clone(), None, None, false, Some(self.time_importance_input));
+                                    self.add_active_thing(self.task_name_input.clone(), None, None, Some(self.time_importance_input), None);
                                     self.new_task_flag = false;
                                 } else {
                                     match date {
                                         Ok(date) => {
-                                            self.add_active_thing(self.task_name_input.clone(), Some(date), Some(importance), false, None);
+                                            self.add_active_thing(self.task_name_input.clone(), Some(date), Some(importance), None, None);
                                             self.new_task_flag = false;
                                         },
                                         _ => {self.show_error("Problem with date".to_string())},
@@ -1846,6 +3119,20 @@ impl TaskApp {
             if let Some(index) = self.expanded_day {
                 if let Some(day) = self.calendar_elements.get(index) {
                 let selected_date = day.date;
+                // Per item: the alert flagging it if it is outdoors, and an
+                // event's own location with its forecast for the day.
+                let item_weather: Vec<ItemWeather> = day.items
+                    .iter()
+                    .map(|item| {
+                        let index = self.item_location(item);
+                        ItemWeather {
+                            alert: index.filter(|_| item.outdoor).and_then(|index| self.weather_alert_days.get(&(index, selected_date))).cloned(),
+                            place: item.location.as_ref().map(|location| {
+                                (location.name.clone(), index.and_then(|index| self.calendar_weather_days.get(&(index, selected_date))).copied())
+                            }),
+                        }
+                    })
+                    .collect();
 
                 let (weekday_str, formatted_date) = utilities::format_date(selected_date);
 
@@ -1858,7 +3145,7 @@ impl TaskApp {
                         ui.vertical_centered(|ui| {
                             ui.label(formatted_date);
                             ui.add_space(-9.0);
-                            ui.add(Label::new(RichText::new(weekday_str).font(FontId::new(50.0, FontFamily::Name("anton".into())))).selectable(false));
+                            ui.add(Label::new(RichText::new(weekday_str).font(FontId::new(50.0, FontFamily::Name(theme::NUMBERS.into())))).selectable(false));
                             ui.add_space(10.0);
                             ui.separator();
                             ui.add_space(2.0);
@@ -1867,22 +3154,22 @@ impl TaskApp {
                             .auto_shrink([true, true])
                             .max_height(280.0)
                             .show(ui, |ui| {
-                                for item in &day.items {
-                                    egui::Frame::new()
-                                        .fill(Color32::from_white_alpha(15))
-                                        .stroke(egui::Stroke::new(1.5, ui.visuals().text_color()))
+                                for (item, ItemWeather { alert: flagged, place: located }) in day.items.iter().zip(&item_weather) {
+                                    let item_frame = egui::Frame::new()
+                                        .fill(self.theme.stroke_with_alpha(15))
+                                        .stroke(egui::Stroke::new(1.5, if flagged.is_some() { ALERT_COLOR } else { ui.visuals().text_color() }))
                                         .corner_radius(egui::CornerRadius::same(60))
                                         .inner_margin(Margin::symmetric(12, 12))
                                         .show(ui, |ui| {
                                             ui.set_min_size(egui::Vec2 { x: 320.0, y: 25.0 });
                                             ui.set_max_size(egui::Vec2 { x: 320.0, y: 25.0 });
                                             ui.horizontal(|ui| {
-                                                let time_font = FontId::new(13.0, FontFamily::Name("space".into()));
-                                                let text_font = FontId::new(12.0, FontFamily::Name("spaceb".into()));
+                                                let time_font = FontId::new(13.0, FontFamily::Name(theme::DETAIL.into()));
+                                                let text_font = FontId::new(12.0, FontFamily::Name(theme::LIST.into()));
 
                                                 ui.label(RichText::new(&item.time).font(time_font));
 
-                                                ui.add(Label::new(RichText::new(item.name.clone()).color(Color32::from_white_alpha(120)).font(text_font)).wrap().selectable(false));
+                                                ui.add(Label::new(RichText::new(item.name.clone()).color(self.theme.weak_text_color()).font(text_font)).wrap().selectable(false));
 
                                                 if ui.rect_contains_pointer(ui.max_rect()) {
                                                     if item.is_event {
@@ -1913,9 +3200,22 @@ impl TaskApp {
                                                             }
                                                         });
                                                     }
-                                                };
+                                                } else if let Some((place, forecast)) = located {
+                                                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                                        if let Some(day) = forecast {
+                                                            let rounding = if self.temperature_rounding == Rounding::Tenths { Rounding::Nearest } else { self.temperature_rounding };
+                                                            let (high, low) = (rounding.format(day.high), rounding.format(day.low));
+                                                            let wet = day.precipitation.is_some_and(|amount| self.units.precipitation.to_mm(amount) >= 1.0);
+                                                            ui.add(calendarwidgets::DayWeather::new(weather::icon_for_symbol(day.symbol, DayPhase::Day), &high, &low, wet));
+                                                        }
+                                                        ui.label(RichText::new(place).size(11.0).color(self.theme.weak_text_color()));
+                                                    });
+                                                }
                                             });
                                         });
+                                    if let Some(text) = flagged {
+                                        item_frame.response.on_hover_text(text);
+                                    }
                                 }
                             });
                         });
@@ -1963,12 +3263,115 @@ impl TaskApp {
                 .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
                 .show(ctx, |ui| {
                     ui.set_min_size(Vec2::new(500.0, 800.0));
-                    
+
+                    ui.horizontal(|ui| {
+                        let noun = if self.archive_query.is_default() { "archived items" } else { "matching items" };
+                        ui.label(RichText::new(format!("{} {}", self.archive_total, noun)).weak());
+
+                        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                            if ui.button("Export JSONL").on_hover_text("Write the whole archive to one plain .jsonl file in the data folder").clicked() {
+                                self.export_archive();
+                            }
+                            if ui.button("Rebuild index").on_hover_text("Regenerate the archive index and set aside unreadable lines").clicked() {
+                                self.rebuild_archive();
+                            }
+                        });
+                    });
+
+                    let query_before = self.archive_query.clone();
+                    ui.horizontal(|ui| {
+                        ComboBox::from_id_salt("archive reason filter")
+                            .selected_text(self.archive_query.reason.map_or("Any reason", ArchiveReason::label))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.archive_query.reason, None, "Any reason");
+                                for reason in ArchiveReason::ALL {
+                                    ui.selectable_value(&mut self.archive_query.reason, Some(reason), reason.label());
+                                }
+                            });
+
+                        ComboBox::from_id_salt("archive kind filter")
+                            .selected_text(match self.archive_query.is_event {
+                                None => "Tasks & events",
+                                Some(false) => "Tasks",
+                                Some(true) => "Events",
+                            })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.archive_query.is_event, None, "Tasks & events");
+                                ui.selectable_value(&mut self.archive_query.is_event, Some(false), "Tasks");
+                                ui.selectable_value(&mut self.archive_query.is_event, Some(true), "Events");
+                            });
+
+                        ComboBox::from_id_salt("archive importance filter")
+                            .selected_text(self.archive_query.importance.and_then(|i| IMPORTANCE.get(i as usize).copied()).unwrap_or("Any importance"))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.archive_query.importance, None, "Any importance");
+                                for (i, importance) in IMPORTANCE.iter().enumerate() {
+                                    ui.selectable_value(&mut self.archive_query.importance, Some(i as u8), *importance);
+                                }
+                            });
+                    });
+                    if self.archive_query != query_before {
+                        self.reload_archive();
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label("From");
+                        ui.add(egui::TextEdit::singleline(&mut self.archive_from_input).hint_text("dd.mm.yyyy").desired_width(90.0));
+                        ui.label("to");
+                        ui.add(egui::TextEdit::singleline(&mut self.archive_to_input).hint_text("dd.mm.yyyy").desired_width(90.0));
+                        if ui.button("Apply").on_hover_text("Filter by archive date; either field may be left empty").clicked() {
+                            self.apply_archive_dates();
+                        }
+                        if ui.button("Reset").on_hover_text("Clear all filters and sort newest first").clicked() {
+                            self.archive_query = tasks::ArchiveQuery::default();
+                            self.archive_from_input.clear();
+                            self.archive_to_input.clear();
+                            self.reload_archive();
+                        }
+                    });
+
+                    egui::CollapsingHeader::new("Statistics")
+                        .id_salt("archive statistics")
+                        .show(ui, |ui| {
+                            if self.archive_stats.is_none() {
+                                self.compute_archive_stats();
+                            }
+                            let Some(stats) = &self.archive_stats else { return };
+
+                            ui.label(format!(
+                                "{} completed, {} deleted, {} expired events, {} merged",
+                                stats.completed, stats.deleted, stats.expired, stats.merged,
+                            ));
+                            ui.label(format!("Completions per week: {:.1}", stats.completions_per_week()));
+                            ui.label(format!(
+                                "Average lead time (created → completed): {}",
+                                stats.average_lead_time.map(utilities::format_duration_short).unwrap_or_else(|| "–".to_string()),
+                            ));
+
+                            // Weekly completions for the most recent 12 weeks, one bar each.
+                            let weeks = &stats.weekly_completions[stats.weekly_completions.len().saturating_sub(12)..];
+                            if !weeks.is_empty() {
+                                let most = weeks.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1);
+                                let (rect, _) = ui.allocate_exact_size(vec2(ui.available_width(), 50.0), egui::Sense::hover());
+                                let slot = rect.width() / 12.0;
+                                for (i, (week, count)) in weeks.iter().enumerate() {
+                                    let x = rect.left() + i as f32 * slot;
+                                    let column = Rect::from_min_max(pos2(x, rect.top()), pos2(x + slot, rect.bottom()));
+                                    let height = rect.height() * (*count as f32 / most as f32);
+                                    let bar = Rect::from_min_max(pos2(x + 2.0, rect.bottom() - height), pos2(x + slot - 2.0, rect.bottom()));
+                                    ui.painter().rect_filled(bar, CornerRadius::same(2), self.theme.accent_color());
+                                    ui.interact(column, ui.id().with(("archive week", i)), egui::Sense::hover())
+                                        .on_hover_text(format!("Week of {}: {} completed", week.format("%d.%m.%Y"), count));
+                                }
+                            }
+                        });
+
                     egui::Frame::default()
-                        .fill(Color32::from_rgba_unmultiplied(40, 44, 52, 240)) // New background color
+                        .fill(self.theme.panel_color())
                         .outer_margin(5)
                         .corner_radius(egui::CornerRadius::same(14))
                         .show(ui, |ui| {
+                                let mut sort_clicked = None;
                                 egui::ScrollArea::vertical().scroll_source(egui::scroll_area::ScrollSource::ALL).show(ui, |ui| {
                                     egui::Grid::new("archive_grid")
                                         .spacing([0.0, 30.0])
@@ -1977,22 +3380,36 @@ impl TaskApp {
                                             let header_font = FontId::new(20.0, FontFamily::Monospace);
                                             let label_color = Color32::LIGHT_GRAY;
 
-                                            ui.label("");
-                                            ui.label(RichText::new("Created").font(header_font.clone()).color(label_color));
-                                            ui.label("");
-                                            ui.label(RichText::new("Name").font(header_font.clone()).color(label_color));
-                                            ui.label("");
-                                            ui.label(RichText::new("Completed").font(header_font).color(label_color));
+                                            for (column, title) in [
+                                                (ArchiveSort::Created, "Created"),
+                                                (ArchiveSort::Name, "Name"),
+                                                (ArchiveSort::Importance, "Imp."),
+                                                (ArchiveSort::Inactivated, "Archived"),
+                                                (ArchiveSort::Reason, "Reason"),
+                                            ] {
+                                                let arrow = match (self.archive_query.sort == column, self.archive_query.descending) {
+                                                    (true, true) => " ▼",
+                                                    (true, false) => " ▲",
+                                                    (false, _) => "",
+                                                };
+                                                ui.label("");
+                                                let header = Label::new(RichText::new(format!("{}{}", title, arrow)).font(header_font.clone()).color(label_color))
+                                                    .sense(egui::Sense::click())
+                                                    .selectable(false);
+                                                if ui.add(header).on_hover_text("Sort by this column").clicked() {
+                                                    sort_clicked = Some(column);
+                                                }
+                                            }
                                             ui.label("");
                                             ui.end_row();
 
-                                            let date_color = Color32::from_rgb(98, 114, 164); // Soft blue
-                                            let name_color = Color32::from_rgba_unmultiplied(255, 255, 255, 180);
+                                            let date_color = self.theme.accent_color();
+                                            let name_color = self.theme.heading_text_color();
                                             let font = FontId::new(18.0, FontFamily::Monospace);
-                                            let font_space = FontId::new(15.0, FontFamily::Name("space".into()));
+                                            let font_space = FontId::new(15.0, FontFamily::Name(theme::DETAIL.into()));
 
                                             if let Some(ref vec) = self.archive {
-                                                for archive in vec {
+                                                for archive in vec.iter().take(self.offset) {
                                                     ui.label("");
                                                     ui.label(RichText::new(archive.created.format("%d.%m.%Y %H.%M").to_string())
                                                         .font(font_space.clone()).color(date_color));
@@ -2000,25 +3417,37 @@ impl TaskApp {
                                                     ui.label(RichText::new(&archive.name)
                                                         .font(font.clone()).color(name_color));
                                                     ui.label("");
+                                                    match archive.importance.and_then(|i| IMPORTANCE.get(i as usize)) {
+                                                        Some(label) => ui.label(RichText::new(archive.importance.unwrap_or(0).to_string())
+                                                            .font(font_space.clone()).color(date_color)).on_hover_text(*label),
+                                                        None => ui.label(RichText::new("–").font(font_space.clone()).color(date_color)),
+                                                    };
+                                                    ui.label("");
                                                     ui.label(RichText::new(archive.inactivated.format("%d.%m.%Y %H.%M").to_string())
                                                         .font(font_space.clone()).color(date_color));
                                                     ui.label("");
+                                                    ui.label(RichText::new(archive.reason.label())
+                                                        .font(font_space.clone()).color(name_color));
+                                                    ui.label("");
                                                     ui.end_row();
                                                 }
                                             }
                                         });
                                     ui.vertical_centered_justified(|ui| {
-                                        if ui.button("Show more").clicked() {
+                                        if (self.offset as u64) < self.archive_total && ui.button("Show more").clicked() {
                                             self.load_more_archives();
                                         }
                                     });
 
                                 });
+                                if let Some(column) = sort_clicked {
+                                    self.sort_archive_by(column);
+                                }
                         });
                 });
         }
 
-        if self.settings_flag && !self.color_picker_flag {
+        if self.settings_flag && !self.color_picker_flag && !self.backup_restore_flag && !self.theme_manager_flag {
             egui::Window::new("Settings")
                 .collapsible(false)
                 .resizable(false)
@@ -2050,7 +3479,7 @@ impl TaskApp {
                                 // the `&mut self` persist call.
                                 let new_background = self.background_options[self.selected_background_index].clone();
 
-                                self.background_image_texture = Some(set_background(ctx, new_background.clone()));
+                                self.background_image_texture = Some(set_background(ctx, &self.images_dir, new_background.clone()));
 
                                 self.persist_config_value("background", new_background);
                             }
@@ -2058,7 +3487,7 @@ impl TaskApp {
                             if ui.button("♲").clicked() {
                                 let available_background_name_to_refresh_into = self.background_options[self.selected_background_index].to_string();
                                 self.persist_config_value("background", available_background_name_to_refresh_into.clone());
-                                self.background_image_texture = Some(set_background(ctx, available_background_name_to_refresh_into));
+                                self.background_image_texture = Some(set_background(ctx, &self.images_dir, available_background_name_to_refresh_into));
                             }
                         });
                         ui.end_row();
@@ -2141,7 +3570,7 @@ impl TaskApp {
                         ui.horizontal_centered(|ui| {
                             let previous_selection = self.three_day_weather;
 
-                            ui.checkbox(&mut self.three_day_weather, "Show weather for three days");
+                            ui.checkbox(&mut self.three_day_weather, "Give the forecast the whole column (hides the notepad)");
 
                             if previous_selection != self.three_day_weather {
                                 self.persist_config_value("three_day_weather", self.three_day_weather);
@@ -2149,6 +3578,187 @@ impl TaskApp {
                         });
                         ui.end_row();
                         ui.end_row();
+                        ui.horizontal_centered(|ui| {
+                            let previous_selection = self.calendar_weather;
+
+                            ui.checkbox(&mut self.calendar_weather, "Show each day's forecast in the calendar");
+
+                            if previous_selection != self.calendar_weather {
+                                self.persist_config_value("calendar_weather", self.calendar_weather);
+                            }
+                        });
+                        ui.end_row();
+                        ui.end_row();
+                        ui.horizontal_centered(|ui| {
+                            let previous_bands = self.night_bands;
+                            let previous_dimming = self.night_dimming;
+
+                            ui.checkbox(&mut self.night_bands, "Shade night hours in the forecast");
+                            ui.checkbox(&mut self.night_dimming, "Dim the screen after sunset");
+
+                            if previous_bands != self.night_bands {
+                                self.persist_config_value("night_bands", self.night_bands);
+                            }
+                            if previous_dimming != self.night_dimming {
+                                self.persist_config_value("night_dimming", self.night_dimming);
+                            }
+                        });
+                        ui.end_row();
+                        ui.end_row();
+                        ui.horizontal_centered(|ui| {
+                            let previous_rules = self.alert_rules;
+                            let defaults = AlertRules::default();
+                            let units = self.units;
+                            let (celsius, mm, ms) = (TemperatureUnit::Celsius, PrecipitationUnit::Millimetres, WindUnit::MetresPerSecond);
+
+                            ui.label("Alert on: ");
+                            alert_threshold_input(ui, "frost below", &mut self.alert_rules.frost_below, defaults.frost_below, units.temperature.label(),
+                                |metric| units.temperature.convert(metric, celsius), |shown| celsius.convert(shown, units.temperature));
+                            ui.add_space(10.0);
+                            alert_threshold_input(ui, "rain over", &mut self.alert_rules.rain_above, defaults.rain_above, units.precipitation.label(),
+                                |metric| units.precipitation.convert(metric, mm), |shown| mm.convert(shown, units.precipitation));
+                            ui.add_space(10.0);
+                            alert_threshold_input(ui, "gusts over", &mut self.alert_rules.gusts_above, defaults.gusts_above, units.wind.label(),
+                                |metric| units.wind.convert(metric, ms), |shown| ms.convert(shown, units.wind));
+                            ui.add_space(10.0);
+                            ui.checkbox(&mut self.alert_rules.thunderstorms, "thunderstorms");
+                            ui.add_space(10.0);
+                            ui.label("within ");
+                            ui.add(egui::DragValue::new(&mut self.alert_rules.horizon_hours).range(1..=MAX_ALERT_HORIZON_HOURS).suffix(" h"));
+
+                            if previous_rules != self.alert_rules {
+                                self.set_alert_rules();
+                            }
+                        });
+                        ui.end_row();
+                        ui.end_row();
+                        ui.horizontal_centered(|ui| {
+                            let previous_selection = self.weather_details_row;
+
+                            ui.checkbox(&mut self.weather_details_row, "Show precipitation and wind in the forecast");
+
+                            if previous_selection != self.weather_details_row {
+                                self.persist_config_value("weather_details_row", self.weather_details_row);
+                            }
+                        });
+                        ui.end_row();
+                        ui.end_row();
+                        ui.horizontal_centered(|ui| {
+                            let previous_units = self.units;
+                            let previous_rounding = self.temperature_rounding;
+
+                            ui.label("Temperature: ");
+                            ComboBox::from_id_salt("temperature_unit")
+                                .selected_text(self.units.temperature.label())
+                                .width(50.0)
+                                .show_ui(ui, |ui| {
+                                    for unit in TemperatureUnit::ALL {
+                                        ui.selectable_value(&mut self.units.temperature, unit, unit.label());
+                                    }
+                                });
+                            ComboBox::from_id_salt("temperature_rounding")
+                                .selected_text(self.temperature_rounding.label())
+                                .show_ui(ui, |ui| {
+                                    for rounding in Rounding::ALL {
+                                        ui.selectable_value(&mut self.temperature_rounding, rounding, rounding.label());
+                                    }
+                                });
+                            ui.add_space(10.0);
+                            ui.label("Wind: ");
+                            ComboBox::from_id_salt("wind_speed_unit")
+                                .selected_text(self.units.wind.label())
+                                .width(60.0)
+                                .show_ui(ui, |ui| {
+                                    for unit in WindUnit::ALL {
+                                        ui.selectable_value(&mut self.units.wind, unit, unit.label());
+                                    }
+                                });
+                            ui.add_space(10.0);
+                            ui.label("Precipitation: ");
+                            ComboBox::from_id_salt("precipitation_unit")
+                                .selected_text(self.units.precipitation.label())
+                                .width(50.0)
+                                .show_ui(ui, |ui| {
+                                    for unit in PrecipitationUnit::ALL {
+                                        ui.selectable_value(&mut self.units.precipitation, unit, unit.label());
+                                    }
+                                });
+
+                            if previous_units != self.units {
+                                self.set_weather_units();
+                            }
+                            if previous_rounding != self.temperature_rounding {
+                                self.persist_config_value("temperature_rounding", self.temperature_rounding.key());
+                            }
+                        });
+                        ui.end_row();
+                        ui.end_row();
+                        ui.horizontal_centered(|ui| {
+                            let previous_window = self.weather_window_hours;
+                            let previous_days = self.forecast_days;
+                            let hours_label = |hours: u32| if hours == 1 { "1 hour".to_string() } else { format!("{hours} hours") };
+
+                            ui.label("Forecast slots: ");
+                            ComboBox::from_id_salt("weather_window_hours")
+                                .selected_text(hours_label(self.weather_window_hours))
+                                .show_ui(ui, |ui| {
+                                    for hours in weather::AGGREGATION_WINDOWS {
+                                        ui.selectable_value(&mut self.weather_window_hours, hours, hours_label(hours));
+                                    }
+                                });
+                            ui.add_space(10.0);
+                            ui.label("Days: ");
+                            ui.add(egui::DragValue::new(&mut self.forecast_days).range(1..=weather::MAX_FORECAST_DAYS));
+
+                            if previous_window != self.weather_window_hours {
+                                self.persist_config_value("weather_window_hours", self.weather_window_hours as i64);
+                            }
+                            if previous_days != self.forecast_days {
+                                self.persist_config_value("forecast_days", self.forecast_days as i64);
+                            }
+                            if (previous_window, previous_days) != (self.weather_window_hours, self.forecast_days) {
+                                self.fix_and_cache_weather_data();
+                            }
+                        });
+                        ui.end_row();
+                        ui.end_row();
+                        ui.horizontal_centered(|ui| {
+                            ui.label("Weather provider: ");
+
+                            let previous_provider = self.weather_provider;
+
+                            ComboBox::from_id_salt("weather_provider")
+                                .selected_text(self.weather_provider.label())
+                                .show_ui(ui, |ui| {
+                                    for kind in WeatherProviderKind::ALL {
+                                        ui.selectable_value(&mut self.weather_provider, kind, kind.label());
+                                    }
+                                });
+
+                            if previous_provider != self.weather_provider {
+                                self.set_weather_provider();
+                            }
+                        });
+                        ui.end_row();
+                        ui.end_row();
+                        ui.horizontal_centered(|ui| {
+                            let previous_refresh = self.weather_refresh;
+
+                            ui.label("Refresh weather every ");
+                            ui.add(egui::DragValue::new(&mut self.weather_refresh.interval_minutes).range(weather::REFRESH_MINUTES).suffix(" min"));
+                            ui.add_space(10.0);
+                            ui.label("On failure, try ");
+                            ui.add(egui::DragValue::new(&mut self.weather_refresh.attempts).range(weather::RETRY_ATTEMPTS));
+                            ui.label(" times, waiting ");
+                            ui.add(egui::DragValue::new(&mut self.weather_refresh.backoff_seconds).range(weather::RETRY_BACKOFF_SECONDS).suffix(" s"))
+                                .on_hover_text("Doubled after each further failure");
+
+                            if previous_refresh != self.weather_refresh {
+                                self.set_weather_refresh();
+                            }
+                        });
+                        ui.end_row();
+                        ui.end_row();
                         ui.horizontal_centered(|ui| {
                             ui.set_max_width(300.0);
                             ui.label("Number of displayed weeks: ");
@@ -2171,7 +3781,36 @@ impl TaskApp {
                         ui.end_row();
                         ui.end_row();
                         ui.horizontal_centered(|ui| {
-                            ui.label("Weather Coordinates: ");
+                            let previous_cues = self.item_cues;
+                            ui.checkbox(&mut self.item_cues, "Mark importance on calendar items")
+                                .on_hover_text("Dots for the importance (one to four) and a ring for events, besides the colour");
+                            if previous_cues != self.item_cues {
+                                self.persist_config_value("item_cues", self.item_cues);
+                            }
+                        });
+                        ui.end_row();
+                        ui.end_row();
+                        ui.horizontal_centered(|ui| {
+                            ui.set_max_width(300.0);
+                            ui.label("Archive past events after (days): ");
+                            if ui.text_edit_singleline(&mut self.archive_events_after_input)
+                                .on_hover_text("Events are moved to the archive this many days after they happen. 0 keeps them.")
+                                .lost_focus()
+                            {
+                                self.set_archive_events_after();
+                            }
+                        });
+                        ui.end_row();
+                        ui.end_row();
+                        ui.horizontal_centered(|ui| {
+                            ui.label("Weather locations: ");
+                            self.show_location_chips(ui);
+                        });
+                        ui.end_row();
+                        ui.end_row();
+                        ui.horizontal_centered(|ui| {
+                            ui.label("New location: ");
+                            ui.add(egui::TextEdit::singleline(&mut self.location_name_input).hint_text("Name").desired_width(120.0));
 
                             let y_slider = egui::DragValue::new(&mut self.coordinates[0])
                                 .prefix("Latitude (Y): ")
@@ -2193,8 +3832,8 @@ impl TaskApp {
                             if ui.button("Pick coordinates with map").clicked() {
                                 self.coordinates_map_flag = true;
                             }
-                            if ui.button("Apply coordinates").clicked() {
-                                self.set_weather_coordinates();
+                            if ui.button("Add location").clicked() {
+                                self.add_weather_location();
                             }
                         });
                         ui.end_row();
@@ -2205,6 +3844,18 @@ impl TaskApp {
                             if button.clicked() {
                                 self.color_picker_flag = true;
                             }
+
+                            let button = ui.add(Button::new("Themes").min_size(Vec2::new(50.0, 30.0)));
+
+                            if button.clicked() {
+                                self.theme_manager_flag = true;
+                            }
+
+                            let button = ui.add(Button::new("Restore from backup").min_size(Vec2::new(50.0, 30.0)));
+
+                            if button.clicked() {
+                                self.open_backup_dialog();
+                            }
                         });
                         ui.end_row();
                         ui.end_row();
@@ -2225,6 +3876,114 @@ impl TaskApp {
                 });
         }
 
+        if self.backup_restore_flag {
+            egui::Window::new("Restore from backup")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.set_min_width(560.0);
+                    ui.label(RichText::new("Snapshots are taken daily and before data migrations. Kept: 7 daily, 4 weekly and 12 monthly.").weak());
+                    ui.add_space(5.0);
+
+                    if self.backup_snapshots.is_empty() {
+                        ui.label("No backups yet.");
+                    }
+
+                    let previous_selection = self.backup_selected;
+                    egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                        Grid::new("backup_grid").striped(true).spacing([18.0, 6.0]).show(ui, |ui| {
+                            for title in ["Taken", "Kind", "Active", "Archived", "Schemes", "Size"] {
+                                ui.label(RichText::new(title).strong());
+                            }
+                            ui.end_row();
+
+                            for (i, snapshot) in self.backup_snapshots.iter().enumerate() {
+                                let taken = snapshot.created.format("%d.%m.%Y %H.%M").to_string();
+                                if ui.selectable_label(self.backup_selected == Some(i), taken).clicked() {
+                                    self.backup_selected = Some(i);
+                                }
+                                ui.label(snapshot.kind.label());
+                                ui.label(snapshot.counts.active.to_string());
+                                ui.label(snapshot.counts.archived.to_string());
+                                ui.label(snapshot.counts.colorschemes.to_string());
+                                ui.label(format!("{:.1} KB", snapshot.size as f64 / 1024.0));
+                                ui.end_row();
+                            }
+                        });
+                    });
+                    if self.backup_selected != previous_selection {
+                        self.backup_diff = None;
+                        self.confirm_restore_flag = false;
+                    }
+
+                    if let Some(diff) = &self.backup_diff {
+                        ui.separator();
+                        if diff.is_empty() {
+                            ui.label("This backup matches the current data.");
+                        } else {
+                            // Long lists are cut short; the counts still tell the whole story.
+                            let names = |names: &[String]| {
+                                let mut text = names.iter().take(8).cloned().collect::<Vec<_>>().join(", ");
+                                if names.len() > 8 {
+                                    text.push_str(&format!(" and {} more", names.len() - 8));
+                                }
+                                text
+                            };
+                            for (title, list) in [
+                                ("Restoring brings back", &diff.active_only_in_backup),
+                                ("Restoring removes", &diff.active_only_now),
+                                ("Edited since the backup", &diff.active_changed),
+                            ] {
+                                if !list.is_empty() {
+                                    ui.label(format!("{} ({}): {}", title, list.len(), names(list)));
+                                }
+                            }
+                            if diff.archived_in_backup != diff.archived_now {
+                                ui.label(format!("Archive: {} items in the backup, {} now", diff.archived_in_backup, diff.archived_now));
+                            }
+                            if !diff.files_changed.is_empty() {
+                                ui.label(format!("Also differs: {}", diff.files_changed.join(", ")));
+                            }
+                        }
+                    }
+
+                    ui.separator();
+                    if self.confirm_restore_flag {
+                        ui.label("Replace the current data with this backup? The current state is backed up first, then TaskDeck restarts.");
+                        ui.horizontal(|ui| {
+                            if ui.button("Yes").clicked() {
+                                self.restore_selected_backup();
+                            }
+                            if ui.button("No").clicked() {
+                                self.confirm_restore_flag = false;
+                            }
+                        });
+                    } else {
+                        ui.horizontal(|ui| {
+                            let selected = self.backup_selected.is_some();
+                            if ui.add_enabled(selected, Button::new("Compare with current")).clicked() {
+                                self.compare_selected_backup();
+                            }
+                            if ui.add_enabled(selected, Button::new("Restore…")).clicked() {
+                                self.confirm_restore_flag = true;
+                            }
+                            if ui.button("Back up now").clicked() {
+                                self.back_up_now();
+                            }
+
+                            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                if ui.button("Close").clicked() {
+                                    self.backup_restore_flag = false;
+                                    self.backup_snapshots.clear();
+                                    self.backup_diff = None;
+                                }
+                            });
+                        });
+                    }
+                });
+        }
+
         if self.coordinates_map_flag {
             egui::Window::new("Weather Coordinates Picker")
                 .collapsible(false)
@@ -2362,6 +4121,35 @@ impl TaskApp {
                             );
                         }
 
+                        // ---------------- SAVED LOCATIONS ----------------
+                        for location in &self.locations {
+                            let world_uv = egui::pos2(
+                                (location.coordinates[1] + 180.0) / 360.0,
+                                1.0 - ((location.coordinates[0] + 90.0) / 180.0),
+                            );
+
+                            let local_uv = egui::pos2(
+                                (world_uv.x - uv_min.x) / uv_size.x,
+                                (world_uv.y - uv_min.y) / uv_size.y,
+                            );
+
+                            if (0.0..=1.0).contains(&local_uv.x) && (0.0..=1.0).contains(&local_uv.y) {
+                                let pos = egui::pos2(
+                                    rect.min.x + local_uv.x * rect.width(),
+                                    rect.min.y + local_uv.y * rect.height(),
+                                );
+
+                                painter.circle_filled(pos, 5.0, egui::Color32::from_rgb(70, 140, 255));
+                                painter.text(
+                                    pos + egui::vec2(8.0, -8.0),
+                                    egui::Align2::LEFT_BOTTOM,
+                                    &location.name,
+                                    egui::TextStyle::Body.resolve(ui.style()),
+                                    egui::Color32::WHITE,
+                                );
+                            }
+                        }
+
                         // ---------------- CITY MARKERS ----------------
                         for city in weather::CITIES {
                             let world_uv = egui::pos2(
@@ -2400,6 +4188,46 @@ impl TaskApp {
                         }
                     });
 
+                    // -------------------------------------------------
+                    // PLACE SEARCH (over the map's top-left corner)
+                    // -------------------------------------------------
+                    let search_rect = egui::Rect::from_min_size(
+                        map_rect.min + egui::vec2(10.0, 10.0),
+                        egui::vec2(360.0, map_rect.height() - 20.0),
+                    );
+
+                    ui.scope_builder(egui::UiBuilder::new().max_rect(search_rect), |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            ui.set_width(340.0);
+                            let search = ui.add(
+                                egui::TextEdit::singleline(&mut self.place_search_input)
+                                    .hint_text("Search for a place")
+                                    .desired_width(f32::INFINITY),
+                            );
+                            if search.changed() {
+                                self.place_search_results = gazetteer::search(&self.place_search_input, gazetteer::SEARCH_LIMIT);
+                            }
+
+                            let mut picked = None;
+                            if search.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                                picked = self.place_search_results.first().copied();
+                            }
+                            for &place in &self.place_search_results {
+                                let row = format!("{} — {} · {}", place.name, place.country, place.timezone);
+                                if ui.selectable_label(false, row).clicked() {
+                                    picked = Some(place);
+                                }
+                            }
+                            if !self.place_search_input.trim().is_empty() && self.place_search_results.is_empty() {
+                                ui.weak("No matching places");
+                            }
+
+                            if let Some(place) = picked {
+                                self.go_to_place(place);
+                            }
+                        });
+                    });
+
                     // -------------------------------------------------
                     // FOOTER
                     // -------------------------------------------------
@@ -2411,6 +4239,8 @@ impl TaskApp {
                             ui.label(format!("Lon: {:.2}", self.coordinates[1]));
                             ui.separator();
                             ui.label(format!("Zoom: {:.2}x", self.map_zoom));
+                            ui.separator();
+                            self.show_location_chips(ui);
 
                             ui.with_layout(
                                 egui::Layout::right_to_left(egui::Align::Center),
@@ -2419,6 +4249,10 @@ impl TaskApp {
                                     if ui.button("OK").clicked() {
                                         self.coordinates_map_flag = false;
                                     }
+                                    if ui.button("Add location").clicked() {
+                                        self.add_weather_location();
+                                    }
+                                    ui.add(egui::TextEdit::singleline(&mut self.location_name_input).hint_text("Name").desired_width(140.0));
                                 },
                             );
                         });
@@ -2510,15 +4344,60 @@ impl TaskApp {
                             ui.separator();
                             ui.add_space(5.0);
 
+                            let previous_options = self.palette_options;
+                            ui.horizontal(|ui| {
+                                ui.label("Algorithm");
+                                ComboBox::from_id_salt("palette_algorithm")
+                                    .selected_text(self.palette_options.algorithm.label())
+                                    .show_ui(ui, |ui| {
+                                        for algorithm in PaletteAlgorithm::ALL {
+                                            ui.selectable_value(&mut self.palette_options.algorithm, algorithm, algorithm.label());
+                                        }
+                                    });
+                            });
+                            ui.checkbox(&mut self.palette_options.saliency, "Weight by saliency")
+                                .on_hover_text("Colours that stand out from the rest of the image count for more");
+                            ui.checkbox(&mut self.palette_options.exclude_greys, "Leave out greys");
+                            if self.palette_previews.is_some() && self.palette_options != previous_options {
+                                self.preview_palettes(ctx);
+                            }
+
                             let generate_button = ui.add(Button::new("Generate new colorscheme from current background").min_size(Vec2::new(50.0, 30.0)));
                             if generate_button.clicked() {
-                                self.try_to_generate_colorscheme();
+                                self.try_to_generate_colorscheme(ctx);
+                            }
+                            let preview_button = ui.add(Button::new("Preview all algorithms").min_size(Vec2::new(50.0, 30.0)));
+                            if preview_button.clicked() {
+                                self.preview_palettes(ctx);
                             }
 
                             ui.add_space(5.0);
                             ui.separator();
                             ui.add_space(5.0);
 
+                            ui.label("Export this colorscheme as");
+                            ui.horizontal(|ui| {
+                                for format in SchemeFormat::ALL {
+                                    if ui.button(format.label()).on_hover_text(format!(".{} file in the data folder", format.extension())).clicked() {
+                                        self.export_current_colorscheme(format);
+                                    }
+                                }
+                            });
+                            ui.add_space(5.0);
+                            ui.label("Import a colorscheme file");
+                            ui.horizontal(|ui| {
+                                ui.add(egui::TextEdit::singleline(&mut self.colorscheme_import_path).hint_text("path to .json/.toml/.gpl/.ase").desired_width(220.0));
+                                if ui.add_enabled(!self.colorscheme_import_path.trim().is_empty(), Button::new("Import")).clicked() {
+                                    let path = PathBuf::from(self.colorscheme_import_path.trim());
+                                    self.import_colorscheme(&path, None);
+                                }
+                            });
+                            ui.label(RichText::new("…or drop the file onto the window.").color(self.theme.weak_text_color()));
+
+                            ui.add_space(5.0);
+                            ui.separator();
+                            ui.add_space(5.0);
+
                             let ok_button = ui.add(Button::new("OK").min_size(Vec2::new(50.0, 30.0)));
 
 
@@ -2534,9 +4413,62 @@ impl TaskApp {
                 });
         }
 
+        if let Some(previews) = &self.palette_previews {
+            let mut keep = None;
+            let mut close = false;
+            egui::Window::new("Palette preview")
+                .collapsible(false)
+                .resizable(false)
+                .default_pos(pos2(580.0, 250.0))
+                .show(ctx, |ui| {
+                    ui.label(RichText::new("Each algorithm on the current background, over the area the calendar covers").weak().small());
+                    ui.add_space(5.0);
+                    egui::Grid::new("palette_previews").spacing(vec2(8.0, 6.0)).show(ui, |ui| {
+                        for (algorithm, scheme) in previews {
+                            ui.label(algorithm.label());
+                            ui.horizontal(|ui| {
+                                for [r, g, b, a] in scheme.colors {
+                                    let (rect, _) = ui.allocate_exact_size(Vec2::new(28.0, 28.0), egui::Sense::hover());
+                                    // Drawn as on the calendar: over the tinted background.
+                                    ui.painter().rect_filled(rect, 3.0, ui.visuals().panel_fill);
+                                    if let Some(background) = &self.background_image_texture {
+                                        let [left, top, right, bottom] = self.calendar_region;
+                                        let tint = (self.background_image_tint_percent as f32 / 100.0).clamp(0.0, 1.0);
+                                        ui.painter().image(background.id(), rect, egui::Rect::from_min_max(pos2(left, top), pos2(right, bottom)), Color32::WHITE.gamma_multiply(tint));
+                                    }
+                                    ui.painter().rect_filled(rect, 3.0, Color32::from_rgba_unmultiplied(r, g, b, a));
+                                }
+                            });
+                            if ui.button("Keep").clicked() {
+                                keep = Some(scheme.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+                    ui.add_space(5.0);
+                    if ui.button("Close").clicked() {
+                        close = true;
+                    }
+                });
+            if let Some(scheme) = keep {
+                self.keep_previewed_colorscheme(scheme);
+            } else if close {
+                self.palette_previews = None;
+            }
+        }
+
         if self.edit_colorscheme_flag && !self.rename_colorscheme_flag && !self.user_wants_to_delete_colorscheme_flag {
             let mut should_save = false;
             let mut should_cancel = false;
+            self.refresh_backdrop_samples(ctx);
+            let goal = self.contrast_goal(ctx);
+            let backdrop = self.backdrop_samples.as_ref().map(|(_, _, samples)| samples.clone()).unwrap_or_default();
+            // The colour-vision rows show the fills over the backdrop's average.
+            let under = {
+                let count = backdrop.len().max(1) as u32;
+                let sum = backdrop.iter().fold([0u32; 3], |sum, c| [sum[0] + c[0] as u32, sum[1] + c[1] as u32, sum[2] + c[2] as u32]);
+                sum.map(|channel| (channel / count) as u8)
+            };
             
             if let Some(scheme) = &mut self.colorscheme_being_edited {
                 egui::Window::new("Editing colorscheme:")
@@ -2546,7 +4478,7 @@ impl TaskApp {
                     .default_pos(pos2(580.0, 250.0))
                     .show(ctx, |ui| {
                         ui.vertical_centered(|ui| {
-                            ui.add(Label::new(RichText::new(scheme.name.clone()).color(Color32::from_white_alpha(120))).wrap().selectable(false));
+                            ui.add(Label::new(RichText::new(scheme.name.clone()).color(self.theme.weak_text_color())).wrap().selectable(false));
 
                             ui.add_space(5.0);
 
@@ -2606,7 +4538,75 @@ impl TaskApp {
                                 ui.add_space(5.0);
                             });
 
-                            ui.add_space(30.0);
+                            ui.add_space(4.0);
+
+                            ui.horizontal(|ui| {
+                                ui.add_space(5.0);
+                                for fill in scheme.colors {
+                                    contrast_badge(ui, fill, &backdrop, &goal);
+                                    ui.add_space(8.0);
+                                }
+                            });
+
+                            ui.add_space(6.0);
+
+                            let adjust_button = ui.add(Button::new("Adjust lightness for contrast").small())
+                                .on_hover_text("Lightens or darkens each colour just enough for the calendar text to stay readable over the background");
+                            if adjust_button.clicked() {
+                                scheme.colors = scheme.colors.map(|fill| color::adjust_for_contrast(fill, &backdrop, &goal));
+                            }
+
+                            ui.add_space(10.0);
+                            ui.separator();
+
+                            // Each fill as drawn, as seen with each deficiency.
+                            let mut look_alike = Vec::new();
+                            for vision in ColorVision::ALL {
+                                ui.label(RichText::new(vision.label()).small());
+                                ui.horizontal(|ui| {
+                                    ui.add_space(5.0);
+                                    for (slot, fill) in scheme.colors.iter().enumerate() {
+                                        let [r, g, b] = color::simulate_fill(*fill, vision, under);
+                                        let (rect, response) = ui.allocate_exact_size(Vec2::new(36.0, 14.0), egui::Sense::hover());
+                                        ui.painter().rect_filled(rect, 3.0, Color32::from_rgb(r, g, b));
+                                        response.on_hover_text(color::SLOT_NAMES[slot]);
+                                        ui.add_space(8.0);
+                                    }
+                                });
+                                for (a, b, _) in color::confusable_slots(&scheme.colors, Some(vision), under) {
+                                    look_alike.push(format!("{:?}: {} and {} look alike", vision, color::SLOT_NAMES[a], color::SLOT_NAMES[b]));
+                                }
+                            }
+                            if look_alike.is_empty() {
+                                ui.label(RichText::new("All six stay apart for each").weak().small());
+                            } else {
+                                for line in look_alike {
+                                    ui.label(RichText::new(line).color(ALERT_COLOR).small());
+                                }
+                            }
+
+                            ui.add_space(4.0);
+                            ui.horizontal(|ui| {
+                                ui.add_space(5.0);
+                                ui.label(RichText::new("Calendar as seen with").small());
+                                ComboBox::from_id_salt("simulated_vision")
+                                    .selected_text(self.simulated_vision.map_or("normal vision", |vision| vision.label()))
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(&mut self.simulated_vision, None, "normal vision");
+                                        for vision in ColorVision::ALL {
+                                            ui.selectable_value(&mut self.simulated_vision, Some(vision), vision.label());
+                                        }
+                                    })
+                                    .response
+                                    .on_hover_text("Only the item colours are simulated, while this editor is open");
+                            });
+                            let safe_button = ui.add(Button::new("Make colour-blind safe").small())
+                                .on_hover_text("Replaces each colour with the nearest from a palette that stays apart with any of the three");
+                            if safe_button.clicked() {
+                                scheme.colors = color::color_blind_safe(scheme.colors);
+                            }
+
+                            ui.add_space(14.0);
 
                             ui.horizontal(|ui| {
                                 ui.add_space(10.0);
@@ -2629,17 +4629,162 @@ impl TaskApp {
                         });
                     });
 
-                    self.active_colorscheme = scheme.colors.map(|c| Color32::from_rgba_unmultiplied(c[0], c[1], c[2], c[3]));
+                    let vision = self.simulated_vision;
+                    self.active_colorscheme = scheme.colors.map(|[r, g, b, a]| {
+                        let [r, g, b] = vision.map_or([r, g, b], |vision| vision.simulate([r, g, b]));
+                        Color32::from_rgba_unmultiplied(r, g, b, a)
+                    });
             }
             
             if should_save {
                 self.save_colorscheme_edits();
                 self.set_colorscheme();
                 self.edit_colorscheme_flag = false;
+                self.simulated_vision = None;
             }
             if should_cancel {
                 self.set_colorscheme();
                 self.edit_colorscheme_flag = false;
+                self.simulated_vision = None;
+            }
+        }
+
+        if self.theme_manager_flag {
+            let editable = self.currently_selected_theme_is_user_configurable();
+            let editing = self.theme_being_edited.is_some();
+            let mut selected = self.selected_theme_id;
+            let (mut duplicate, mut edit, mut save, mut cancel, mut delete, mut close) = (false, false, false, false, false, false);
+
+            egui::Window::new("Themes")
+                .collapsible(false)
+                .resizable(false)
+                .default_pos(pos2(580.0, 250.0))
+                .show(ctx, |ui| {
+                    ui.horizontal_top(|ui| {
+                        ui.group(|ui| {
+                            ui.set_min_width(220.0);
+                            ui.vertical(|ui| {
+                                let mut ids: Vec<u32> = self.themes.keys().copied().collect();
+                                ids.sort_unstable();
+                                ui.add_enabled_ui(!editing, |ui| {
+                                    for id in ids {
+                                        ui.selectable_value(&mut selected, id, &self.themes[&id].name);
+                                    }
+                                });
+
+                                ui.add_space(5.0);
+                                ui.separator();
+                                ui.add_space(5.0);
+
+                                if self.user_wants_to_delete_theme_flag {
+                                    ui.label(format!("Delete \"{}\"?", self.themes.get(&self.selected_theme_id).map_or("", |theme| theme.name.as_str())));
+                                    ui.horizontal(|ui| {
+                                        delete = ui.button("Delete").clicked();
+                                        if ui.button("Keep").clicked() {
+                                            self.user_wants_to_delete_theme_flag = false;
+                                        }
+                                    });
+                                } else if !editing {
+                                    duplicate = ui.add(Button::new("Duplicate theme").min_size(Vec2::new(50.0, 30.0))).clicked();
+                                    if editable {
+                                        edit = ui.add(Button::new("Edit theme").min_size(Vec2::new(50.0, 30.0))).clicked();
+                                        if ui.add(Button::new("Delete theme").min_size(Vec2::new(50.0, 30.0))).clicked() {
+                                            self.user_wants_to_delete_theme_flag = true;
+                                        }
+                                    } else {
+                                        ui.label(RichText::new("Duplicate a built-in theme to change it").weak().small());
+                                    }
+                                }
+                            });
+                        });
+
+                        if let Some(theme) = &mut self.theme_being_edited {
+                            ui.vertical(|ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label("Name");
+                                    ui.text_edit_singleline(&mut theme.name);
+                                });
+                                ui.checkbox(&mut theme.light, "Light base")
+                                    .on_hover_text("Start from egui's light widgets, for bright rooms");
+
+                                ui.add_space(5.0);
+
+                                Grid::new("theme_colors").num_columns(2).show(ui, |ui| {
+                                    for (label, color) in [
+                                        ("Text", &mut theme.text),
+                                        ("Headings", &mut theme.heading_text),
+                                        ("Secondary text", &mut theme.weak_text),
+                                        ("Accent", &mut theme.accent),
+                                        ("Frames", &mut theme.stroke),
+                                        ("Panels", &mut theme.panel),
+                                        ("Today", &mut theme.today),
+                                        ("Item names", &mut theme.item_text),
+                                    ] {
+                                        ui.label(label);
+                                        ui.color_edit_button_srgba_unmultiplied(color);
+                                        ui.end_row();
+                                    }
+                                });
+
+                                ui.add_space(5.0);
+
+                                Grid::new("theme_fonts").num_columns(2).show(ui, |ui| {
+                                    for (label, font) in [
+                                        ("Interface", &mut theme.fonts.interface),
+                                        ("Day numbers", &mut theme.fonts.numbers),
+                                        ("Calendar items", &mut theme.fonts.detail),
+                                        ("Task list", &mut theme.fonts.tasks),
+                                        ("Day popup", &mut theme.fonts.list),
+                                    ] {
+                                        ui.label(label);
+                                        ComboBox::from_id_salt(label)
+                                            .selected_text(font.label())
+                                            .show_ui(ui, |ui| {
+                                                for choice in EmbeddedFont::ALL {
+                                                    ui.selectable_value(font, choice, choice.label());
+                                                }
+                                            });
+                                        ui.end_row();
+                                    }
+                                });
+
+                                ui.add_space(10.0);
+
+                                ui.horizontal(|ui| {
+                                    save = ui.add(Button::new("Save").min_size(Vec2::new(50.0, 30.0))).clicked();
+                                    cancel = ui.add(Button::new("Cancel").min_size(Vec2::new(50.0, 30.0))).clicked();
+                                });
+                            });
+                        }
+                    });
+
+                    ui.add_space(5.0);
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        close = ui.add(Button::new("Close").min_size(Vec2::new(50.0, 30.0))).clicked();
+                    });
+                });
+
+            if selected != self.selected_theme_id {
+                self.select_theme(selected);
+            }
+            if duplicate {
+                self.duplicate_current_theme();
+            }
+            if edit {
+                self.theme_being_edited = self.themes.get(&self.selected_theme_id).cloned();
+            }
+            if save {
+                self.save_theme_edits();
+            }
+            if delete {
+                self.delete_current_theme();
+            }
+            if cancel || close {
+                self.theme_being_edited = None;
+            }
+            if close {
+                self.theme_manager_flag = false;
+                self.user_wants_to_delete_theme_flag = false;
             }
         }
 
@@ -2694,6 +4839,28 @@ impl TaskApp {
                 });
         }
 
+        if self.notice_flag {
+            egui::Window::new("Notice")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .min_size(Vec2::new(400.0, 200.0))
+                .show(ctx, |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.add_space(5.0);
+                        ui.colored_label(self.theme.heading_text_color(), &self.notice_text);
+
+                        ui.add_space(15.0);
+
+                        let button = ui.add(Button::new("Ok").min_size(Vec2::new(50.0, 30.0)));
+
+                        if button.clicked() {
+                            self.notice_flag = false;
+                        }
+                    });
+                });
+        }
+
         //this should be displayed last such that the error window is always on top
         if self.error_flag {
             egui::Window::new("error window")
@@ -2704,7 +4871,7 @@ impl TaskApp {
                 .show(ctx, |ui| {
                     ui.vertical_centered(|ui| {
                         ui.add_space(5.0);
-                        ui.colored_label(Color32::from_white_alpha(180), &self.error_text);
+                        ui.colored_label(self.theme.heading_text_color(), &self.error_text);
 
                         ui.add_space(15.0);
 
@@ -2723,83 +4890,6 @@ impl TaskApp {
     }
 }
 
-pub fn set_styles(ctx: &egui::Context) {
-    let mut style = (*ctx.global_style()).clone();
-    style.text_styles = [
-        (egui::TextStyle::Heading, egui::FontId::new(30.0, egui::FontFamily::Monospace)),
-        (egui::TextStyle::Body, egui::FontId::new(18.0, egui::FontFamily::Monospace)),
-        (egui::TextStyle::Button, egui::FontId::new(22.0, egui::FontFamily::Monospace)),
-        (egui::TextStyle::Small, egui::FontId::new(11.0, egui::FontFamily::Monospace)),
-        (egui::TextStyle::Monospace, egui::FontId::new(11.0, egui::FontFamily::Monospace)),
-    ]
-    .into();
-    ctx.set_global_style(style);
-}
-
-pub fn load_fonts(ctx: &egui::Context) {
-    let mut fonts = FontDefinitions::default();
-
-    fonts.font_data.insert(
-        "fixedsys".to_owned(),
-        Arc::new(FontData::from_static(include_bytes!(r#"../fonts/FSEX300.ttf"#))),
-    );
-    fonts.font_data.insert(
-        "dejavu".to_owned(),
-        Arc::new(FontData::from_static(include_bytes!(r#"../fonts/DejaVuSans.ttf"#))),
-    );
-        fonts.font_data.insert(
-        "anton".to_owned(),
-        Arc::new(FontData::from_static(include_bytes!(r#"../fonts/Anton-Regular.ttf"#))),
-    );
-        fonts.font_data.insert(
-        "space".to_owned(),
-        Arc::new(FontData::from_static(include_bytes!(r#"../fonts/SpaceMono-Regular.ttf"#))),
-    );
-        fonts.font_data.insert(
-        "spaceb".to_owned(),
-        Arc::new(FontData::from_static(include_bytes!(r#"../fonts/LexendGiga-Light.ttf"#))),
-    );
-        fonts.font_data.insert(
-        "bungee".to_owned(),
-        Arc::new(FontData::from_static(include_bytes!(r#"../fonts/FacultyGlyphic-Regular.ttf"#))),
-    );    
-
-    fonts.families.get_mut(&egui::FontFamily::Monospace).unwrap().clear();
-
-    fonts
-        .families
-        .get_mut(&egui::FontFamily::Monospace)
-        .unwrap()
-        .push("fixedsys".to_owned());
-    fonts
-        .families
-        .get_mut(&egui::FontFamily::Monospace)
-        .unwrap()
-        .push("dejavu".to_owned());
-    fonts
-        .families
-        .get_mut(&egui::FontFamily::Monospace)
-        .unwrap()
-        .push("space".to_owned());
-    fonts
-        .families
-        .get_mut(&egui::FontFamily::Proportional)
-        .unwrap()
-        .push("spaceb".to_owned());
-
-    fonts.families.insert(FontFamily::Name("anton".into()), vec!["anton".to_owned()]);
-
-    fonts.families.insert(FontFamily::Name("dejavu".into()), vec!["dejavu".to_owned()]);
-
-    fonts.families.insert(FontFamily::Name("space".into()), vec!["space".to_owned()]);
-
-    fonts.families.insert(FontFamily::Name("spaceb".into()), vec!["spaceb".to_owned()]);
-
-    fonts.families.insert(FontFamily::Name("bungee".into()), vec!["bungee".to_owned()]);
-
-    ctx.set_fonts(fonts);
-}
-
 fn attempt_background(path: PathBuf) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, Box<dyn Error>> {
     let image_bytes = fs::read(&path)?;
     let image = image::load_from_memory(&image_bytes)?
@@ -2808,10 +4898,10 @@ fn attempt_background(path: PathBuf) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, B
     Ok(image)
 }
 
-fn set_background(ctx: &Context, name: String) -> TextureHandle {
+fn set_background(ctx: &Context, images_dir: &Path, name: String) -> TextureHandle {
     // Fall back to the bundled placeholder if the name is unusable or the file
-    // can't be loaded. `safe_image_path` keeps this confined to `images/`.
-    let image = utilities::safe_image_path(&name)
+    // can't be loaded. `safe_image_path` keeps this confined to `images_dir`.
+    let image = utilities::safe_image_path(images_dir, &name)
         .and_then(|path| attempt_background(path).ok())
         .unwrap_or_else(|| {
             image::load_from_memory(include_bytes!("../noback.png"))
@@ -2826,6 +4916,28 @@ fn set_background(ctx: &Context, name: String) -> TextureHandle {
     ctx.load_texture("background", texture, Default::default())
 }
 
+/// WCAG verdict for the text on one fill: AA, AA for large text only, or a
+/// fail. The tooltip has the ratio of each text colour.
+fn contrast_badge(ui: &mut egui::Ui, fill: [u8; 4], backdrop: &[[u8; 3]], goal: &ContrastGoal) {
+    let worst = color::worst_contrast(fill, backdrop, goal);
+    let (text, badge_color) = if worst >= color::WCAG_AA {
+        ("pass", Color32::from_rgb(110, 190, 110))
+    } else if worst >= color::WCAG_AA_LARGE {
+        ("large", ALERT_COLOR)
+    } else {
+        ("fail", Color32::from_rgb(225, 85, 75))
+    };
+
+    let (rect, response) = ui.allocate_exact_size(Vec2::new(36.0, 16.0), egui::Sense::hover());
+    ui.painter().rect_stroke(rect, 3.0, egui::Stroke::new(1.0, badge_color), egui::StrokeKind::Inside);
+    ui.painter().text(rect.center(), egui::Align2::CENTER_CENTER, text, egui::FontId::proportional(9.0), badge_color);
+
+    let details: Vec<String> = goal.texts.iter()
+        .map(|(what, text)| format!("{what}: {:.1}:1", color::fill_contrast(fill, *text, backdrop)))
+        .collect();
+    response.on_hover_text(format!("Contrast over the background (AA needs {}:1, large text {}:1)\n{}", color::WCAG_AA, color::WCAG_AA_LARGE, details.join("\n")));
+}
+
 fn set_world_map(ctx: &Context) -> TextureHandle {
     let bytes = image::load_from_memory(include_bytes!("../1920px-Blue_Marble_2002.png")).expect("Did not get access to fallback background").to_rgba8();
 
@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+
+/// Display units for the forecast. Each `key()` is both the `userconfig.toml`
+/// value and the Open-Meteo query value, so Open-Meteo can be asked for the
+/// data in these units directly; other providers' metric data is converted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Units {
+    pub temperature: TemperatureUnit,
+    pub wind: WindUnit,
+    pub precipitation: PrecipitationUnit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindUnit {
+    #[default]
+    MetresPerSecond,
+    KilometresPerHour,
+    MilesPerHour,
+    Knots,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PrecipitationUnit {
+    #[default]
+    Millimetres,
+    Inches,
+}
+
+/// How forecast temperatures are rounded for display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rounding {
+    /// Half away from zero: 2.5 → 3, -2.5 → -3.
+    #[default]
+    Nearest,
+    /// Towards minus infinity: 2.9 → 2.
+    Down,
+    /// Towards plus infinity: 2.1 → 3.
+    Up,
+    /// One decimal place.
+    Tenths,
+}
+
+impl TemperatureUnit {
+    pub const ALL: [TemperatureUnit; 2] = [TemperatureUnit::Celsius, TemperatureUnit::Fahrenheit];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "celsius",
+            TemperatureUnit::Fahrenheit => "fahrenheit",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<TemperatureUnit> {
+        TemperatureUnit::ALL.into_iter().find(|unit| unit.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        }
+    }
+
+    fn celsius_to(self, celsius: f64) -> f64 {
+        match self {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    fn to_celsius(self, value: f64) -> f64 {
+        match self {
+            TemperatureUnit::Celsius => value,
+            TemperatureUnit::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+        }
+    }
+
+    /// `value`, given in `from`, in this unit.
+    pub fn convert(self, value: f64, from: TemperatureUnit) -> f64 {
+        if self == from { value } else { self.celsius_to(from.to_celsius(value)) }
+    }
+}
+
+impl WindUnit {
+    pub const ALL: [WindUnit; 4] = [WindUnit::MetresPerSecond, WindUnit::KilometresPerHour, WindUnit::MilesPerHour, WindUnit::Knots];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            WindUnit::MetresPerSecond => "ms",
+            WindUnit::KilometresPerHour => "kmh",
+            WindUnit::MilesPerHour => "mph",
+            WindUnit::Knots => "kn",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<WindUnit> {
+        WindUnit::ALL.into_iter().find(|unit| unit.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WindUnit::MetresPerSecond => "m/s",
+            WindUnit::KilometresPerHour => "km/h",
+            WindUnit::MilesPerHour => "mph",
+            WindUnit::Knots => "kn",
+        }
+    }
+
+    /// How many of this unit make one metre per second.
+    fn per_metre_per_second(self) -> f64 {
+        match self {
+            WindUnit::MetresPerSecond => 1.0,
+            WindUnit::KilometresPerHour => 3.6,
+            WindUnit::MilesPerHour => 3600.0 / 1609.344,
+            WindUnit::Knots => 3600.0 / 1852.0,
+        }
+    }
+
+    /// `value`, given in `from`, in this unit.
+    pub fn convert(self, value: f64, from: WindUnit) -> f64 {
+        if self == from { value } else { value / from.per_metre_per_second() * self.per_metre_per_second() }
+    }
+}
+
+impl PrecipitationUnit {
+    pub const ALL: [PrecipitationUnit; 2] = [PrecipitationUnit::Millimetres, PrecipitationUnit::Inches];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            PrecipitationUnit::Millimetres => "mm",
+            PrecipitationUnit::Inches => "inch",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<PrecipitationUnit> {
+        PrecipitationUnit::ALL.into_iter().find(|unit| unit.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PrecipitationUnit::Millimetres => "mm",
+            PrecipitationUnit::Inches => "in",
+        }
+    }
+
+    fn per_millimetre(self) -> f64 {
+        match self {
+            PrecipitationUnit::Millimetres => 1.0,
+            PrecipitationUnit::Inches => 1.0 / 25.4,
+        }
+    }
+
+    /// `value`, given in `from`, in this unit.
+    pub fn convert(self, value: f64, from: PrecipitationUnit) -> f64 {
+        if self == from { value } else { value / from.per_millimetre() * self.per_millimetre() }
+    }
+
+    /// `value` (in this unit) in millimetres, for thresholds defined in mm.
+    pub fn to_mm(self, value: f64) -> f64 {
+        PrecipitationUnit::Millimetres.convert(value, self)
+    }
+
+    /// An amount with its unit, to the precision that unit needs: "1.2 mm",
+    /// "0.05 in".
+    pub fn format(self, value: f64) -> String {
+        match self {
+            PrecipitationUnit::Millimetres => format!("{value:.1} mm"),
+            PrecipitationUnit::Inches => format!("{value:.2} in"),
+        }
+    }
+}
+
+impl Rounding {
+    pub const ALL: [Rounding; 4] = [Rounding::Nearest, Rounding::Down, Rounding::Up, Rounding::Tenths];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Rounding::Nearest => "nearest",
+            Rounding::Down => "down",
+            Rounding::Up => "up",
+            Rounding::Tenths => "tenths",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Rounding> {
+        Rounding::ALL.into_iter().find(|rounding| rounding.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Rounding::Nearest => "Nearest degree",
+            Rounding::Down => "Round down",
+            Rounding::Up => "Round up",
+            Rounding::Tenths => "One decimal",
+        }
+    }
+
+    pub fn apply(self, value: f64) -> f64 {
+        match self {
+            Rounding::Nearest => value.round(),
+            Rounding::Down => value.floor(),
+            Rounding::Up => value.ceil(),
+            Rounding::Tenths => (value * 10.0).round() / 10.0,
+        }
+    }
+
+    /// The rounded value as text. Never shows "-0": adding 0.0 turns a
+    /// negative zero positive.
+    pub fn format(self, value: f64) -> String {
+        let decimals = if self == Rounding::Tenths { 1 } else { 0 };
+        format!("{:.*}", decimals, self.apply(value) + 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions_round_trip_through_the_base_units() {
+        let c = TemperatureUnit::Celsius;
+        let f = TemperatureUnit::Fahrenheit;
+        assert_eq!(f.convert(100.0, c), 212.0);
+        assert_eq!(c.convert(-40.0, f), -40.0);
+        assert_eq!(c.convert(21.5, c), 21.5);
+
+        assert!((WindUnit::KilometresPerHour.convert(10.0, WindUnit::MetresPerSecond) - 36.0).abs() < 1e-9);
+        assert!((WindUnit::Knots.convert(1.0, WindUnit::MetresPerSecond) - 1.943_844).abs() < 1e-6);
+        assert!((WindUnit::MilesPerHour.convert(36.0, WindUnit::KilometresPerHour) - 22.369_363).abs() < 1e-6);
+
+        assert!((PrecipitationUnit::Inches.convert(25.4, PrecipitationUnit::Millimetres) - 1.0).abs() < 1e-12);
+        assert!((PrecipitationUnit::Inches.to_mm(0.5) - 12.7).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rounding_modes_never_print_negative_zero() {
+        assert_eq!(Rounding::Nearest.format(-0.3), "0");
+        assert_eq!(Rounding::Nearest.format(2.5), "3");
+        assert_eq!(Rounding::Nearest.format(-2.5), "-3");
+        assert_eq!(Rounding::Down.format(2.9), "2");
+        assert_eq!(Rounding::Up.format(-0.7), "0");
+        assert_eq!(Rounding::Up.format(2.1), "3");
+        assert_eq!(Rounding::Tenths.format(-0.04), "0.0");
+        assert_eq!(Rounding::Tenths.format(12.345), "12.3");
+    }
+
+    #[test]
+    fn keys_parse_back() {
+        for unit in TemperatureUnit::ALL {
+            assert_eq!(TemperatureUnit::from_key(unit.key()), Some(unit));
+        }
+        for unit in WindUnit::ALL {
+            assert_eq!(WindUnit::from_key(unit.key()), Some(unit));
+        }
+        for unit in PrecipitationUnit::ALL {
+            assert_eq!(PrecipitationUnit::from_key(unit.key()), Some(unit));
+        }
+        for rounding in Rounding::ALL {
+            assert_eq!(Rounding::from_key(rounding.key()), Some(rounding));
+        }
+        assert_eq!(WindUnit::from_key("knots"), None);
+    }
+}
@@ -1,19 +1,20 @@
-use std::{collections::HashMap, error::Error, fs::{self, File}, io::{BufReader, BufWriter, Write}, path::PathBuf};
+use std::{collections::HashMap, error::Error, fs::{self, File}, io::{BufReader, BufWriter, Write}, path::{Path, PathBuf}};
 use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
 use egui::Color32;
 use tempfile::NamedTempFile;
 
 use crate::color::ColorScheme;
 
-/// Resolve a user-supplied image name to a path inside `images/`, defending
+/// Resolve a user-supplied image name to a path inside `images_dir`, defending
 /// against path traversal. Only the final path component is kept, so `..`,
-/// absolute paths, drive prefixes, and embedded separators can't escape the
-/// directory. Returns `None` when `name` has no usable file-name component
+/// absolute paths, drive prefixes, and embedded separators (`/` or `\`, on every
+/// platform) can't escape the directory. Returns `None` when `name` has no usable file-name component
 /// (e.g. `""`, `".."`, `"sub/"`). This is the single source of truth for that
 /// check — both the background loader and the colour-scheme generator use it.
-pub fn safe_image_path(name: &str) -> Option<PathBuf> {
-    let file_name = std::path::Path::new(name).file_name()?;
-    Some(PathBuf::from("images").join(file_name))
+pub fn safe_image_path(images_dir: &Path, name: &str) -> Option<PathBuf> {
+    let last = name.rsplit(['/', '\\']).find(|part| !part.is_empty())?;
+    let file_name = Path::new(last).file_name()?;
+    Some(images_dir.join(file_name))
 }
 
 /// Build a TOML array `[a, b]` of two floats for the config file. Used for the
@@ -66,12 +67,31 @@ pub fn parse_time_input(day: i32, month: i32, year: i32, hour: i32, minute: i32)
     Ok(date_time)
 }
 
-pub fn next_three_weekdays(now: DateTime<Local>) -> (String, String, String) {
-    (
-        now.format("%A").to_string(),
-        (now + Duration::days(1)).format("%A").to_string(),
-        (now + Duration::days(2)).format("%A").to_string(),
-    )
+/// Parse a `dd.mm.yyyy` date typed into a text field (the format the archive
+/// shows dates in). Surrounding whitespace is ignored.
+pub fn parse_date_input(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text.trim(), "%d.%m.%Y").ok()
+}
+
+/// Compact human duration for summaries: "3d 4h", "5h 12m" or "40m". Only the
+/// two largest units are shown; negative durations are treated as zero.
+pub fn format_duration_short(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// The 8-point compass name for a bearing in degrees: 350 → "N", 100 → "E".
+pub fn compass_point(degrees: f64) -> &'static str {
+    const POINTS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    POINTS[((degrees.rem_euclid(360.0) + 22.5) / 45.0) as usize % 8]
 }
 
 /// Number of days in `month` (1..=12) of `year`, accounting for leap years.
@@ -140,9 +160,9 @@ pub fn read_notepad_text(exe_path: &PathBuf) -> Result<String, Box<dyn Error>> {
     
     let file_path = dir_path.join("notepad_text.json");
     
+    // As with `tasks::read_at_startup`, a missing file is left for the first save.
     if !file_path.exists() {
-        let mut file = File::create(&file_path).expect("failed to create notepad_text JSON file");
-        file.write_all(b"{}").expect("failed to write to notepad_text JSON file");
+        return Ok(String::new());
     }
 
     let file = File::open(&file_path)?;
@@ -162,21 +182,21 @@ mod tests {
         let images = PathBuf::from("images");
 
         // Ordinary names resolve directly under images/.
-        assert_eq!(safe_image_path("pic.png"), Some(images.join("pic.png")));
+        assert_eq!(safe_image_path(&images, "pic.png"), Some(images.join("pic.png")));
 
         // Traversal and absolute paths are reduced to their final component, so
         // they can't escape images/.
-        assert_eq!(safe_image_path("../../etc/passwd"), Some(images.join("passwd")));
-        assert_eq!(safe_image_path("/etc/passwd"), Some(images.join("passwd")));
-        assert_eq!(safe_image_path("sub/dir/p.png"), Some(images.join("p.png")));
+        assert_eq!(safe_image_path(&images, "../../etc/passwd"), Some(images.join("passwd")));
+        assert_eq!(safe_image_path(&images, "/etc/passwd"), Some(images.join("passwd")));
+        assert_eq!(safe_image_path(&images, "sub/dir/p.png"), Some(images.join("p.png")));
         // A trailing separator is ignored — the final named component is kept.
-        assert_eq!(safe_image_path("sub/"), Some(images.join("sub")));
+        assert_eq!(safe_image_path(&images, "sub/"), Some(images.join("sub")));
         // Windows-style separators are handled too.
-        assert_eq!(safe_image_path(r"..\..\win.png"), Some(images.join("win.png")));
+        assert_eq!(safe_image_path(&images, r"..\..\win.png"), Some(images.join("win.png")));
 
         // Names with no usable file component are rejected.
-        assert_eq!(safe_image_path(""), None);
-        assert_eq!(safe_image_path(".."), None);
+        assert_eq!(safe_image_path(&images, ""), None);
+        assert_eq!(safe_image_path(&images, ".."), None);
     }
 
     #[test]
@@ -232,6 +252,31 @@ mod tests {
         assert_eq!(dt.minute(), 30);
     }
 
+    #[test]
+    fn parse_date_input_reads_archive_format() {
+        assert_eq!(parse_date_input(" 03.02.2025 "), NaiveDate::from_ymd_opt(2025, 2, 3));
+        assert_eq!(parse_date_input("31.02.2025"), None);
+        assert_eq!(parse_date_input("2025-02-03"), None);
+        assert_eq!(parse_date_input(""), None);
+    }
+
+    #[test]
+    fn format_duration_short_keeps_two_largest_units() {
+        assert_eq!(format_duration_short(Duration::minutes(40)), "40m");
+        assert_eq!(format_duration_short(Duration::minutes(5 * 60 + 12)), "5h 12m");
+        assert_eq!(format_duration_short(Duration::hours(76) + Duration::minutes(59)), "3d 4h");
+        assert_eq!(format_duration_short(Duration::hours(-2)), "0m");
+    }
+
+    #[test]
+    fn compass_point_wraps_around_north() {
+        assert_eq!(compass_point(0.0), "N");
+        assert_eq!(compass_point(350.0), "N");
+        assert_eq!(compass_point(100.0), "E");
+        assert_eq!(compass_point(225.0), "SW");
+        assert_eq!(compass_point(-45.0), "NW");
+    }
+
     #[test]
     fn parse_time_input_rejects_impossible_dates() {
         assert!(parse_time_input(31, 2, 2025, 12, 0).is_err()); // Feb 31
@@ -1,119 +1,1176 @@
 use std::{
+    error::Error,
+    fs::File,
+    io::BufReader,
+    ops::RangeInclusive,
+    path::PathBuf,
     sync::{Arc, RwLock, atomic::{AtomicU64, Ordering}},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Local, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use egui::ImageSource;
 use reqwest::blocking::Client;
 use reqwest::header::USER_AGENT;
-use serde::Deserialize;
-use winit::event_loop::EventLoopProxy;
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::{self, Alert, AlertRules};
+use crate::units::Units;
 
 use std::sync::mpsc::{channel, Receiver, Sender};
 
+/// MET Norway's terms require an identifying User-Agent; Open-Meteo gets the
+/// same one.
+const WEATHER_USER_AGENT: &str = concat!("TaskDeck/", env!("CARGO_PKG_VERSION"), " github.com/TT-SAL/taskdeck");
+
+/// The longest horizon the panel can show (`forecast_days`). Both providers
+/// reach at least this far, and a fetch that doesn't counts as failed.
+pub const MAX_FORECAST_DAYS: usize = 7;
+
+/// Days of daily summaries requested for the calendar's day cells, as far as
+/// Open-Meteo goes. MET's hourly data is summarised instead and ends sooner.
+pub const MAX_DAILY_DAYS: usize = 16;
+
+/// The slot lengths the panel offers (`weather_window_hours`), in hours.
+pub const AGGREGATION_WINDOWS: [u32; 4] = [1, 2, 3, 6];
+
+/// The last successful forecast, in the data directory. Loaded before the
+/// first fetch so the panel has something to show offline.
+const WEATHER_CACHE_FILE: &str = "weather_cache.json";
+
+/// Age past which the panel says "last updated … ago". A healthy thread
+/// refreshes every 10 minutes, so this only shows when fetches keep failing.
+pub const STALE_AFTER_MINUTES: i64 = 60;
+
+/// Which half of the yr symbol set an hour uses: `{code}d.svg`, `{code}n.svg`
+/// or `{code}m.svg` (polar twilight). Symbols without variants ignore it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DayPhase {
+    Day,
+    Night,
+    PolarTwilight,
+}
+
+impl DayPhase {
+    /// The phase shown for a slot merging two hours: the sun is drawn if it is
+    /// up for either of them.
+    pub fn merge(self, other: DayPhase) -> DayPhase {
+        match (self, other) {
+            (DayPhase::Day, _) | (_, DayPhase::Day) => DayPhase::Day,
+            (DayPhase::PolarTwilight, _) | (_, DayPhase::PolarTwilight) => DayPhase::PolarTwilight,
+            _ => DayPhase::Night,
+        }
+    }
+}
+
+/// One hour of forecast, provider-independent. `symbol` is a yr/MET Norway
+/// symbol number (see the naming reference below), so it maps straight onto
+/// the bundled SVGs whichever provider produced it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeatherData {
+    /// Local wall-clock time of the start of the hour.
+    pub time: NaiveDateTime,
+    /// In the forecast's `units`, like the wind and precipitation details.
+    pub temp: f64,
+    pub symbol: u8,
+    pub phase: DayPhase,
+    /// Absent from caches written before these fields existed.
+    #[serde(default)]
+    pub details: HourDetails,
+}
+
+/// The secondary fields behind the expanded forecast row. Everything is
+/// optional: providers don't all report everything (MET has no feels-like,
+/// and its 6-hourly tail no UV), and a missing value is left blank rather
+/// than shown as zero.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct HourDetails {
+    /// Feels-like temperature.
+    pub apparent_temp: Option<f64>,
+    /// Precipitation during the hour.
+    pub precipitation: Option<f64>,
+    /// Chance of precipitation during the hour, %.
+    pub precipitation_probability: Option<f64>,
+    /// Mean wind at 10 m.
+    pub wind_speed: Option<f64>,
+    pub wind_gusts: Option<f64>,
+    /// Where the wind blows *from*, degrees clockwise from north.
+    pub wind_direction: Option<f64>,
+    pub uv_index: Option<f64>,
+}
+
+impl HourDetails {
+    /// The details of a slot spanning several hours: totals add up, chances
+    /// and peaks take the worst hour, the rest is averaged over the hours that
+    /// have a value. The wind direction is the first hour's, since averaging
+    /// angles across north goes wrong.
+    pub fn aggregate(hours: &[HourDetails]) -> HourDetails {
+        fn values(hours: &[HourDetails], field: fn(&HourDetails) -> Option<f64>) -> impl Iterator<Item = f64> + '_ {
+            hours.iter().filter_map(field)
+        }
+        let sum = |field| values(hours, field).reduce(|a, b| a + b);
+        let max = |field| values(hours, field).reduce(f64::max);
+        let mean = |field| {
+            let (total, count) = values(hours, field).fold((0.0, 0), |(total, count), value| (total + value, count + 1));
+            (count > 0).then(|| total / count as f64)
+        };
+        HourDetails {
+            apparent_temp: mean(|hour| hour.apparent_temp),
+            precipitation: sum(|hour| hour.precipitation),
+            precipitation_probability: max(|hour| hour.precipitation_probability),
+            wind_speed: mean(|hour| hour.wind_speed),
+            wind_gusts: max(|hour| hour.wind_gusts),
+            wind_direction: hours.iter().find_map(|hour| hour.wind_direction),
+            uv_index: max(|hour| hour.uv_index),
+        }
+    }
+}
+
+impl WeatherData {
+    fn convert(&mut self, from: Units, to: Units) {
+        let temperature = |value: f64| to.temperature.convert(value, from.temperature);
+        let wind = |value: f64| to.wind.convert(value, from.wind);
+        self.temp = temperature(self.temp);
+        let details = &mut self.details;
+        details.apparent_temp = details.apparent_temp.map(temperature);
+        details.precipitation = details.precipitation.map(|value| to.precipitation.convert(value, from.precipitation));
+        details.wind_speed = details.wind_speed.map(wind);
+        details.wind_gusts = details.wind_gusts.map(wind);
+    }
+}
+
+/// One day at a glance, for the calendar's day cells.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DaySummary {
+    pub date: NaiveDate,
+    pub high: f64,
+    pub low: f64,
+    pub symbol: u8,
+    /// The day's total, if the provider reports precipitation.
+    pub precipitation: Option<f64>,
+}
+
+impl DaySummary {
+    fn convert(&mut self, from: Units, to: Units) {
+        self.high = to.temperature.convert(self.high, from.temperature);
+        self.low = to.temperature.convert(self.low, from.temperature);
+        self.precipitation = self.precipitation.map(|value| to.precipitation.convert(value, from.precipitation));
+    }
+}
+
+/// A named place to forecast, one entry of `locations` in `userconfig.toml`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    pub name: String,
+    /// `[latitude, longitude]`.
+    pub coordinates: [f32; 2],
+}
+
+/// Whether two coordinates are the same place, give or take the map picker's
+/// precision. Forecasts, alerts and events find their location this way.
+pub fn same_place(a: [f32; 2], b: [f32; 2]) -> bool {
+    (a[0] - b[0]).abs() < 0.005 && (a[1] - b[1]).abs() < 0.005
+}
+
+/// `locations` as `userconfig.toml` holds it: an inline array of
+/// `{ name = "…", coordinates = [lat, lon] }` tables.
+pub fn locations_to_toml(locations: &[Location]) -> toml_edit::Value {
+    let mut array = toml_edit::Array::new();
+    for location in locations {
+        let mut table = toml_edit::InlineTable::new();
+        table.insert("name", location.name.as_str().into());
+        table.insert("coordinates", crate::utilities::float_pair_array(location.coordinates).into());
+        array.push(table);
+    }
+    array.into()
+}
+
+/// One day of display slots, as returned by `Forecast::days`.
+pub type ForecastDay = (NaiveDate, Vec<WeatherData>);
+
+/// A successful fetch: what `WeatherService::data` holds and what is cached
+/// in `weather_cache.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Forecast {
+    pub fetched_at: DateTime<Local>,
+    pub coordinates: [f32; 2],
+    pub provider: WeatherProviderKind,
+    /// What the points are measured in: the provider's native units for the
+    /// settings at fetch time. Caches from before units existed are metric.
+    #[serde(default)]
+    pub units: Units,
+    /// Ascending hourly points, as parsed.
+    pub points: Vec<WeatherData>,
+    /// Ascending daily summaries. Caches from before these existed have
+    /// none; `summaries` falls back to the points for them.
+    #[serde(default)]
+    pub daily: Vec<DaySummary>,
+}
+
+impl Forecast {
+    /// The same forecast in `units`, for display. A no-op when the provider
+    /// already delivered them.
+    pub fn in_units(mut self, units: Units) -> Forecast {
+        if self.units != units {
+            for point in &mut self.points {
+                point.convert(self.units, units);
+            }
+            for day in &mut self.daily {
+                day.convert(self.units, units);
+            }
+            self.units = units;
+        }
+        self
+    }
+
+    /// End of the last forecast step, taking it to be as long as the one
+    /// before it (MET's tail is 6-hourly), and at least an hour.
+    pub fn covered_until(&self) -> Option<NaiveDateTime> {
+        points_end(&self.points)
+    }
+
+    /// Whether the forecast still says anything about `now`. Only once this
+    /// fails is the weather shown as broken; before that old data is shown as
+    /// stale instead.
+    pub fn covers(&self, now: NaiveDateTime) -> bool {
+        self.covered_until().is_some_and(|end| now < end)
+    }
+
+    /// The panel's days, `days` of them starting at `today` — or at the
+    /// forecast's first day if that is later (a location ahead of the local
+    /// clock) — each cut into `window_hours` slots by `day_slots`.
+    pub fn days<Tz: TimeZone>(
+        &self,
+        today: NaiveDate,
+        days: usize,
+        window_hours: u32,
+        tz: &Tz,
+    ) -> Result<Vec<ForecastDay>, Box<dyn Error>> {
+        let first_day = self.points.first().ok_or("The forecast contained no data")?.time.date().max(today);
+        Ok((0..days as i64)
+            .map(|offset| first_day + chrono::Duration::days(offset))
+            .map(|day| (day, day_slots(&self.points, day, window_hours, tz)))
+            .collect())
+    }
+
+    /// The daily summaries, or for a cache without them, the ones the points
+    /// allow (see `summarize_days`).
+    pub fn summaries<Tz: TimeZone>(&self, tz: &Tz) -> Vec<DaySummary> {
+        if self.daily.is_empty() { summarize_days(&self.points, tz) } else { self.daily.clone() }
+    }
+
+    /// Whether this is the forecast for `coordinates` (see `same_place`).
+    pub fn is_for(&self, coordinates: [f32; 2]) -> bool {
+        same_place(self.coordinates, coordinates)
+    }
+}
+
+/// A forecast source. Implementations only build the request and parse the
+/// response into hourly points; fetching, retries and the reshape into the
+/// panel's slots are shared (`fetch_weather_once`, `day_slots`).
+pub trait WeatherProvider: Send {
+    /// Scheme and host of the real API, e.g. `https://api.met.no`. URLs are
+    /// built on whatever base they are given, so tests can use a local server.
+    fn base_url(&self) -> &'static str;
+    fn url(&self, base_url: &str, coordinates: [f32; 2], units: Units) -> String;
+    /// The units `parse` returns when `url` was built for `requested`: the
+    /// same where the API converts for us, else whatever it always sends.
+    fn native_units(&self, requested: Units) -> Units;
+    /// Hourly points in ascending time order.
+    fn parse(&self, body: &[u8]) -> Result<Vec<WeatherData>, Box<dyn Error>>;
+    /// Daily summaries in ascending order, in the same units as `parse`.
+    /// By default summarised from the hourly `points`.
+    fn parse_daily(&self, _body: &[u8], points: &[WeatherData]) -> Result<Vec<DaySummary>, Box<dyn Error>> {
+        Ok(summarize_days(points, &Local))
+    }
+    /// Whether `url_for_all` can ask for several locations in one request.
+    fn batches(&self) -> bool {
+        false
+    }
+    /// One request for every location in `coordinates`; only used when
+    /// `batches`.
+    fn url_for_all(&self, base_url: &str, coordinates: &[[f32; 2]], units: Units) -> String {
+        self.url(base_url, coordinates[0], units)
+    }
+    /// A batched response cut into one body per location, in request order.
+    fn split_batch(&self, body: &[u8]) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        Ok(vec![body.to_vec()])
+    }
+}
+
+/// The providers selectable in Settings, persisted as `weather_provider`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WeatherProviderKind {
+    #[default]
+    OpenMeteo,
+    MetNorway,
+}
+
+impl WeatherProviderKind {
+    pub const ALL: [WeatherProviderKind; 2] = [WeatherProviderKind::OpenMeteo, WeatherProviderKind::MetNorway];
+
+    /// The `userconfig.toml` value.
+    pub fn key(self) -> &'static str {
+        match self {
+            WeatherProviderKind::OpenMeteo => "open-meteo",
+            WeatherProviderKind::MetNorway => "met-norway",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<WeatherProviderKind> {
+        WeatherProviderKind::ALL.into_iter().find(|kind| kind.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WeatherProviderKind::OpenMeteo => "Open-Meteo",
+            WeatherProviderKind::MetNorway => "MET Norway (yr)",
+        }
+    }
+
+    pub fn provider(self) -> Box<dyn WeatherProvider> {
+        match self {
+            WeatherProviderKind::OpenMeteo => Box::new(OpenMeteo),
+            WeatherProviderKind::MetNorway => Box::new(MetNorway),
+        }
+    }
+}
+
+/* ───────────────────────── Open-Meteo ───────────────────────── */
+
+/// <https://open-meteo.com/en/docs>. Days start at the location's midnight
+/// (`timezone=auto`), but times come as Unix timestamps and are converted to
+/// the machine's local time like MET's; local wall-clock strings can't tell
+/// the two 02:00s of a DST change apart. Conditions are WMO codes,
+/// approximated onto yr symbols by `symbol_for_wmo`. It converts to
+/// any of our units itself.
+pub struct OpenMeteo;
+
 #[derive(Debug, Deserialize)]
-struct WeatherResponse {
-    hourly: HourlyData,
+struct OpenMeteoResponse {
+    hourly: OpenMeteoHourly,
+    daily: Option<OpenMeteoDaily>,
+    /// The location's offset from UTC now, with `timezone=auto`.
+    #[serde(default)]
+    utc_offset_seconds: i64,
 }
 
 #[derive(Debug, Deserialize)]
-struct HourlyData {
-    time: Vec<String>,
+struct OpenMeteoDaily {
+    /// Unix seconds of midnight at the location.
+    time: Vec<i64>,
+    weather_code: Vec<Option<i32>>,
+    temperature_2m_max: Vec<Option<f64>>,
+    temperature_2m_min: Vec<Option<f64>>,
+    #[serde(default)]
+    precipitation_sum: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenMeteoHourly {
+    /// Unix seconds (`timeformat=unixtime`).
+    time: Vec<i64>,
     temperature_2m: Vec<f64>,
     weather_code: Vec<i32>,
     is_day: Vec<i32>,
+    // The detail series can hold nulls (UV near the end of the range).
+    #[serde(default)]
+    apparent_temperature: Vec<Option<f64>>,
+    #[serde(default)]
+    precipitation: Vec<Option<f64>>,
+    #[serde(default)]
+    precipitation_probability: Vec<Option<f64>>,
+    #[serde(default)]
+    wind_speed_10m: Vec<Option<f64>>,
+    #[serde(default)]
+    wind_gusts_10m: Vec<Option<f64>>,
+    #[serde(default)]
+    wind_direction_10m: Vec<Option<f64>>,
+    #[serde(default)]
+    uv_index: Vec<Option<f64>>,
 }
 
-#[derive(Clone)]
-pub struct WeatherData {
-    pub is_day: i32,
-    pub temp: f64,
-    pub weather_code: i32,
-    pub time: String,
+impl WeatherProvider for OpenMeteo {
+    fn base_url(&self) -> &'static str {
+        "https://api.open-meteo.com"
+    }
+
+    fn url(&self, base_url: &str, coordinates: [f32; 2], units: Units) -> String {
+        self.url_for_all(base_url, &[coordinates], units)
+    }
+
+    fn batches(&self) -> bool {
+        true
+    }
+
+    /// Comma-separated latitudes and longitudes; the response is then an
+    /// array with one forecast per location.
+    fn url_for_all(&self, base_url: &str, coordinates: &[[f32; 2]], units: Units) -> String {
+        let join = |index: usize| coordinates.iter().map(|pair| pair[index].to_string()).collect::<Vec<_>>().join(",");
+        format!(
+            "{base_url}/v1/forecast\
+            ?latitude={}&longitude={}\
+            &hourly=temperature_2m,weather_code,is_day,apparent_temperature,\
+            precipitation,precipitation_probability,\
+            wind_speed_10m,wind_gusts_10m,wind_direction_10m,uv_index\
+            &daily=weather_code,temperature_2m_max,temperature_2m_min,precipitation_sum\
+            &temperature_unit={}&wind_speed_unit={}&precipitation_unit={}\
+            &timezone=auto&timeformat=unixtime&forecast_days={MAX_DAILY_DAYS}",
+            join(0), join(1),
+            units.temperature.key(), units.wind.key(), units.precipitation.key()
+        )
+    }
+
+    fn native_units(&self, requested: Units) -> Units {
+        requested
+    }
+
+    fn parse(&self, body: &[u8]) -> Result<Vec<WeatherData>, Box<dyn Error>> {
+        OpenMeteo::parse_in(body, &Local)
+    }
+
+    fn split_batch(&self, body: &[u8]) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        match serde_json::from_slice::<serde_json::Value>(body)? {
+            serde_json::Value::Array(forecasts) => forecasts.iter().map(|forecast| Ok(serde_json::to_vec(forecast)?)).collect(),
+            _ => Ok(vec![body.to_vec()]),
+        }
+    }
+
+    fn parse_daily(&self, body: &[u8], points: &[WeatherData]) -> Result<Vec<DaySummary>, Box<dyn Error>> {
+        let json = serde_json::from_slice::<OpenMeteoResponse>(body)?;
+        let Some(daily) = json.daily else {
+            return Ok(summarize_days(points, &Local));
+        };
+
+        let mut days = Vec::with_capacity(daily.time.len());
+        for (i, time) in daily.time.iter().enumerate() {
+            // The date is the location's, not ours: it is the day the
+            // summary describes. Timestamps are its local midnights; shifted
+            // by today's offset and read at midday, a DST change within the
+            // horizon can't move one to the neighbouring date.
+            let date = DateTime::from_timestamp(*time + json.utc_offset_seconds + 12 * 3600, 0)
+                .ok_or_else(|| format!("Invalid forecast timestamp {time}"))?
+                .date_naive();
+            // Days past the model's reach come back as nulls.
+            let (Some(high), Some(low), Some(code)) = (
+                daily.temperature_2m_max.get(i).copied().flatten(),
+                daily.temperature_2m_min.get(i).copied().flatten(),
+                daily.weather_code.get(i).copied().flatten(),
+            ) else {
+                continue;
+            };
+            days.push(DaySummary {
+                date,
+                high,
+                low,
+                symbol: symbol_for_wmo(code),
+                precipitation: daily.precipitation_sum.get(i).copied().flatten(),
+            });
+        }
+        Ok(days)
+    }
 }
 
-enum WeatherCommand {
-    SetCoordinates([f32; 2]),
-    Stop,
+impl OpenMeteo {
+    /// `parse` with the output timezone passed in, so tests don't depend on
+    /// the machine's.
+    fn parse_in<Tz: TimeZone>(body: &[u8], tz: &Tz) -> Result<Vec<WeatherData>, Box<dyn Error>> {
+        let json = serde_json::from_slice::<OpenMeteoResponse>(body)?;
+        let hourly = json.hourly;
+
+        // A series shorter than `time` is an incomplete response, not a 0° clear hour.
+        let missing = || format!("Open-Meteo sent fewer values than its {} hourly times", hourly.time.len());
+        let at = |series: &[Option<f64>], i: usize| series.get(i).copied().flatten();
+
+        let mut points = Vec::with_capacity(hourly.time.len());
+        for (i, time) in hourly.time.iter().enumerate() {
+            points.push(WeatherData {
+                time: DateTime::from_timestamp(*time, 0)
+                    .ok_or_else(|| format!("Invalid forecast timestamp {time}"))?
+                    .with_timezone(tz)
+                    .naive_local(),
+                temp: *hourly.temperature_2m.get(i).ok_or_else(missing)?,
+                symbol: symbol_for_wmo(*hourly.weather_code.get(i).ok_or_else(missing)?),
+                phase: if *hourly.is_day.get(i).ok_or_else(missing)? == 1 { DayPhase::Day } else { DayPhase::Night },
+                details: HourDetails {
+                    apparent_temp: at(&hourly.apparent_temperature, i),
+                    precipitation: at(&hourly.precipitation, i),
+                    precipitation_probability: at(&hourly.precipitation_probability, i),
+                    wind_speed: at(&hourly.wind_speed_10m, i),
+                    wind_gusts: at(&hourly.wind_gusts_10m, i),
+                    wind_direction: at(&hourly.wind_direction_10m, i),
+                    uv_index: at(&hourly.uv_index, i),
+                },
+            });
+        }
+        Ok(points)
+    }
 }
 
-pub struct WeatherService {
-    pub data: Arc<RwLock<Vec<Vec<WeatherData>>>>,
-    pub version: Arc<AtomicU64>,
-    tx: Sender<WeatherCommand>,
+/// Approximate a WMO weather code (Open-Meteo) with the closest yr symbol.
+pub fn symbol_for_wmo(code: i32) -> u8 {
+    match code {
+        // --- Clear & clouds ---
+        0 => 1,
+        1 => 2,
+        2 => 3,
+        3 => 4,
+
+        // --- Fog ---
+        45 | 48 => 15,
+
+        // --- Drizzle ---
+        51 | 53 => 46,
+        55 => 9,
+        56 | 57 => 47,
+
+        // --- Rain ---
+        61 => 46,
+        63 => 9,
+        65 => 10,
+        66 => 47,
+        67 => 48,
+
+        // --- Snow ---
+        71 | 77 => 49,
+        73 => 13,
+        75 => 50,
+
+        // --- Rain showers ---
+        80 => 40,
+        81 => 5,
+        82 => 41,
+
+        // --- Snow showers ---
+        85 => 44,
+        86 => 45,
+
+        // --- Thunderstorms ---
+        95 => 22,
+        96 | 99 => 11,
+
+        // --- Fallback ---
+        _ => 4,
+    }
 }
 
-impl WeatherService {
-    pub fn set_coordinates(&self, coords: [f32; 2]) {
-        let _ = self.tx.send(WeatherCommand::SetCoordinates(coords));
+/* ───────────────────────── MET Norway ───────────────────────── */
+
+/// MET Norway Locationforecast 2.0 (the data behind yr.no),
+/// <https://api.met.no/weatherapi/locationforecast/2.0/documentation>.
+/// Its `symbol_code`s (`clearsky_day`, `lightrainshowers_polartwilight`, …)
+/// name the bundled SVGs directly. Times are UTC and converted to the
+/// machine's local time, the clock the forecast panel highlights against.
+/// Uses the `complete` variant for wind, UV and precipitation chance, which
+/// `compact` leaves out. Always metric.
+pub struct MetNorway;
+
+#[derive(Debug, Deserialize)]
+struct MetResponse {
+    properties: MetProperties,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetProperties {
+    timeseries: Vec<MetStep>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetStep {
+    time: DateTime<Utc>,
+    data: MetStepData,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetStepData {
+    instant: MetInstant,
+    next_1_hours: Option<MetPeriod>,
+    next_6_hours: Option<MetPeriod>,
+    next_12_hours: Option<MetPeriod>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetInstant {
+    details: MetInstantDetails,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetInstantDetails {
+    air_temperature: f64,
+    relative_humidity: Option<f64>,
+    wind_speed: Option<f64>,
+    wind_speed_of_gust: Option<f64>,
+    wind_from_direction: Option<f64>,
+    ultraviolet_index_clear_sky: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetPeriod {
+    summary: MetSummary,
+    #[serde(default)]
+    details: MetPeriodDetails,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct MetPeriodDetails {
+    precipitation_amount: Option<f64>,
+    probability_of_precipitation: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetSummary {
+    symbol_code: String,
+}
+
+impl MetNorway {
+    /// `parse` with the output timezone passed in, so tests don't depend on
+    /// the machine's.
+    fn parse_in<Tz: TimeZone>(body: &[u8], tz: &Tz) -> Result<Vec<WeatherData>, Box<dyn Error>> {
+        let json = serde_json::from_slice::<MetResponse>(body)?;
+
+        let mut points = Vec::with_capacity(json.properties.timeseries.len());
+        for step in json.properties.timeseries {
+            // The shortest period that has a summary; the last steps have none.
+            let periods = [(step.data.next_1_hours, 1.0), (step.data.next_6_hours, 6.0), (step.data.next_12_hours, 12.0)];
+            let Some((period, hours)) = periods.into_iter().find_map(|(period, hours)| Some((period?, hours))) else {
+                continue;
+            };
+            let (symbol, phase) = parse_symbol_code(&period.summary.symbol_code)
+                .ok_or_else(|| format!("Unknown MET symbol_code '{}'", period.summary.symbol_code))?;
+
+            let instant = step.data.instant.details;
+            points.push(WeatherData {
+                time: step.time.with_timezone(tz).naive_local(),
+                temp: instant.air_temperature,
+                symbol,
+                phase,
+                details: HourDetails {
+                    apparent_temp: instant
+                        .relative_humidity
+                        .zip(instant.wind_speed)
+                        .map(|(humidity, wind)| apparent_temperature(instant.air_temperature, humidity, wind)),
+                    // Spread over the period, so every step reads per hour.
+                    precipitation: period.details.precipitation_amount.map(|amount| (amount / hours * 100.0).round() / 100.0),
+                    precipitation_probability: period.details.probability_of_precipitation,
+                    wind_speed: instant.wind_speed,
+                    wind_gusts: instant.wind_speed_of_gust,
+                    wind_direction: instant.wind_from_direction,
+                    uv_index: instant.ultraviolet_index_clear_sky,
+                },
+            });
+        }
+        Ok(points)
     }
 }
 
-impl Drop for WeatherService {
-    fn drop(&mut self) {
-        let _ = self.tx.send(WeatherCommand::Stop);
+impl WeatherProvider for MetNorway {
+    fn base_url(&self) -> &'static str {
+        "https://api.met.no"
+    }
+
+    fn url(&self, base_url: &str, coordinates: [f32; 2], _units: Units) -> String {
+        // The API asks for at most four decimals, for cacheability.
+        format!(
+            "{base_url}/weatherapi/locationforecast/2.0/complete?lat={:.4}&lon={:.4}",
+            coordinates[0], coordinates[1]
+        )
+    }
+
+    fn native_units(&self, _requested: Units) -> Units {
+        Units::default()
+    }
+
+    fn parse(&self, body: &[u8]) -> Result<Vec<WeatherData>, Box<dyn Error>> {
+        MetNorway::parse_in(body, &Local)
     }
 }
 
-fn fetch_weather_once(
-    client: &Client,
-    coordinates: [f32; 2],
-) -> Result<Vec<Vec<WeatherData>>, Box<dyn std::error::Error>> {
-    let url = format!(
-        "https://api.open-meteo.com/v1/forecast\
-        ?latitude={}&longitude={}\
-        &hourly=temperature_2m,weather_code,is_day\
-        &timezone=auto&forecast_days=3",
-        coordinates[0], coordinates[1]
-    );
+/// Steadman's apparent temperature (the Australian BoM's shade version), for
+/// providers that don't report a feels-like value. °C, % and m/s in, °C out,
+/// rounded to a tenth like the measured values.
+pub fn apparent_temperature(temp: f64, relative_humidity: f64, wind_speed: f64) -> f64 {
+    let vapour_pressure = relative_humidity / 100.0 * 6.105 * (17.27 * temp / (237.7 + temp)).exp();
+    ((temp + 0.33 * vapour_pressure - 0.70 * wind_speed - 4.0) * 10.0).round() / 10.0
+}
+
+/// yr symbol names and their SVG numbers. Two names carry the historical
+/// "lights" misspelling the API still uses; the corrected forms are accepted too.
+const SYMBOL_NAMES: &[(&str, u8)] = &[
+    ("clearsky", 1), ("fair", 2), ("partlycloudy", 3), ("cloudy", 4),
+    ("rainshowers", 5), ("rainshowersandthunder", 6), ("sleetshowers", 7), ("snowshowers", 8),
+    ("rain", 9), ("heavyrain", 10), ("heavyrainandthunder", 11), ("sleet", 12),
+    ("snow", 13), ("snowandthunder", 14), ("fog", 15),
+    ("sleetshowersandthunder", 20), ("snowshowersandthunder", 21), ("rainandthunder", 22), ("sleetandthunder", 23),
+    ("lightrainshowersandthunder", 24), ("heavyrainshowersandthunder", 25),
+    ("lightssleetshowersandthunder", 26), ("lightsleetshowersandthunder", 26), ("heavysleetshowersandthunder", 27),
+    ("lightssnowshowersandthunder", 28), ("lightsnowshowersandthunder", 28), ("heavysnowshowersandthunder", 29),
+    ("lightrainandthunder", 30), ("lightsleetandthunder", 31), ("heavysleetandthunder", 32),
+    ("lightsnowandthunder", 33), ("heavysnowandthunder", 34),
+    ("lightrainshowers", 40), ("heavyrainshowers", 41), ("lightsleetshowers", 42), ("heavysleetshowers", 43),
+    ("lightsnowshowers", 44), ("heavysnowshowers", 45),
+    ("lightrain", 46), ("lightsleet", 47), ("heavysleet", 48), ("lightsnow", 49), ("heavysnow", 50),
+];
 
+/// Split a MET `symbol_code` such as `rainshowers_polartwilight` into its
+/// symbol number and phase. Codes without a suffix (`rain`) are phase-less;
+/// they get `Day`, which their icons ignore.
+pub fn parse_symbol_code(code: &str) -> Option<(u8, DayPhase)> {
+    let (name, phase) = match code.rsplit_once('_') {
+        Some((name, "day")) => (name, DayPhase::Day),
+        Some((name, "night")) => (name, DayPhase::Night),
+        Some((name, "polartwilight")) => (name, DayPhase::PolarTwilight),
+        Some(_) => return None,
+        None => (code, DayPhase::Day),
+    };
+    SYMBOL_NAMES.iter().find(|(known, _)| *known == name).map(|&(_, number)| (number, phase))
+}
+
+/* ───────────────────────── Fetching ───────────────────────── */
+
+/// Rank of a symbol from calmest to most severe, used when two hours share a
+/// forecast slot. yr numbers aren't ordered by severity the way WMO codes
+/// roughly are, so the slot can't just take the larger number.
+pub fn symbol_severity(symbol: u8) -> usize {
+    const MILDEST_FIRST: [u8; 41] = [
+        1, 2, 3, 4, 15,
+        40, 5, 41, 46, 9, 10,
+        42, 7, 43, 47, 12, 48,
+        44, 8, 45, 49, 13, 50,
+        24, 6, 25, 30, 22, 11,
+        26, 20, 27, 31, 23, 32,
+        28, 21, 29, 33, 14, 34,
+    ];
+    MILDEST_FIRST.iter().position(|&known| known == symbol).unwrap_or(3)
+}
+
+/// Whether a yr symbol has thunder in it: WMO 95–99 on Open-Meteo, the
+/// `…andthunder` codes on MET.
+pub fn is_thunder(symbol: u8) -> bool {
+    matches!(symbol, 6 | 11 | 14 | 20..=34)
+}
+
+/// Cut one local day into slots of `window_hours`, starting at midnight,
+/// each summarising the hours it spans: mean temperature, the most severe
+/// symbol, the sun if any hour has it, and `HourDetails::aggregate`.
+///
+/// Slots are built from the wall-clock hours that actually exist in `tz` on
+/// that day, so a DST day has 23 or 25 hours rather than breaking the 24-hour
+/// shape: a missing hour is skipped (a slot made only of it is dropped) and a
+/// repeated one counts twice. Each hour takes the latest point at or before
+/// it, so coarser steps (MET's 6-hourly tail) fill forward; hours before the
+/// first point (MET starts at the current hour) repeat it, and hours after
+/// `Forecast::covered_until` repeat the last one for the caller to blank out.
+pub fn day_slots<Tz: TimeZone>(points: &[WeatherData], day: NaiveDate, window_hours: u32, tz: &Tz) -> Vec<WeatherData> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+    let window = window_hours.clamp(1, 24);
+
+    let mut slots = Vec::with_capacity((24 / window) as usize + 1);
+    for first_hour in (0..24).step_by(window as usize) {
+        let start = day.and_time(chrono::NaiveTime::MIN) + chrono::Duration::hours(first_hour as i64);
+        let mut hours: Vec<&WeatherData> = Vec::with_capacity(window as usize + 1);
+        for hour in first_hour..(first_hour + window).min(24) {
+            let time = day.and_time(chrono::NaiveTime::MIN) + chrono::Duration::hours(hour as i64);
+            let repeats = match tz.from_local_datetime(&time) {
+                LocalResult::None => 0,
+                LocalResult::Single(_) => 1,
+                LocalResult::Ambiguous(_, _) => 2,
+            };
+            let covering = points.partition_point(|point| point.time <= time).saturating_sub(1);
+            for repeat in 0..repeats {
+                // A repeated hour is in the data twice (MET, converted to local
+                // time); use both, else the one point twice.
+                let index = covering.saturating_sub(repeats - 1 - repeat);
+                hours.push(if points[index].time == time { &points[index] } else { &points[covering] });
+            }
+        }
+        let Some(first) = hours.first() else {
+            continue;
+        };
+
+        let details: Vec<HourDetails> = hours.iter().map(|hour| hour.details).collect();
+        slots.push(WeatherData {
+            time: start,
+            temp: hours.iter().map(|hour| hour.temp).sum::<f64>() / hours.len() as f64,
+            symbol: hours.iter().map(|hour| hour.symbol).max_by_key(|&symbol| symbol_severity(symbol)).unwrap_or(first.symbol),
+            phase: hours.iter().map(|hour| hour.phase).reduce(DayPhase::merge).unwrap_or(first.phase),
+            details: HourDetails::aggregate(&details),
+        });
+    }
+    slots
+}
+
+/// End of the last point's step, taking it to be as long as the one before
+/// it (MET's tail is 6-hourly), and at least an hour.
+pub fn points_end(points: &[WeatherData]) -> Option<NaiveDateTime> {
+    let last = points.last()?.time;
+    let step = match points.len() {
+        0 | 1 => chrono::Duration::hours(1),
+        len => (last - points[len - 2].time).max(chrono::Duration::hours(1)),
+    };
+    Some(last + step)
+}
+
+/// Daily summaries from hourly points, for providers without a daily
+/// endpoint: the extremes of the points, the symbol that covers the most
+/// hours (the more severe on a tie), and the summed precipitation. Hours are
+/// those of `day_slots` with one-hour windows, so coarse steps count for
+/// every hour they span. The last day is left out unless the data reaches its
+/// end; the first may be partial, since MET starts at the current hour.
+pub fn summarize_days<Tz: TimeZone>(points: &[WeatherData], tz: &Tz) -> Vec<DaySummary> {
+    let (Some(first), Some(end)) = (points.first(), points_end(points)) else {
+        return Vec::new();
+    };
+
+    let mut summaries = Vec::new();
+    let mut day = first.time.date();
+    while day.and_time(chrono::NaiveTime::MIN) + chrono::Duration::days(1) <= end {
+        let start = day.and_time(chrono::NaiveTime::MIN).max(first.time);
+        let hours: Vec<WeatherData> = day_slots(points, day, 1, tz).into_iter().filter(|hour| hour.time >= start).collect();
+        day += chrono::Duration::days(1);
+        if hours.is_empty() {
+            continue;
+        }
+
+        let mut counts: Vec<(u8, usize)> = Vec::new();
+        for hour in &hours {
+            match counts.iter_mut().find(|(symbol, _)| *symbol == hour.symbol) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((hour.symbol, 1)),
+            }
+        }
+        let details: Vec<HourDetails> = hours.iter().map(|hour| hour.details).collect();
+        summaries.push(DaySummary {
+            date: hours[0].time.date(),
+            high: hours.iter().map(|hour| hour.temp).fold(f64::MIN, f64::max),
+            low: hours.iter().map(|hour| hour.temp).fold(f64::MAX, f64::min),
+            symbol: counts
+                .into_iter()
+                .max_by_key(|&(symbol, count)| (count, symbol_severity(symbol)))
+                .map_or(hours[0].symbol, |(symbol, _)| symbol),
+            precipitation: HourDetails::aggregate(&details).precipitation.map(|total| (total * 100.0).round() / 100.0),
+        });
+    }
+    summaries
+}
+
+/// A fresh forecast must reach the longest horizon the panel can show; a
+/// shorter one is treated as a failed fetch so the previous data is kept.
+fn check_complete(points: &[WeatherData]) -> Result<(), Box<dyn Error>> {
+    let first = points.first().ok_or("The forecast contained no data")?;
+    let last_day = first.time.date() + chrono::Duration::days(MAX_FORECAST_DAYS as i64 - 1);
+    match points.last() {
+        Some(last) if last.time.date() >= last_day => Ok(()),
+        _ => Err(format!("The forecast ends before {last_day}").into()),
+    }
+}
+
+fn get_body(client: &Client, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
     let resp = client
-        .get(&url)
-        .header(USER_AGENT, "egui-weather-app")
+        .get(url)
+        .header(USER_AGENT, WEATHER_USER_AGENT)
         .send()?
         .error_for_status()?;
 
-    let bytes = resp.bytes()?;
-    let json = serde_json::from_slice::<WeatherResponse>(&bytes)?;
+    Ok(resp.bytes()?.to_vec())
+}
 
-    let mut new_data = vec![vec![]; 24];
+/// One location's response: hourly points and daily summaries.
+type Fetched = (Vec<WeatherData>, Vec<DaySummary>);
 
-    for i in 0..json.hourly.time.len() {
-        let item = WeatherData {
-            time: {
-                let input = json.hourly.time.get(i).cloned().unwrap_or_default();
-                NaiveDateTime::parse_from_str(&input, "%Y-%m-%dT%H:%M")?.format("%H:%M").to_string()
-            },
-            temp: *json.hourly.temperature_2m.get(i).unwrap_or(&0.0),
-            weather_code: *json.hourly.weather_code.get(i).unwrap_or(&0),
-            is_day: *json.hourly.is_day.get(i).unwrap_or(&0),
-        };
+/// The raw response for each of `coordinates`, in order: one request where
+/// the provider batches, else one per location.
+fn fetch_bodies(
+    client: &Client,
+    provider: &dyn WeatherProvider,
+    base_url: &str,
+    coordinates: &[[f32; 2]],
+    units: Units,
+) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+    if provider.batches() && coordinates.len() > 1 {
+        let bodies = provider.split_batch(&get_body(client, &provider.url_for_all(base_url, coordinates, units))?)?;
+        if bodies.len() != coordinates.len() {
+            return Err(format!("Asked for {} locations, got {}", coordinates.len(), bodies.len()).into());
+        }
+        Ok(bodies)
+    } else {
+        coordinates
+            .iter()
+            .map(|pair| get_body(client, &provider.url(base_url, *pair, units)))
+            .collect()
+    }
+}
+
+/// Points and daily summaries from each body, checked to be complete.
+fn parse_bodies(provider: &dyn WeatherProvider, bodies: &[Vec<u8>]) -> Result<Vec<Fetched>, Box<dyn Error>> {
+    bodies
+        .iter()
+        .map(|bytes| {
+            let points = provider.parse(bytes)?;
+            check_complete(&points)?;
+            let daily = provider.parse_daily(bytes, &points)?;
+            Ok((points, daily))
+        })
+        .collect()
+}
+
+/// Points and daily summaries for each of `coordinates`, in order. Any
+/// location failing fails them all, so a retry starts over.
+fn fetch_weather_once(
+    client: &Client,
+    provider: &dyn WeatherProvider,
+    base_url: &str,
+    coordinates: &[[f32; 2]],
+    units: Units,
+) -> Result<Vec<Fetched>, Box<dyn Error>> {
+    parse_bodies(provider, &fetch_bodies(client, provider, base_url, coordinates, units)?)
+}
+
+/// `weather_cache.json`: one forecast per location. Files from before
+/// locations existed hold a single one.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WeatherCacheFile {
+    Locations(Vec<Forecast>),
+    Single(Forecast),
+}
+
+/// The cached forecasts, one per location. A missing file has none.
+pub fn read_weather_cache(exe_path: &PathBuf) -> Result<Vec<Forecast>, Box<dyn Error>> {
+    let path = crate::tasks::get_data_dir(exe_path)?.join(WEATHER_CACHE_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let reader = BufReader::new(File::open(&path)?);
+    Ok(match serde_json::from_reader(reader)? {
+        WeatherCacheFile::Locations(forecasts) => forecasts,
+        WeatherCacheFile::Single(forecast) => vec![forecast],
+    })
+}
+
+pub fn save_weather_cache(forecasts: &[Forecast], exe_path: &PathBuf) -> Result<(), Box<dyn Error>> {
+    let data_dir = crate::tasks::get_data_dir(exe_path)?;
+    let bytes = serde_json::to_vec(forecasts)?;
+    crate::tasks::write_atomically(&data_dir, &data_dir.join(WEATHER_CACHE_FILE), &bytes)
+}
+
+/// How often the weather thread refetches, and how it retries a failed
+/// fetch: up to `attempts` tries, waiting `backoff_seconds` after the first
+/// failure and twice as long after each further one. Stored in
+/// `userconfig.toml` as `weather_refresh_minutes`, `weather_retry_attempts`
+/// and `weather_retry_backoff_seconds`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RefreshPolicy {
+    pub interval_minutes: u32,
+    pub attempts: u32,
+    pub backoff_seconds: u32,
+}
+
+impl Default for RefreshPolicy {
+    fn default() -> Self {
+        Self { interval_minutes: 10, attempts: 3, backoff_seconds: 1 }
+    }
+}
 
-        new_data[i % 24].push(item);
+/// Refresh intervals offered. Forecasts change hourly at most, and MET Norway
+/// asks clients not to poll much more often than this.
+pub const REFRESH_MINUTES: RangeInclusive<u32> = 5..=360;
+pub const RETRY_ATTEMPTS: RangeInclusive<u32> = 1..=10;
+pub const RETRY_BACKOFF_SECONDS: RangeInclusive<u32> = 1..=300;
+
+impl RefreshPolicy {
+    /// Each setting clamped to the range offered.
+    pub fn clamped(self) -> Self {
+        Self {
+            interval_minutes: self.interval_minutes.clamp(*REFRESH_MINUTES.start(), *REFRESH_MINUTES.end()),
+            attempts: self.attempts.clamp(*RETRY_ATTEMPTS.start(), *RETRY_ATTEMPTS.end()),
+            backoff_seconds: self.backoff_seconds.clamp(*RETRY_BACKOFF_SECONDS.start(), *RETRY_BACKOFF_SECONDS.end()),
+        }
+    }
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(u64::from(self.interval_minutes) * 60)
     }
+    /// The wait after `failures` failed attempts in a row (at least one);
+    /// never longer than the refresh interval.
+    pub fn backoff(&self, failures: u32) -> Duration {
+        let doublings = failures.saturating_sub(1).min(16);
+        Duration::from_secs(u64::from(self.backoff_seconds) << doublings).min(self.interval())
+    }
+}
 
-    Ok(new_data)
+/// What the weather thread is up to, for the forecast panel's status
+/// tooltip. Updated as it goes, alongside (not under) `version`.
+#[derive(Debug, Clone, Default)]
+pub struct WeatherStatus {
+    /// When the last successful fetch finished, and how long the provider
+    /// took to answer it (all locations, parsing included).
+    pub last_success: Option<DateTime<Local>>,
+    pub latency: Option<Duration>,
+    /// When the last fetch failed, and why. Kept after later successes.
+    pub last_error: Option<(DateTime<Local>, String)>,
+    /// Failed attempts in a row; reset by a success or by giving up until
+    /// the next refresh.
+    pub failures: u32,
+    /// The wait before the next retry, while retrying.
+    pub backoff: Option<Duration>,
+    /// When the next fetch or retry is due; `None` while one is running.
+    pub next_fetch: Option<DateTime<Local>>,
 }
 
-pub fn get_weather(initial_coordinates: [f32; 2], proxy: EventLoopProxy<()>) -> WeatherService {
-    const REFRESH_INTERVAL: Duration = Duration::from_secs(600);
-    const MAX_RETRIES: u32 = 3;
+impl WeatherStatus {
+    /// Whether the latest fetch failed.
+    pub fn failing(&self) -> bool {
+        match (&self.last_error, self.last_success) {
+            (Some((failed_at, _)), Some(succeeded_at)) => *failed_at > succeeded_at,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+/// What time it is, as far as the weather thread is concerned.
+pub type Clock = Arc<dyn Fn() -> DateTime<Local> + Send + Sync>;
+
+/// How `get_weather`'s thread reaches the outside world. The default talks
+/// to the real APIs on the system clock; tests swap in a local server and a
+/// fixed time.
+pub struct WeatherEnvironment {
+    /// Replaces the providers' `base_url`, e.g. `http://127.0.0.1:8080`.
+    pub base_url: Option<String>,
+    /// The HTTP client; `None` builds one with a 10 s timeout in the thread.
+    pub client: Option<Client>,
+    /// Stamps fetched forecasts and dates the alerts.
+    pub clock: Clock,
+    /// Called after every new forecast or alert evaluation, to wake the UI.
+    pub wake: Box<dyn Fn() + Send>,
+    /// Whether new forecasts are written to the cache; not in read-only mode.
+    pub cache: bool,
+}
 
-    let data = Arc::new(RwLock::new(vec![vec![]]));
+impl Default for WeatherEnvironment {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            client: None,
+            clock: Arc::new(Local::now),
+            wake: Box::new(|| {}),
+            cache: true,
+        }
+    }
+}
+
+fn update_status(status: &RwLock<WeatherStatus>, change: impl FnOnce(&mut WeatherStatus)) {
+    if let Ok(mut status) = status.write() {
+        change(&mut status);
+    }
+}
+
+enum WeatherCommand {
+    SetLocations(Vec<[f32; 2]>),
+    SetProvider(WeatherProviderKind),
+    SetUnits(Units),
+    SetAlertRules(AlertRules),
+    SetRefreshPolicy(RefreshPolicy),
+    Refresh,
+    Stop,
+}
+
+pub struct WeatherService {
+    /// The latest forecast for each location, from the network or (until the
+    /// first fetch succeeds) from the cache. Matched to a location with
+    /// `Forecast::is_for`; empty until either exists.
+    pub data: Arc<RwLock<Vec<Forecast>>>,
+    /// What `alerts::evaluate` made of `data`, redone with every new forecast
+    /// or rule change. Covered by the same `version`.
+    pub alerts: Arc<RwLock<Vec<Alert>>>,
+    pub version: Arc<AtomicU64>,
+    pub status: Arc<RwLock<WeatherStatus>>,
+    tx: Sender<WeatherCommand>,
+}
+
+impl WeatherService {
+    /// Forecast these locations from now on, refetching immediately.
+    pub fn set_locations(&self, locations: &[Location]) {
+        let coordinates = locations.iter().map(|location| location.coordinates).collect();
+        let _ = self.tx.send(WeatherCommand::SetLocations(coordinates));
+    }
+    /// Switch provider and refetch immediately.
+    pub fn set_provider(&self, kind: WeatherProviderKind) {
+        let _ = self.tx.send(WeatherCommand::SetProvider(kind));
+    }
+    /// Refetch in `units` where the provider supports them. The UI converts
+    /// whatever it has in the meantime, so this is not needed for display.
+    pub fn set_units(&self, units: Units) {
+        let _ = self.tx.send(WeatherCommand::SetUnits(units));
+    }
+    /// Re-evaluate the current forecast against new rules, without a refetch.
+    pub fn set_alert_rules(&self, rules: AlertRules) {
+        let _ = self.tx.send(WeatherCommand::SetAlertRules(rules));
+    }
+    /// Refetch on a new schedule. A waiting refresh is rescheduled from the
+    /// last fetch; a pending retry keeps its wait.
+    pub fn set_refresh_policy(&self, policy: RefreshPolicy) {
+        let _ = self.tx.send(WeatherCommand::SetRefreshPolicy(policy));
+    }
+    /// Refetch now, with a fresh round of attempts.
+    pub fn refresh(&self) {
+        let _ = self.tx.send(WeatherCommand::Refresh);
+    }
+}
+
+impl Drop for WeatherService {
+    fn drop(&mut self) {
+        let _ = self.tx.send(WeatherCommand::Stop);
+    }
+}
+
+pub fn get_weather(
+    initial_locations: Vec<[f32; 2]>,
+    initial_provider: WeatherProviderKind,
+    initial_units: Units,
+    initial_alert_rules: AlertRules,
+    initial_refresh: RefreshPolicy,
+    exe_path: PathBuf,
+    environment: WeatherEnvironment,
+) -> WeatherService {
+    let WeatherEnvironment { base_url, client, clock, wake, cache } = environment;
+
+    // Start from the cached forecasts, so the panel is filled before (or
+    // without) the first fetch. It is only a cache: if it is unreadable, start
+    // empty, and drop forecasts for places no longer listed.
+    let cached: Vec<Forecast> = match read_weather_cache(&exe_path) {
+        Ok(cached) => cached
+            .into_iter()
+            .filter(|forecast| initial_locations.iter().any(|pair| forecast.is_for(*pair)))
+            .collect(),
+        Err(e) => {
+            eprintln!("Ignoring unreadable weather cache: {}", e);
+            Vec::new()
+        }
+    };
+    let initial_version = u64::from(!cached.is_empty());
+    let initial_alerts = evaluate_alerts(&cached, &initial_alert_rules, clock().naive_local());
+
+    let data = Arc::new(RwLock::new(cached));
     let data_clone = Arc::clone(&data);
 
-    let version = Arc::new(AtomicU64::new(0));
+    let alerts = Arc::new(RwLock::new(initial_alerts));
+    let alerts_clone = Arc::clone(&alerts);
+
+    let version = Arc::new(AtomicU64::new(initial_version));
     let version_clone = Arc::clone(&version);
 
+    let status = Arc::new(RwLock::new(WeatherStatus::default()));
+    let status_clone = Arc::clone(&status);
+
     let (tx, rx): (Sender<WeatherCommand>, Receiver<WeatherCommand>) = channel();
 
     thread::spawn(move || {
-        let client = match Client::builder()
-            .timeout(Duration::from_secs(10))
-            .build()
-        {
+        let client = match client.map_or_else(|| Client::builder().timeout(Duration::from_secs(10)).build(), Ok) {
             Ok(c) => c,
             Err(e) => {
                 eprintln!("Failed to build HTTP client: {}", e);
@@ -121,63 +1178,152 @@ pub fn get_weather(initial_coordinates: [f32; 2], proxy: EventLoopProxy<()>) ->
             }
         };
 
-        let mut coordinates = initial_coordinates;
+        let mut locations = initial_locations;
+        let mut provider_kind = initial_provider;
+        let mut provider = provider_kind.provider();
+        let mut units = initial_units;
+        let mut alert_rules = initial_alert_rules;
+
+        // Evaluated here rather than in the UI so a new forecast and its
+        // alerts are published under one `version`.
+        let publish_alerts = |rules: &AlertRules| {
+            let evaluated = data_clone.read().map(|data| evaluate_alerts(&data, rules, clock().naive_local())).unwrap_or_default();
+            if let Ok(mut a) = alerts_clone.write() {
+                *a = evaluated;
+            }
+        };
 
-        loop {
-            let mut success = false;
+        let mut refresh = initial_refresh;
+        // Failed attempts in a row, counted against `refresh.attempts`.
+        let mut failures = 0;
 
-            for attempt in 0..MAX_RETRIES {
-                match fetch_weather_once(&client, coordinates) {
-                    Ok(new_data) => {
-                        if let Ok(mut w) = data_clone.write() {
-                            *w = new_data;
-                        }
-                        version_clone.fetch_add(1, Ordering::Relaxed);
+        loop {
+            update_status(&status_clone, |status| {
+                status.backoff = None;
+                status.next_fetch = None;
+            });
+
+            let started = Instant::now();
+            let base_url = base_url.as_deref().unwrap_or(provider.base_url());
+            let wait = match fetch_weather_once(&client, provider.as_ref(), base_url, &locations, units) {
+                Ok(fetched) => {
+                    let latency = started.elapsed();
+                    let fetched_at = clock();
+                    let forecasts: Vec<Forecast> = fetched
+                        .into_iter()
+                        .zip(&locations)
+                        .map(|((points, daily), coordinates)| Forecast {
+                            fetched_at,
+                            coordinates: *coordinates,
+                            provider: provider_kind,
+                            units: provider.native_units(units),
+                            points,
+                            daily,
+                        })
+                        .collect();
+                    if cache && let Err(e) = save_weather_cache(&forecasts, &exe_path) {
+                        eprintln!("Could not cache the forecast: {}", e);
+                    }
+                    if let Ok(mut w) = data_clone.write() {
+                        *w = forecasts;
+                    }
+                    publish_alerts(&alert_rules);
+                    version_clone.fetch_add(1, Ordering::Relaxed);
 
-                        let _ = proxy.send_event(());
+                    wake();
 
-                        #[cfg(debug_assertions)] {
-                            println!("Weather thread updating!");
-                        }
+                    #[cfg(debug_assertions)] {
+                        println!("Weather thread updating!");
+                    }
 
-                        success = true;
+                    failures = 0;
+                    update_status(&status_clone, |status| {
+                        status.last_success = Some(fetched_at);
+                        status.latency = Some(latency);
+                        status.failures = 0;
+                    });
+                    refresh.interval()
+                }
+                Err(e) => {
+                    failures += 1;
+                    eprintln!("Weather fetch failed (attempt {}): {}", failures, e);
+                    let backoff = (failures < refresh.attempts).then(|| refresh.backoff(failures));
+                    update_status(&status_clone, |status| {
+                        status.last_error = Some((clock(), e.to_string()));
+                        status.failures = failures;
+                        status.backoff = backoff;
+                    });
+                    backoff.unwrap_or_else(|| {
+                        eprintln!("Weather update failed after retries; keeping old data");
+                        failures = 0;
+                        refresh.interval()
+                    })
+                }
+            };
+
+            // Wait for the next fetch or retry; only rule and schedule changes
+            // are handled without a refetch.
+            let waiting_since = Instant::now();
+            let mut next_fetch = waiting_since + wait;
+            update_status(&status_clone, |status| status.next_fetch = Some(clock() + wait));
+            loop {
+                match rx.recv_timeout(next_fetch.saturating_duration_since(Instant::now())) {
+                    Ok(WeatherCommand::SetLocations(new_locations)) => {
+                        locations = new_locations;
+                        failures = 0;
                         break;
                     }
-                    Err(e) => {
-                        eprintln!(
-                            "Weather fetch failed (attempt {}): {}",
-                            attempt + 1,
-                            e
-                        );
-
-                        let backoff = Duration::from_secs(2u64.pow(attempt));
-                        thread::sleep(backoff);
+                    Ok(WeatherCommand::SetProvider(kind)) => {
+                        provider_kind = kind;
+                        provider = kind.provider();
+                        failures = 0;
+                        break;
                     }
+                    Ok(WeatherCommand::SetUnits(new_units)) => {
+                        units = new_units;
+                        failures = 0;
+                        break;
+                    }
+                    Ok(WeatherCommand::Refresh) => {
+                        failures = 0;
+                        break;
+                    }
+                    Ok(WeatherCommand::SetAlertRules(rules)) => {
+                        alert_rules = rules;
+                        publish_alerts(&alert_rules);
+                        version_clone.fetch_add(1, Ordering::Relaxed);
+                        wake();
+                    }
+                    Ok(WeatherCommand::SetRefreshPolicy(policy)) => {
+                        refresh = policy;
+                        if failures == 0 {
+                            next_fetch = waiting_since + refresh.interval();
+                            let remaining = next_fetch.saturating_duration_since(Instant::now());
+                            update_status(&status_clone, |status| status.next_fetch = Some(clock() + remaining));
+                        }
+                    }
+                    Ok(WeatherCommand::Stop) => return,
+                    Err(_) => break,
                 }
             }
-
-            if !success {
-                eprintln!("Weather update failed after retries; keeping old data");
-            }
-
-            match rx.recv_timeout(REFRESH_INTERVAL) {
-                Ok(WeatherCommand::SetCoordinates(c)) => {
-                    coordinates = c;
-                    continue;
-                }
-                Ok(WeatherCommand::Stop) => break,
-                Err(_) => {}
-            }
         }
     });
 
     WeatherService {
         data,
+        alerts,
         version,
+        status,
         tx,
     }
 }
 
+/// The alerts for every forecast at `now`, each tagged with its forecast's
+/// coordinates.
+fn evaluate_alerts(forecasts: &[Forecast], rules: &AlertRules, now: NaiveDateTime) -> Vec<Alert> {
+    forecasts.iter().flat_map(|forecast| alerts::evaluate(forecast, rules, now)).collect()
+}
+
 // SVG Weather Symbol File Naming Reference
 // ==========================================
 // The weather_svgs_2 directory contains SVG files using the following naming scheme:
@@ -230,128 +1376,72 @@ pub fn get_weather(initial_coordinates: [f32; 2], proxy: EventLoopProxy<()>) ->
 // 49 - Light snow (lightsnow) - no day/night variant
 // 50 - Heavy snow (heavysnow) - no day/night variant
 
-static CLEAR_DAY: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/01d.svg");
-static CLEAR_NIGHT: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/01n.svg");
-
-static FAIR_DAY: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/02d.svg");
-static FAIR_NIGHT: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/02n.svg");
-
-static PARTLY_CLOUDY_DAY: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/03d.svg");
-static PARTLY_CLOUDY_NIGHT: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/03n.svg");
-
-static CLOUDY: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/04.svg");
-
-static FOG: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/15.svg");
-
-// --- Rain ---
-static LIGHT_RAIN: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/46.svg");
-static RAIN: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/09.svg");
-static HEAVY_RAIN: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/10.svg");
-
-// --- Sleet ---
-static LIGHT_SLEET: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/47.svg");
-static HEAVY_SLEET: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/48.svg");
-
-// --- Snow ---
-static LIGHT_SNOW: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/49.svg");
-static SNOW: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/13.svg");
-static HEAVY_SNOW: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/50.svg");
-
-// --- Showers ---
-static LIGHT_RAIN_SHOWERS_DAY: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/40d.svg");
-static LIGHT_RAIN_SHOWERS_NIGHT: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/40n.svg");
-
-static RAIN_SHOWERS_DAY: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/05d.svg");
-static RAIN_SHOWERS_NIGHT: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/05n.svg");
-
-static HEAVY_RAIN_SHOWERS_DAY: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/41d.svg");
-static HEAVY_RAIN_SHOWERS_NIGHT: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/41n.svg");
-
-static LIGHT_SNOW_SHOWERS_DAY: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/44d.svg");
-static LIGHT_SNOW_SHOWERS_NIGHT: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/44n.svg");
-
-static HEAVY_SNOW_SHOWERS_DAY: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/45d.svg");
-static HEAVY_SNOW_SHOWERS_NIGHT: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/45n.svg");
-
-// --- Thunder ---
-static RAIN_THUNDER: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/22.svg");
-static HEAVY_RAIN_THUNDER: ImageSource<'static> =
-    egui::include_image!("../weather_svgs_2/11.svg");
-
-
-pub fn icon_for_wmo(
-    code: i32,
-    is_day: bool,
-) -> &'static egui::ImageSource<'static> {
-    match code {
-        // --- Clear & clouds ---
-        0 => if is_day { &CLEAR_DAY } else { &CLEAR_NIGHT },
-        1 => if is_day { &FAIR_DAY } else { &FAIR_NIGHT },
-        2 => if is_day { &PARTLY_CLOUDY_DAY } else { &PARTLY_CLOUDY_NIGHT },
-        3 => &CLOUDY,
-
-        // --- Fog ---
-        45 | 48 => &FOG,
-
-        // --- Drizzle ---
-        51 | 53 => &LIGHT_RAIN,
-        55 => &RAIN,
-        56 | 57 => &LIGHT_SLEET,
-
-        // --- Rain ---
-        61 => &LIGHT_RAIN,
-        63 => &RAIN,
-        65 => &HEAVY_RAIN,
-        66 => &LIGHT_SLEET,
-        67 => &HEAVY_SLEET,
-
-        // --- Snow ---
-        71 | 77 => &LIGHT_SNOW,
-        73 => &SNOW,
-        75 => &HEAVY_SNOW,
-
-        // --- Rain showers ---
-        80 => if is_day { &LIGHT_RAIN_SHOWERS_DAY } else { &LIGHT_RAIN_SHOWERS_NIGHT },
-        81 => if is_day { &RAIN_SHOWERS_DAY } else { &RAIN_SHOWERS_NIGHT },
-        82 => if is_day { &HEAVY_RAIN_SHOWERS_DAY } else { &HEAVY_RAIN_SHOWERS_NIGHT },
-
-        // --- Snow showers ---
-        85 => if is_day { &LIGHT_SNOW_SHOWERS_DAY } else { &LIGHT_SNOW_SHOWERS_NIGHT },
-        86 => if is_day { &HEAVY_SNOW_SHOWERS_DAY } else { &HEAVY_SNOW_SHOWERS_NIGHT },
+/// Embed one SVG from `weather_svgs_2/` as a `'static` image source.
+macro_rules! svg {
+    ($name:expr) => {{
+        static ICON: ImageSource<'static> = egui::include_image!(concat!("../weather_svgs_2/", $name, ".svg"));
+        &ICON
+    }};
+}
 
-        // --- Thunderstorms ---
-        95 => &RAIN_THUNDER,
-        96 | 99 => &HEAVY_RAIN_THUNDER,
+/// A symbol with day/night/polar-twilight variants (`{code}d/n/m.svg`).
+macro_rules! phased_svg {
+    ($code:literal, $phase:expr) => {
+        match $phase {
+            DayPhase::Day => svg!(concat!($code, "d")),
+            DayPhase::Night => svg!(concat!($code, "n")),
+            DayPhase::PolarTwilight => svg!(concat!($code, "m")),
+        }
+    };
+}
 
-        // --- Fallback ---
-        _ => &CLOUDY,
+/// The bundled icon for a yr symbol number. Unknown numbers fall back to cloudy.
+pub fn icon_for_symbol(
+    symbol: u8,
+    phase: DayPhase,
+) -> &'static egui::ImageSource<'static> {
+    match symbol {
+        1 => phased_svg!("01", phase),
+        2 => phased_svg!("02", phase),
+        3 => phased_svg!("03", phase),
+        5 => phased_svg!("05", phase),
+        6 => phased_svg!("06", phase),
+        7 => phased_svg!("07", phase),
+        8 => phased_svg!("08", phase),
+        9 => svg!("09"),
+        10 => svg!("10"),
+        11 => svg!("11"),
+        12 => svg!("12"),
+        13 => svg!("13"),
+        14 => svg!("14"),
+        15 => svg!("15"),
+        20 => phased_svg!("20", phase),
+        21 => phased_svg!("21", phase),
+        22 => svg!("22"),
+        23 => svg!("23"),
+        24 => phased_svg!("24", phase),
+        25 => phased_svg!("25", phase),
+        26 => phased_svg!("26", phase),
+        27 => phased_svg!("27", phase),
+        28 => phased_svg!("28", phase),
+        29 => phased_svg!("29", phase),
+        30 => svg!("30"),
+        31 => svg!("31"),
+        32 => svg!("32"),
+        33 => svg!("33"),
+        34 => svg!("34"),
+        40 => phased_svg!("40", phase),
+        41 => phased_svg!("41", phase),
+        42 => phased_svg!("42", phase),
+        43 => phased_svg!("43", phase),
+        44 => phased_svg!("44", phase),
+        45 => phased_svg!("45", phase),
+        46 => svg!("46"),
+        47 => svg!("47"),
+        48 => svg!("48"),
+        49 => svg!("49"),
+        50 => svg!("50"),
+        _ => svg!("04"),
     }
 }
 
@@ -692,3 +1782,485 @@ pub static CITIES: &[City] = &[
     City { name: "Uppsala", latitude: 59.8586, longitude: 17.6389 },
     City { name: "Västerås", latitude: 59.6099, longitude: 16.5448 },
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, NaiveTime};
+
+    const OPEN_METEO_FIXTURE: &str = include_str!("../tests/fixtures/weather/open_meteo.json");
+    const OPEN_METEO_DST_SPRING: &str = include_str!("../tests/fixtures/weather/open_meteo_dst_spring.json");
+    const OPEN_METEO_DST_AUTUMN: &str = include_str!("../tests/fixtures/weather/open_meteo_dst_autumn.json");
+    const MET_NORWAY_FIXTURE: &str = include_str!("../tests/fixtures/weather/met_norway_complete.json");
+
+    fn at(date: (i32, u32, u32), hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn point(time: NaiveDateTime, temp: f64, symbol: u8) -> WeatherData {
+        WeatherData { time, temp, symbol, phase: DayPhase::Day, details: HourDetails::default() }
+    }
+
+    /// Europe/Oslo for 2025 only: summer time from 30 March to 26 October,
+    /// switching at 01:00 UTC. Enough of a real DST zone for `day_slots`.
+    #[derive(Debug, Clone, Copy)]
+    struct Oslo2025;
+
+    impl Oslo2025 {
+        fn offset_at(utc: &NaiveDateTime) -> FixedOffset {
+            let summer = at((2025, 3, 30), 1) <= *utc && *utc < at((2025, 10, 26), 1);
+            FixedOffset::east_opt(if summer { 7200 } else { 3600 }).unwrap()
+        }
+    }
+
+    impl TimeZone for Oslo2025 {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            Oslo2025
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let fits = |seconds| {
+                let offset = FixedOffset::east_opt(seconds).unwrap();
+                (Oslo2025::offset_at(&(*local - offset)) == offset).then_some(offset)
+            };
+            match (fits(7200), fits(3600)) {
+                (Some(summer), Some(winter)) => LocalResult::Ambiguous(summer, winter),
+                (Some(offset), None) | (None, Some(offset)) => LocalResult::Single(offset),
+                (None, None) => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            Oslo2025::offset_at(&utc.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            Oslo2025::offset_at(utc)
+        }
+    }
+
+    #[test]
+    fn open_meteo_fixture_parses_into_day_slots() {
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+        let points = OpenMeteo::parse_in(OPEN_METEO_FIXTURE.as_bytes(), &tz).unwrap();
+        assert_eq!(points.len(), 72);
+        assert_eq!(points[0].time, at((2025, 6, 14), 0));
+        // WMO 0 at midnight: clear sky, night variant.
+        assert_eq!((points[0].symbol, points[0].phase), (1, DayPhase::Night));
+        // WMO 80 at noon: light rain showers, day variant.
+        assert_eq!((points[12].symbol, points[12].phase), (40, DayPhase::Day));
+
+        // Hour-long slots are the points themselves.
+        let day = at((2025, 6, 15), 0).date();
+        let hourly = day_slots(&points, day, 1, &tz);
+        assert_eq!(hourly.len(), 24);
+        assert_eq!(hourly[13], points[24 + 13]);
+
+        // Two-hour slots average the temperature and keep the worse symbol.
+        let slots = day_slots(&points, day, 2, &tz);
+        assert_eq!(slots.len(), 12);
+        assert_eq!(slots[6].time, at((2025, 6, 15), 12));
+        assert!((slots[6].temp - (points[36].temp + points[37].temp) / 2.0).abs() < 1e-9);
+        let worse = symbol_severity(points[36].symbol).max(symbol_severity(points[37].symbol));
+        assert_eq!(symbol_severity(slots[6].symbol), worse);
+        assert_eq!(day_slots(&points, day, 3, &tz).len(), 8);
+        assert_eq!(day_slots(&points, day, 6, &tz).len(), 4);
+    }
+
+    #[test]
+    fn open_meteo_rejects_series_shorter_than_the_times() {
+        let body = r#"{"hourly": {"time": [1749852000, 1749855600],
+            "temperature_2m": [12.5], "weather_code": [0, 0], "is_day": [0, 0]}}"#;
+        assert!(OpenMeteo.parse(body.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn open_meteo_details_keep_nulls_as_missing() {
+        let points = OpenMeteo.parse(OPEN_METEO_FIXTURE.as_bytes()).unwrap();
+        let noon = points[12].details;
+        assert_eq!(noon.wind_speed, Some(4.3));
+        assert_eq!(noon.wind_gusts, Some(8.2));
+        assert_eq!(noon.wind_direction, Some(322.0));
+        assert_eq!(noon.apparent_temp, Some(14.8));
+        assert_eq!((noon.precipitation, noon.precipitation_probability), (Some(0.6), Some(40.0)));
+        // The last hours have no UV yet.
+        assert_eq!(points[70].details.uv_index, None);
+        assert!(points[70].details.wind_speed.is_some());
+
+        // A response without the detail series still parses.
+        let bare = r#"{"hourly":{"time":[1749852000],"temperature_2m":[12.0],"weather_code":[3],"is_day":[0]}}"#;
+        assert_eq!(OpenMeteo.parse(bare.as_bytes()).unwrap()[0].details, HourDetails::default());
+    }
+
+    #[test]
+    fn met_norway_fixture_maps_symbol_codes_onto_the_svgs() {
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+        let points = MetNorway::parse_in(MET_NORWAY_FIXTURE.as_bytes(), &tz).unwrap();
+
+        // The final step carries no period summary and is dropped.
+        assert_eq!(points.len(), 64);
+        // 10:00Z is 12:00 at UTC+2; next_1_hours wins over the longer periods.
+        assert_eq!(points[0].time, at((2025, 6, 14), 12));
+        assert_eq!((points[0].symbol, points[0].phase, points[0].temp), (3, DayPhase::Day, 17.9));
+        assert!(points.iter().any(|p| (p.symbol, p.phase) == (1, DayPhase::PolarTwilight)));
+        assert!(points.iter().any(|p| (p.symbol, p.phase) == (2, DayPhase::Night)));
+        // The API's "lightssleet…" spelling.
+        assert!(points.iter().any(|p| p.symbol == 26));
+        // After 60 hours the steps are 6-hourly and take next_6_hours.
+        assert_eq!(points[60].time, at((2025, 6, 17), 2));
+        assert_eq!(points[61].time - points[60].time, chrono::Duration::hours(6));
+
+        let slots = day_slots(&points, at((2025, 6, 14), 0).date(), 1, &tz);
+        // Hours before the forecast starts repeat its first step…
+        assert_eq!(slots[0].temp, points[0].temp);
+        assert_eq!(slots[0].time, at((2025, 6, 14), 0));
+        // …and later hours line up with their own step.
+        assert_eq!(day_slots(&points, at((2025, 6, 15), 0).date(), 1, &tz)[15], points[27]);
+    }
+
+    #[test]
+    fn met_norway_details_come_from_the_instant_and_the_period() {
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+        let points = MetNorway::parse_in(MET_NORWAY_FIXTURE.as_bytes(), &tz).unwrap();
+
+        let first = points[0].details;
+        assert_eq!((first.wind_speed, first.wind_gusts, first.wind_direction), (Some(3.1), Some(5.7), Some(200.0)));
+        assert_eq!(first.uv_index, Some(6.1));
+        assert_eq!(first.precipitation_probability, Some(0.0));
+        assert_eq!(first.apparent_temp, Some(apparent_temperature(17.9, 82.0, 3.1)));
+
+        // 6-hourly amounts are spread per hour: 2.4 mm from 12:00Z.
+        let six_hourly = points.iter().find(|p| p.time == at((2025, 6, 17), 14)).unwrap();
+        assert_eq!(six_hourly.symbol, 46);
+        assert_eq!(six_hourly.details.precipitation, Some(0.4));
+        assert_eq!(six_hourly.details.precipitation_probability, Some(65.0));
+    }
+
+    #[test]
+    fn apparent_temperature_follows_steadman() {
+        // BoM table values: 25 °C, 50 %, still air feels ~26 °C; wind cools.
+        assert_eq!(apparent_temperature(25.0, 50.0, 0.0), 26.2);
+        assert!(apparent_temperature(10.0, 70.0, 8.0) < 10.0 - 4.0);
+    }
+
+    #[test]
+    fn aggregated_details_add_totals_and_keep_the_worst_hour() {
+        let first = HourDetails {
+            apparent_temp: Some(10.0),
+            precipitation: Some(0.4),
+            precipitation_probability: Some(30.0),
+            wind_speed: Some(4.0),
+            wind_gusts: Some(7.0),
+            wind_direction: Some(350.0),
+            uv_index: None,
+        };
+        let second = HourDetails {
+            apparent_temp: Some(8.0),
+            precipitation: Some(1.1),
+            precipitation_probability: Some(70.0),
+            wind_speed: Some(6.0),
+            wind_gusts: Some(11.0),
+            wind_direction: Some(10.0),
+            uv_index: Some(2.0),
+        };
+        // Hours without a value don't drag the means down.
+        let merged = HourDetails::aggregate(&[first, second, HourDetails::default()]);
+        assert_eq!(merged.apparent_temp, Some(9.0));
+        assert!((merged.precipitation.unwrap() - 1.5).abs() < 1e-9);
+        assert_eq!((merged.precipitation_probability, merged.wind_gusts), (Some(70.0), Some(11.0)));
+        assert_eq!((merged.wind_speed, merged.wind_direction, merged.uv_index), (Some(5.0), Some(350.0), Some(2.0)));
+        assert_eq!(HourDetails::aggregate(&[]), HourDetails::default());
+    }
+
+    #[test]
+    fn symbol_codes_parse_with_and_without_variants() {
+        assert_eq!(parse_symbol_code("rain"), Some((9, DayPhase::Day)));
+        assert_eq!(parse_symbol_code("clearsky_night"), Some((1, DayPhase::Night)));
+        assert_eq!(parse_symbol_code("lightrainshowers_polartwilight"), Some((40, DayPhase::PolarTwilight)));
+        assert_eq!(parse_symbol_code("lightssnowshowersandthunder_day"), Some((28, DayPhase::Day)));
+        assert_eq!(parse_symbol_code("rain_dusk"), None);
+        assert_eq!(parse_symbol_code("hail_day"), None);
+    }
+
+    #[test]
+    fn every_symbol_has_its_own_icon_and_severity() {
+        let icon_uri = |symbol, phase| match icon_for_symbol(symbol, phase) {
+            ImageSource::Bytes { uri, .. } => uri.to_string(),
+            other => panic!("unexpected image source {other:?}"),
+        };
+
+        let mut severities = std::collections::HashSet::new();
+        for &(name, symbol) in SYMBOL_NAMES {
+            let night = icon_uri(symbol, DayPhase::Night);
+            assert!(
+                night.ends_with(&format!("/{symbol:02}n.svg")) || night.ends_with(&format!("/{symbol:02}.svg")),
+                "{name} -> {night}"
+            );
+            severities.insert(symbol_severity(symbol));
+        }
+        // 41 distinct symbols, each with its own rank.
+        assert_eq!(severities.len(), 41);
+        assert!(symbol_severity(11) > symbol_severity(9));
+        assert!(symbol_severity(9) > symbol_severity(1));
+    }
+
+    #[test]
+    fn day_slots_fill_forward_and_short_forecasts_are_incomplete() {
+        let day = (2025, 3, 29);
+        let points = vec![
+            point(at(day, 0), 1.0, 1),
+            point(at(day, 5), 2.0, 9),
+            point(at((2025, 3, 31), 12), 3.0, 13),
+        ];
+        let slots = day_slots(&points, at(day, 0).date(), 1, &Utc);
+        assert_eq!((slots[4].temp, slots[4].time), (1.0, at(day, 4)));
+        assert_eq!(slots[5].symbol, 9);
+        assert_eq!(day_slots(&points, at((2025, 3, 30), 0).date(), 1, &Utc)[23].symbol, 9);
+        assert_eq!(day_slots(&points, at((2025, 3, 31), 0).date(), 1, &Utc)[12].symbol, 13);
+        // A slot straddling a step averages both hours and takes the worse symbol.
+        let straddling = &day_slots(&points, at(day, 0).date(), 2, &Utc)[2];
+        assert_eq!((straddling.time, straddling.temp, straddling.symbol), (at(day, 4), 1.5, 9));
+        assert!(day_slots(&[], at(day, 0).date(), 2, &Utc).is_empty());
+
+        // Complete means reaching the longest horizon the panel offers.
+        let mut week = points.clone();
+        week.push(point(at((2025, 4, 4), 0), 4.0, 4));
+        assert!(check_complete(&week).is_ok());
+        assert!(check_complete(&points).is_err());
+        assert!(check_complete(&[]).is_err());
+    }
+
+    #[test]
+    fn spring_forward_day_has_23_hours_and_no_0200_slot() {
+        let points = OpenMeteo::parse_in(OPEN_METEO_DST_SPRING.as_bytes(), &Oslo2025).unwrap();
+        assert_eq!(points.len(), 71);
+        let day = at((2025, 3, 30), 0).date();
+        assert_eq!(points.iter().filter(|p| p.time.date() == day).count(), 23);
+
+        let hourly = day_slots(&points, day, 1, &Oslo2025);
+        assert_eq!(hourly.len(), 23);
+        assert!(hourly.iter().all(|slot| slot.time != at((2025, 3, 30), 2)));
+        assert_eq!(hourly[2].time, at((2025, 3, 30), 3));
+
+        // The two-hour slot holding the gap is just its 03:00.
+        let slots = day_slots(&points, day, 2, &Oslo2025);
+        assert_eq!(slots.len(), 12);
+        let three = points.iter().find(|p| p.time == at((2025, 3, 30), 3)).unwrap();
+        assert_eq!((slots[1].temp, slots[1].details.precipitation), (three.temp, three.details.precipitation));
+
+        // The days either side are whole.
+        assert_eq!(day_slots(&points, at((2025, 3, 29), 0).date(), 1, &Oslo2025).len(), 24);
+        assert_eq!(day_slots(&points, at((2025, 3, 31), 0).date(), 1, &Oslo2025).len(), 24);
+    }
+
+    #[test]
+    fn fall_back_day_has_25_hours_and_counts_0200_twice() {
+        let points = OpenMeteo::parse_in(OPEN_METEO_DST_AUTUMN.as_bytes(), &Oslo2025).unwrap();
+        assert_eq!(points.len(), 73);
+        let day = at((2025, 10, 26), 0).date();
+        let two = at((2025, 10, 26), 2);
+        let repeated: Vec<&WeatherData> = points.iter().filter(|p| p.time == two).collect();
+        assert_eq!(repeated.len(), 2);
+
+        // 24 wall-clock hours, with 02:00 summarising both of its hours.
+        let hourly = day_slots(&points, day, 1, &Oslo2025);
+        assert_eq!(hourly.len(), 24);
+        assert!((hourly[2].temp - (repeated[0].temp + repeated[1].temp) / 2.0).abs() < 1e-9);
+        let rain = repeated[0].details.precipitation.unwrap() + repeated[1].details.precipitation.unwrap();
+        assert!((hourly[2].details.precipitation.unwrap() - rain).abs() < 1e-9);
+
+        // The 02:00–04:00 slot spans three real hours.
+        let slots = day_slots(&points, day, 2, &Oslo2025);
+        assert_eq!(slots.len(), 12);
+        let real: Vec<f64> = points.iter().filter(|p| p.time >= two && p.time < at((2025, 10, 26), 4)).map(|p| p.temp).collect();
+        assert_eq!(real.len(), 3);
+        assert!((slots[1].temp - real.iter().sum::<f64>() / 3.0).abs() < 1e-9);
+
+        // A horizon across the change still tiles every day.
+        let forecast = Forecast {
+            fetched_at: Local::now(),
+            coordinates: [59.91, 10.75],
+            provider: WeatherProviderKind::OpenMeteo,
+            units: Units::default(),
+            points,
+            daily: Vec::new(),
+        };
+        let days = forecast.days(at((2025, 10, 25), 0).date(), 3, 6, &Oslo2025).unwrap();
+        assert_eq!(days.iter().map(|(_, slots)| slots.len()).collect::<Vec<_>>(), vec![4, 4, 4]);
+        assert_eq!(days[1].0, day);
+        assert_eq!(days[1].1[1].time, at((2025, 10, 26), 6));
+    }
+
+    fn sample_forecast() -> Forecast {
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+        let points = MetNorway::parse_in(MET_NORWAY_FIXTURE.as_bytes(), &tz).unwrap();
+        Forecast {
+            fetched_at: Local::now(),
+            coordinates: [59.91, 10.75],
+            provider: WeatherProviderKind::MetNorway,
+            units: Units::default(),
+            daily: summarize_days(&points, &tz),
+            points,
+        }
+    }
+
+    #[test]
+    fn met_norway_days_are_summarised_from_the_hours() {
+        let forecast = sample_forecast();
+        let dates: Vec<NaiveDate> = forecast.daily.iter().map(|day| day.date).collect();
+        // The data ends at 02:00 on the 18th, so that day is left out.
+        assert_eq!(dates, (14..=17).map(|day| at((2025, 6, day), 0).date()).collect::<Vec<_>>());
+
+        // The first day starts at the first point (noon) and only has those hours.
+        let first_day: Vec<f64> = forecast.points.iter().filter(|p| p.time.date() == dates[0]).map(|p| p.temp).collect();
+        assert_eq!(first_day.len(), 12);
+        assert_eq!(forecast.daily[0].high, first_day.iter().copied().fold(f64::MIN, f64::max));
+        assert_eq!(forecast.daily[0].low, first_day.iter().copied().fold(f64::MAX, f64::min));
+
+        // The 6-hourly shower on the 17th adds up to its 2.4 mm again, but
+        // clear hours outnumber it.
+        let last = forecast.daily[3];
+        assert_eq!(last.precipitation, Some(2.4));
+        assert_eq!(last.symbol, 1);
+        assert_eq!(forecast.summaries(&Local), forecast.daily);
+    }
+
+    #[test]
+    fn open_meteo_daily_summaries_are_dated_at_the_location() {
+        let url = OpenMeteo.url(OpenMeteo.base_url(), [59.91, 10.75], Units::default());
+        assert!(url.starts_with("https://api.open-meteo.com/v1/forecast?latitude=59.91&longitude=10.75&"), "{url}");
+        assert!(url.contains("&daily=weather_code,temperature_2m_max,temperature_2m_min,precipitation_sum&"), "{url}");
+        assert!(url.ends_with(&format!("&forecast_days={MAX_DAILY_DAYS}")), "{url}");
+
+        let days = OpenMeteo.parse_daily(OPEN_METEO_FIXTURE.as_bytes(), &[]).unwrap();
+        // The fourth day is past the model's reach: all nulls, left out.
+        assert_eq!(days.len(), 3);
+        assert_eq!(
+            days[0],
+            DaySummary { date: at((2025, 6, 14), 0).date(), high: 18.7, low: 8.3, symbol: symbol_for_wmo(95), precipitation: Some(4.4) }
+        );
+        assert_eq!(days[2].date, at((2025, 6, 16), 0).date());
+    }
+
+    #[test]
+    fn open_meteo_batches_locations_into_one_request() {
+        let url = OpenMeteo.url_for_all("http://127.0.0.1:9", &[[59.91, 10.75], [52.52, 13.41]], Units::default());
+        assert!(url.starts_with("http://127.0.0.1:9/v1/forecast?latitude=59.91,52.52&longitude=10.75,13.41&"), "{url}");
+        assert_eq!(OpenMeteo.url("", [59.91, 10.75], Units::default()), OpenMeteo.url_for_all("", &[[59.91, 10.75]], Units::default()));
+
+        // Several locations come back as an array, one alone as the object.
+        let batch = format!("[{OPEN_METEO_FIXTURE},{OPEN_METEO_FIXTURE}]");
+        let bodies = OpenMeteo.split_batch(batch.as_bytes()).unwrap();
+        assert_eq!(bodies.len(), 2);
+        let expected = OpenMeteo.parse(OPEN_METEO_FIXTURE.as_bytes()).unwrap();
+        for body in &bodies {
+            assert_eq!(OpenMeteo.parse(body).unwrap(), expected);
+        }
+        assert_eq!(OpenMeteo.split_batch(OPEN_METEO_FIXTURE.as_bytes()).unwrap().len(), 1);
+        assert!(!MetNorway.batches());
+    }
+
+    #[test]
+    fn forecast_covers_until_its_last_hour_and_regrids_from_today() {
+        let forecast = sample_forecast();
+        // Last step: 18:00Z on the 17th, 20:00 at UTC+2, good for its 6 hours.
+        assert_eq!(forecast.covered_until(), Some(at((2025, 6, 18), 2)));
+        assert!(forecast.covers(at((2025, 6, 14), 3)));
+        assert!(forecast.covers(at((2025, 6, 18), 1)));
+        assert!(!forecast.covers(at((2025, 6, 18), 2)));
+
+        // Days later the panel starts at the new today; hours past the end
+        // repeat the last step (the UI blanks them using `covered_until`).
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+        let days = forecast.days(at((2025, 6, 16), 0).date(), 3, 1, &tz).unwrap();
+        assert_eq!(days[0].1[0].time, at((2025, 6, 16), 0));
+        assert_eq!(days[2].1[23].time, at((2025, 6, 18), 23));
+        assert_eq!(days[2].1[23].temp, forecast.points.last().unwrap().temp);
+        // An older "today" never starts the panel before the data does.
+        assert_eq!(forecast.days(at((2025, 6, 1), 0).date(), 1, 2, &tz).unwrap()[0].0, at((2025, 6, 14), 0).date());
+    }
+
+    #[test]
+    fn open_meteo_is_asked_for_our_units_and_met_is_converted() {
+        use crate::units::{PrecipitationUnit, TemperatureUnit, WindUnit};
+        let imperial = Units {
+            temperature: TemperatureUnit::Fahrenheit,
+            wind: WindUnit::MilesPerHour,
+            precipitation: PrecipitationUnit::Inches,
+        };
+
+        let url = OpenMeteo.url(OpenMeteo.base_url(), [59.91, 10.75], imperial);
+        assert!(url.contains("&temperature_unit=fahrenheit&wind_speed_unit=mph&precipitation_unit=inch&"), "{url}");
+        assert_eq!(OpenMeteo.native_units(imperial), imperial);
+        assert_eq!(MetNorway.native_units(imperial), Units::default());
+
+        let metric = sample_forecast();
+        let converted = metric.clone().in_units(imperial);
+        assert_eq!(converted.units, imperial);
+        let (before, after) = (&metric.points[0], &converted.points[0]);
+        assert!((after.temp - (before.temp * 1.8 + 32.0)).abs() < 1e-9);
+        assert!((converted.daily[0].low - (metric.daily[0].low * 1.8 + 32.0)).abs() < 1e-9);
+        let wind_before = before.details.wind_speed.unwrap();
+        assert!((after.details.wind_speed.unwrap() - wind_before * 2.236_936).abs() < 1e-5);
+        // Directions, chances and UV don't have units.
+        assert_eq!(after.details.wind_direction, before.details.wind_direction);
+        assert_eq!(after.details.uv_index, before.details.uv_index);
+        // Converting back is lossless up to float error, and same-unit is a no-op.
+        assert!((converted.in_units(Units::default()).points[0].temp - before.temp).abs() < 1e-9);
+        assert_eq!(metric.clone().in_units(Units::default()), metric);
+    }
+
+    #[test]
+    fn weather_cache_round_trips_through_the_data_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(tmp.path().join("taskdeck_data")).unwrap();
+        let exe = tmp.path().join("app.exe");
+
+        assert_eq!(read_weather_cache(&exe).unwrap(), Vec::new());
+
+        let forecast = sample_forecast();
+        let mut elsewhere = sample_forecast();
+        elsewhere.coordinates = [60.17, 24.94];
+        save_weather_cache(&[forecast.clone(), elsewhere.clone()], &exe).unwrap();
+        let cached = read_weather_cache(&exe).unwrap();
+        assert_eq!(cached, vec![forecast.clone(), elsewhere]);
+        assert!(cached[0].is_for([59.912, 10.748]));
+        assert!(!cached[0].is_for([60.17, 24.94]));
+
+        // A cache from before locations holds a single forecast.
+        let single = serde_json::to_vec(&forecast).unwrap();
+        std::fs::write(tmp.path().join("taskdeck_data").join(WEATHER_CACHE_FILE), single).unwrap();
+        assert_eq!(read_weather_cache(&exe).unwrap(), vec![forecast]);
+
+        std::fs::write(tmp.path().join("taskdeck_data").join(WEATHER_CACHE_FILE), "{").unwrap();
+        assert!(read_weather_cache(&exe).is_err());
+    }
+
+    #[test]
+    fn retry_backoff_doubles_up_to_the_refresh_interval() {
+        let policy = RefreshPolicy { interval_minutes: 5, attempts: 10, backoff_seconds: 30 };
+        let waits: Vec<u64> = (1..=6).map(|failures| policy.backoff(failures).as_secs()).collect();
+        assert_eq!(waits, vec![30, 60, 120, 240, 300, 300]);
+        assert_eq!(policy.backoff(u32::MAX), policy.interval());
+
+        let wild = RefreshPolicy { interval_minutes: 0, attempts: 0, backoff_seconds: 100_000 }.clamped();
+        assert_eq!(wild, RefreshPolicy { interval_minutes: 5, attempts: 1, backoff_seconds: 300 });
+    }
+
+    #[test]
+    fn status_is_failing_until_a_later_success() {
+        let earlier = Local::now() - chrono::Duration::minutes(5);
+        let error = |at| Some((at, "timed out".to_string()));
+
+        assert!(!WeatherStatus::default().failing());
+        assert!(WeatherStatus { last_error: error(earlier), ..Default::default() }.failing());
+        assert!(!WeatherStatus { last_error: error(earlier), last_success: Some(Local::now()), ..Default::default() }.failing());
+        assert!(WeatherStatus { last_error: error(Local::now()), last_success: Some(earlier), ..Default::default() }.failing());
+    }
+}
@@ -0,0 +1,293 @@
+//! The weather thread end to end, against a local stand-in for the provider:
+//! what it publishes on `data`/`version`, and how it retries when the server
+//! misbehaves. Retries wait whole seconds, so some of these take a few.
+
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local, TimeZone};
+use reqwest::blocking::Client;
+use task_deck::{
+    alerts::AlertRules,
+    units::Units,
+    weather::{self, OpenMeteo, RefreshPolicy, WeatherEnvironment, WeatherProvider, WeatherProviderKind, WeatherService, get_weather},
+};
+use tempfile::TempDir;
+
+const WEEK: &str = include_str!("fixtures/weather/open_meteo_week.json");
+const DST_WEEK: &str = include_str!("fixtures/weather/open_meteo_week_dst_spring.json");
+
+const OSLO: [f32; 2] = [59.91, 10.75];
+/// How long the client waits for a response before giving up on it.
+const CLIENT_TIMEOUT: Duration = Duration::from_millis(500);
+/// Generous, for slow CI machines; the waits under test are a few seconds.
+const DEADLINE: Duration = Duration::from_secs(20);
+
+/// One scripted response.
+struct Reply {
+    status: u16,
+    body: Vec<u8>,
+    delay: Duration,
+}
+
+impl Reply {
+    fn ok(body: &str) -> Self {
+        Self { status: 200, body: body.as_bytes().to_vec(), delay: Duration::ZERO }
+    }
+    fn error(status: u16) -> Self {
+        Self { status, body: br#"{"error":true,"reason":"Internal error"}"#.to_vec(), delay: Duration::ZERO }
+    }
+    fn delayed(self, delay: Duration) -> Self {
+        Self { delay, ..self }
+    }
+}
+
+/// A one-connection-per-request HTTP server answering with `replies` in
+/// order, then refusing connections. Records when each request came and
+/// what it asked for.
+struct Server {
+    base_url: String,
+    requests: Arc<Mutex<Vec<(Instant, String)>>>,
+}
+
+impl Server {
+    fn start(replies: Vec<Reply>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let log = Arc::clone(&requests);
+        thread::spawn(move || {
+            for reply in replies {
+                let Ok((stream, _)) = listener.accept() else { return };
+                let log = Arc::clone(&log);
+                // Answered on its own thread, so a slow reply doesn't hold
+                // up the retry that follows it.
+                thread::spawn(move || respond(stream, reply, &log));
+            }
+        });
+
+        Self { base_url, requests }
+    }
+
+    fn requests(&self) -> Vec<(Instant, String)> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn respond(mut stream: TcpStream, reply: Reply, log: &Mutex<Vec<(Instant, String)>>) {
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        match stream.read(&mut byte) {
+            Ok(1) => head.push(byte[0]),
+            _ => return,
+        }
+    }
+    let head = String::from_utf8_lossy(&head);
+    let path = head.split(' ').nth(1).unwrap_or_default().to_string();
+    log.lock().unwrap().push((Instant::now(), path));
+
+    thread::sleep(reply.delay);
+    let reason = if reply.status == 200 { "OK" } else { "Error" };
+    let response = format!(
+        "HTTP/1.1 {} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        reply.status,
+        reply.body.len()
+    );
+    // The client may have timed out and hung up; that is the point.
+    let _ = stream.write_all(response.as_bytes()).and_then(|_| stream.write_all(&reply.body));
+}
+
+/// The fixed time the service's clock reports.
+fn now() -> DateTime<Local> {
+    Local.with_ymd_and_hms(2025, 6, 2, 6, 0, 0).unwrap()
+}
+
+/// A weather service for Oslo talking to `server`, with an empty data dir
+/// (so no cached forecast) and a wake-up counter.
+struct Harness {
+    service: WeatherService,
+    exe: PathBuf,
+    wakes: Arc<AtomicUsize>,
+    started: Instant,
+    _dir: TempDir,
+}
+
+impl Harness {
+    fn start(server: &Server, attempts: u32) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("taskdeck_data")).unwrap();
+        let exe = dir.path().join("app.exe");
+
+        let wakes = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&wakes);
+        let environment = WeatherEnvironment {
+            base_url: Some(server.base_url.clone()),
+            client: Some(Client::builder().timeout(CLIENT_TIMEOUT).no_proxy().build().unwrap()),
+            clock: Arc::new(now),
+            wake: Box::new(move || {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }),
+            cache: true,
+        };
+        let refresh = RefreshPolicy { interval_minutes: 60, attempts, backoff_seconds: 1 };
+
+        let service = get_weather(
+            vec![OSLO],
+            WeatherProviderKind::OpenMeteo,
+            Units::default(),
+            AlertRules::default(),
+            refresh,
+            exe.clone(),
+            environment,
+        );
+        Self { service, exe, wakes, started: Instant::now(), _dir: dir }
+    }
+
+    fn version(&self) -> u64 {
+        self.service.version.load(Ordering::Relaxed)
+    }
+
+    /// Whether the thread is done with its last attempt and waiting: the
+    /// status is written after `version`, `next_fetch` last of all.
+    fn waiting(&self) -> bool {
+        self.service.status.read().unwrap().next_fetch.is_some()
+    }
+
+    /// Waits until `done` holds, failing the test at the deadline.
+    fn wait_until(&self, what: &str, done: impl Fn(&Self) -> bool) {
+        while !done(self) {
+            assert!(self.started.elapsed() < DEADLINE, "timed out waiting for {what}");
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
+
+fn parsed(body: &str) -> (Vec<weather::WeatherData>, Vec<weather::DaySummary>) {
+    let points = OpenMeteo.parse(body.as_bytes()).unwrap();
+    let daily = OpenMeteo.parse_daily(body.as_bytes(), &points).unwrap();
+    (points, daily)
+}
+
+#[test]
+fn a_normal_response_is_published_and_cached() {
+    let server = Server::start(vec![Reply::ok(WEEK)]);
+    let harness = Harness::start(&server, 3);
+    assert_eq!(harness.version(), 0);
+
+    harness.wait_until("the forecast", |h| h.version() == 1 && h.waiting());
+
+    let data = harness.service.data.read().unwrap().clone();
+    assert_eq!(data.len(), 1);
+    let (points, daily) = parsed(WEEK);
+    assert!(data[0].is_for(OSLO));
+    assert_eq!(data[0].fetched_at, now());
+    assert_eq!(data[0].provider, WeatherProviderKind::OpenMeteo);
+    assert_eq!((&data[0].points, &data[0].daily), (&points, &daily));
+    assert_eq!(weather::read_weather_cache(&harness.exe).unwrap(), data);
+    assert!(harness.wakes.load(Ordering::Relaxed) >= 1);
+
+    let status = harness.service.status.read().unwrap().clone();
+    assert_eq!(status.last_success, Some(now()));
+    assert!(status.last_error.is_none() && status.failures == 0);
+    assert_eq!(status.next_fetch, Some(now() + chrono::Duration::minutes(60)));
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].1.starts_with("/v1/forecast?latitude=59.91&longitude=10.75&"), "{}", requests[0].1);
+}
+
+#[test]
+fn refresh_now_fetches_again_and_bumps_the_version() {
+    let server = Server::start(vec![Reply::ok(WEEK), Reply::ok(DST_WEEK)]);
+    let harness = Harness::start(&server, 3);
+    harness.wait_until("the first forecast", |h| h.version() == 1);
+
+    harness.service.refresh();
+    harness.wait_until("the refreshed forecast", |h| h.version() == 2);
+    assert_eq!(harness.service.data.read().unwrap()[0].points, parsed(DST_WEEK).0);
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[test]
+fn a_truncated_response_is_retried_after_the_backoff() {
+    let server = Server::start(vec![Reply::ok(&WEEK[..WEEK.len() / 2]), Reply::ok(WEEK)]);
+    let harness = Harness::start(&server, 3);
+
+    harness.wait_until("the retried forecast", |h| h.version() == 1 && h.waiting());
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].0 - requests[0].0 >= Duration::from_secs(1));
+    assert_eq!(harness.service.data.read().unwrap()[0].points, parsed(WEEK).0);
+
+    let status = harness.service.status.read().unwrap().clone();
+    assert!(status.last_error.is_some(), "the failed attempt is still reported");
+    assert_eq!(status.last_success, Some(now()));
+    assert_eq!(status.failures, 0);
+}
+
+#[test]
+fn server_errors_back_off_doubling_then_give_up() {
+    let server = Server::start(vec![Reply::error(500), Reply::error(500), Reply::error(500)]);
+    let harness = Harness::start(&server, 3);
+
+    harness.wait_until("the last attempt", |h| h.service.status.read().unwrap().failures == 3 && h.waiting());
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[1].0 - requests[0].0 >= Duration::from_secs(1));
+    assert!(requests[2].0 - requests[1].0 >= Duration::from_secs(2));
+
+    // Given up until the next scheduled refresh, with nothing published.
+    let status = harness.service.status.read().unwrap().clone();
+    assert!(status.failing() && status.backoff.is_none());
+    let (_, error) = status.last_error.unwrap();
+    assert!(error.contains("500"), "{error}");
+    assert_eq!(status.next_fetch, Some(now() + chrono::Duration::minutes(60)));
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(harness.version(), 0);
+    assert!(harness.service.data.read().unwrap().is_empty());
+    assert_eq!(harness.wakes.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn a_slow_response_times_out_and_is_retried() {
+    let slow = Duration::from_secs(4);
+    let server = Server::start(vec![Reply::ok(WEEK).delayed(slow), Reply::ok(WEEK)]);
+    let harness = Harness::start(&server, 3);
+
+    harness.wait_until("the retried forecast", |h| h.version() == 1);
+
+    // Published from the retry, not by waiting out the slow reply.
+    assert!(harness.started.elapsed() < slow);
+    assert_eq!(server.requests().len(), 2);
+    assert!(harness.service.status.read().unwrap().last_error.is_some());
+}
+
+#[test]
+fn a_dst_week_keeps_every_hour() {
+    let server = Server::start(vec![Reply::ok(DST_WEEK)]);
+    let harness = Harness::start(&server, 3);
+
+    harness.wait_until("the forecast", |h| h.version() == 1);
+
+    let forecast = harness.service.data.read().unwrap()[0].clone();
+    // Eight days, one of them 23 hours long.
+    assert_eq!(forecast.points.len(), 8 * 24 - 1);
+    assert!(forecast.points.windows(2).all(|pair| pair[0].time < pair[1].time));
+    let dates: Vec<_> = forecast.daily.iter().map(|day| day.date).collect();
+    let first = chrono::NaiveDate::from_ymd_opt(2025, 3, 27).unwrap();
+    assert_eq!(dates, first.iter_days().take(8).collect::<Vec<_>>());
+    assert_eq!((forecast.points, forecast.daily), parsed(DST_WEEK));
+}